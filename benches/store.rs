@@ -1,18 +1,21 @@
 use async_std::task::{block_on, sleep};
 use async_trait::async_trait;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use libipld::block::create_raw_block;
+use libipld::cid::Cid;
 use libipld::error::Result;
-use libipld::store::{BufStore, MemStore, Store};
-use libipld::{Cid, DefaultHash as H};
-use std::path::Path;
+use libipld::mem::MemStore;
+use libipld::multihash::Code;
+use libipld::raw::RawCodec;
+use libipld::store::Store;
+use libipld::{Block, DefaultParams};
 use std::time::Duration;
 
-fn gen_block(n: usize) -> (Cid, Box<[u8]>) {
-    let data = n.to_ne_bytes().to_vec().into_boxed_slice();
-    create_raw_block::<H>(data).unwrap()
+fn gen_block(n: usize) -> Block<DefaultParams> {
+    let data = n.to_ne_bytes().to_vec();
+    Block::encode(RawCodec, Code::Blake3_256, &data).unwrap()
 }
 
+#[derive(Clone)]
 struct DelayStore<TStore: Store>(TStore);
 
 impl<TStore: Store> DelayStore<TStore> {
@@ -23,195 +26,165 @@ impl<TStore: Store> DelayStore<TStore> {
 
 #[async_trait]
 impl<TStore: Store> Store for DelayStore<TStore> {
-    async fn read(&self, cid: &Cid) -> Result<Option<Box<[u8]>>> {
-        sleep(Duration::from_millis(1)).await;
-        self.0.read(cid).await
+    type Params = TStore::Params;
+    type TempPin = TStore::TempPin;
+
+    fn create_temp_pin(&self) -> Result<Self::TempPin> {
+        self.0.create_temp_pin()
     }
 
-    async fn write(&self, cid: &Cid, data: Box<[u8]>) -> Result<()> {
-        sleep(Duration::from_millis(1)).await;
-        self.0.write(cid, data).await
+    fn temp_pin(&self, tmp: &Self::TempPin, cid: &Cid) -> Result<()> {
+        self.0.temp_pin(tmp, cid)
     }
 
-    async fn flush(&self) -> Result<()> {
-        sleep(Duration::from_millis(1)).await;
-        self.0.flush().await
+    fn contains(&self, cid: &Cid) -> Result<bool> {
+        self.0.contains(cid)
     }
 
-    async fn gc(&self) -> Result<()> {
-        sleep(Duration::from_millis(1)).await;
-        self.0.gc().await
+    fn get(&self, cid: &Cid) -> Result<Block<Self::Params>> {
+        self.0.get(cid)
     }
 
-    async fn pin(&self, cid: &Cid) -> Result<()> {
-        sleep(Duration::from_millis(1)).await;
-        self.0.pin(cid).await
+    fn insert(&self, block: &Block<Self::Params>) -> Result<()> {
+        self.0.insert(block)
     }
 
-    async fn unpin(&self, cid: &Cid) -> Result<()> {
-        sleep(Duration::from_millis(1)).await;
-        self.0.unpin(cid).await
+    fn alias<T: AsRef<[u8]> + Send + Sync>(&self, alias: T, cid: Option<&Cid>) -> Result<()> {
+        self.0.alias(alias, cid)
     }
 
-    async fn autopin(&self, cid: &Cid, auto_path: &Path) -> Result<()> {
-        sleep(Duration::from_millis(1)).await;
-        self.0.autopin(cid, auto_path).await
+    fn resolve<T: AsRef<[u8]> + Send + Sync>(&self, alias: T) -> Result<Option<Cid>> {
+        self.0.resolve(alias)
+    }
+
+    fn reverse_alias(&self, cid: &Cid) -> Result<Option<Vec<Vec<u8>>>> {
+        self.0.reverse_alias(cid)
     }
 
-    async fn write_link(&self, label: &str, cid: &Cid) -> Result<()> {
+    async fn flush(&self) -> Result<()> {
         sleep(Duration::from_millis(1)).await;
-        self.0.write_link(label, cid).await
+        self.0.flush().await
     }
 
-    async fn read_link(&self, label: &str) -> Result<Option<Cid>> {
+    async fn fetch(&self, cid: &Cid) -> Result<Block<Self::Params>> {
         sleep(Duration::from_millis(1)).await;
-        self.0.read_link(label).await
+        self.0.fetch(cid).await
     }
 
-    async fn remove_link(&self, label: &str) -> Result<()> {
+    async fn sync(&self, cid: &Cid) -> Result<()> {
         sleep(Duration::from_millis(1)).await;
-        self.0.remove_link(label).await
+        self.0.sync(cid).await
     }
 }
 
-type StoreSetup = Box<dyn Fn() -> Box<dyn Store>>;
-
-fn store_bench(c: &mut Criterion, stores: Vec<(&str, StoreSetup)>) {
-    let blocks = [gen_block(0)];
-
-    for (store_name, store_setup) in &stores {
-        c.bench_function(&format!("{} read:miss", store_name), |b| {
-            let store = store_setup();
-            let (cid, _) = &blocks[0];
-            b.iter(|| {
-                black_box(block_on(store.read(black_box(cid)))).unwrap();
-            });
+fn store_bench<S, F>(c: &mut Criterion, store_name: &str, store_setup: F)
+where
+    S: Store<Params = DefaultParams>,
+    F: Fn() -> S,
+{
+    let block = gen_block(0);
+
+    c.bench_function(&format!("{} get:miss", store_name), |b| {
+        let store = store_setup();
+        b.iter(|| {
+            black_box(store.get(black_box(block.cid()))).unwrap_err();
         });
-    }
+    });
 
-    for (store_name, store_setup) in &stores {
-        c.bench_function(&format!("{} read:after-write", store_name), |b| {
-            let store = store_setup();
-            let (cid, data) = &blocks[0];
-            block_on(store.write(cid, data.clone())).unwrap();
-            b.iter(|| {
-                black_box(block_on(store.read(black_box(cid))))
-                    .unwrap()
-                    .unwrap();
-            });
+    c.bench_function(&format!("{} get:after-insert", store_name), |b| {
+        let store = store_setup();
+        store.insert(&block).unwrap();
+        b.iter(|| {
+            black_box(store.get(black_box(block.cid()))).unwrap();
         });
-    }
-
-    for (store_name, store_setup) in &stores {
-        c.bench_function(&format!("{} read:after-flush", store_name), |b| {
-            let store = store_setup();
-            let (cid, data) = &blocks[0];
-            block_on(store.write(cid, data.clone())).unwrap();
-            block_on(store.flush()).unwrap();
-            b.iter(|| {
-                black_box(block_on(store.read(black_box(cid))))
-                    .unwrap()
-                    .unwrap();
-            });
+    });
+
+    c.bench_function(&format!("{} fetch:after-flush", store_name), |b| {
+        let store = store_setup();
+        store.insert(&block).unwrap();
+        block_on(store.flush()).unwrap();
+        b.iter(|| {
+            black_box(block_on(store.fetch(black_box(block.cid())))).unwrap();
         });
-    }
+    });
 
-    for (store_name, store_setup) in &stores {
-        c.bench_function(&format!("{} write:exists", store_name), |b| {
-            let store = store_setup();
-            let (cid, data) = &blocks[0];
-            block_on(store.write(cid, data.clone())).unwrap();
-            b.iter(|| {
-                black_box(block_on(
-                    store.write(black_box(cid), black_box(data.clone())),
-                ))
-                .unwrap();
-            });
+    c.bench_function(&format!("{} insert:exists", store_name), |b| {
+        let store = store_setup();
+        store.insert(&block).unwrap();
+        b.iter(|| {
+            black_box(store.insert(black_box(&block))).unwrap();
         });
-    }
-
-    for (store_name, store_setup) in &stores {
-        c.bench_function(&format!("{} write-flush:exists", store_name), |b| {
-            let store = store_setup();
-            let (cid, data) = &blocks[0];
-            block_on(store.write(cid, data.clone())).unwrap();
-            block_on(store.flush()).unwrap();
-            b.iter(|| {
-                black_box(block_on(async {
-                    store
-                        .write(black_box(cid), black_box(data.clone()))
-                        .await
-                        .unwrap();
-                    store.flush().await.unwrap();
-                }));
-            });
+    });
+
+    c.bench_function(&format!("{} insert-flush:exists", store_name), |b| {
+        let store = store_setup();
+        store.insert(&block).unwrap();
+        block_on(store.flush()).unwrap();
+        b.iter(|| {
+            black_box(block_on(async {
+                store.insert(black_box(&block))?;
+                store.flush().await
+            }))
+            .unwrap();
         });
-    }
-
-    for (store_name, store_setup) in &stores {
-        c.bench_function(&format!("{} pin:pinned", store_name), |b| {
-            let store = store_setup();
-            let (cid, _) = &blocks[0];
-            block_on(store.pin(cid)).unwrap();
-            b.iter(|| {
-                black_box(block_on(store.pin(cid))).unwrap();
-            });
+    });
+
+    c.bench_function(&format!("{} alias:aliased", store_name), |b| {
+        let store = store_setup();
+        store.insert(&block).unwrap();
+        store.alias(b"alias", Some(block.cid())).unwrap();
+        b.iter(|| {
+            black_box(store.alias(b"alias", Some(block.cid()))).unwrap();
         });
-    }
-
-    for (store_name, store_setup) in &stores {
-        c.bench_function(&format!("{} pin-flush:pinned", store_name), |b| {
-            let store = store_setup();
-            let (cid, _) = &blocks[0];
-            block_on(store.pin(cid)).unwrap();
-            block_on(store.flush()).unwrap();
-            b.iter(|| {
-                black_box(block_on(async {
-                    store.pin(cid).await.unwrap();
-                    store.flush().await.unwrap();
-                }));
-            });
+    });
+
+    c.bench_function(&format!("{} alias-flush:aliased", store_name), |b| {
+        let store = store_setup();
+        store.insert(&block).unwrap();
+        store.alias(b"alias", Some(block.cid())).unwrap();
+        block_on(store.flush()).unwrap();
+        b.iter(|| {
+            black_box(block_on(async {
+                store.alias(b"alias", Some(block.cid()))?;
+                store.flush().await
+            }))
+            .unwrap();
         });
-    }
+    });
 
-    for (store_name, store_setup) in &stores {
-        c.bench_function(&format!("{} unpin", store_name), |b| {
-            let store = store_setup();
-            let (cid, _) = &blocks[0];
-            b.iter(|| {
-                black_box(block_on(store.unpin(cid))).unwrap();
-            });
+    c.bench_function(&format!("{} unalias", store_name), |b| {
+        let store = store_setup();
+        b.iter(|| {
+            black_box(store.alias(b"alias", None)).unwrap();
         });
-    }
-
-    for (store_name, store_setup) in &stores {
-        c.bench_function(&format!("{} unpin-flush", store_name), |b| {
-            let store = store_setup();
-            let (cid, _) = &blocks[0];
-            b.iter(|| {
-                black_box(block_on(async {
-                    store.unpin(cid).await.unwrap();
-                    store.flush().await.unwrap();
-                }));
-            });
+    });
+
+    c.bench_function(&format!("{} unalias-flush", store_name), |b| {
+        let store = store_setup();
+        b.iter(|| {
+            black_box(block_on(async {
+                store.alias(b"alias", None)?;
+                store.flush().await
+            }))
+            .unwrap();
         });
-    }
-
-    // TODO write/read/remove link
+    });
+
+    c.bench_function(&format!("{} resolve", store_name), |b| {
+        let store = store_setup();
+        store.insert(&block).unwrap();
+        store.alias(b"alias", Some(block.cid())).unwrap();
+        b.iter(|| {
+            black_box(store.resolve(b"alias")).unwrap().unwrap();
+        });
+    });
 }
 
 fn bench_stores(c: &mut Criterion) {
-    let mem_store: StoreSetup = Box::new(|| Box::new(MemStore::default()));
-    let buf_store: StoreSetup = Box::new(|| Box::new(BufStore::new(MemStore::default(), 16, 16)));
-    let buf_delay_store: StoreSetup =
-        Box::new(|| Box::new(BufStore::new(DelayStore::new(MemStore::default()), 16, 16)));
-
-    let stores = vec![
-        ("mem", mem_store),
-        ("buf", buf_store),
-        ("delay", buf_delay_store),
-    ];
-    store_bench(c, stores);
+    store_bench(c, "mem", MemStore::<DefaultParams>::default);
+    store_bench(c, "delay", || {
+        DelayStore::new(MemStore::<DefaultParams>::default())
+    });
 }
 
 criterion_group! {
@@ -1,10 +1,17 @@
 //! `Ipld` codecs.
 use crate::cid::Cid;
 use crate::error::{Result, UnsupportedCodec};
+use crate::io::{Cursor, Read, Seek, Write};
 use crate::ipld::Ipld;
+#[cfg(feature = "std")]
+use crate::lookahead::LookaheadReader;
+use alloc::{format, string::String, vec::Vec};
 use core::convert::TryFrom;
-use std::io::{Cursor, Read, Seek, Write};
-use std::ops::Deref;
+
+#[cfg(feature = "async")]
+mod async_io;
+#[cfg(feature = "async")]
+pub use async_io::{encode_async, AsyncDecoder};
 
 /// Codec trait.
 pub trait Codec:
@@ -22,6 +29,29 @@ pub trait Codec:
         T::decode(*self, &mut Cursor::new(bytes))
     }
 
+    /// Decodes a decodable type from the start of `bytes`.
+    ///
+    /// Returns the value and the number of bytes it was decoded from.
+    fn decode_prefix<T: Decode<Self>>(&self, bytes: &[u8]) -> Result<(T, usize)> {
+        let mut r = Cursor::new(bytes);
+        let value = T::decode(*self, &mut r)?;
+        Ok((value, r.position() as usize))
+    }
+
+    /// Decodes a decodable type from a reader that doesn't implement `Seek`.
+    ///
+    /// Only the bytes of the decoded value are read from `r`. The decoder may seek back by a few
+    /// bytes to peek at the input, seeking back any further fails.
+    #[cfg(feature = "std")]
+    fn decode_from_reader<T: Decode<Self>, R: Read>(&self, r: R) -> Result<T> {
+        T::decode(*self, &mut LookaheadReader::new(r))
+    }
+
+    /// Decodes a type borrowing from `bytes`.
+    fn decode_ref<'a, T: DecodeRef<'a, Self>>(&self, bytes: &'a [u8]) -> Result<T> {
+        T::decode_ref(*self, &mut Cursor::new(bytes))
+    }
+
     /// Scrapes the references.
     fn references<T: References<Self>, E: Extend<Cid>>(
         &self,
@@ -44,9 +74,9 @@ pub trait Encode<C: Codec> {
     fn encode<W: Write>(&self, c: C, w: &mut W) -> Result<()>;
 }
 
-impl<C: Codec, T: Encode<C>> Encode<C> for &T {
+impl<C: Codec, T: Encode<C> + ?Sized> Encode<C> for &T {
     fn encode<W: Write>(&self, c: C, w: &mut W) -> Result<()> {
-        self.deref().encode(c, w)
+        (**self).encode(c, w)
     }
 }
 
@@ -62,6 +92,18 @@ pub trait Decode<C: Codec>: Sized {
     fn decode<R: Read + Seek>(c: C, r: &mut R) -> Result<Self>;
 }
 
+/// Decode trait for types borrowing from the data they are decoded from.
+///
+/// Strings and bytes can be borrowed from the input instead of being copied into a new
+/// allocation. Types that don't borrow implement it by forwarding to [`Decode`].
+pub trait DecodeRef<'a, C: Codec>: Sized {
+    /// Decode from a cursor over a byte slice.
+    ///
+    /// It takes a specific codec as parameter, so that the [`DecodeRef`] can be generic over an
+    /// enum that contains multiple codecs.
+    fn decode_ref(c: C, r: &mut Cursor<&'a [u8]>) -> Result<Self>;
+}
+
 /// References trait.
 ///
 /// This trait is generic over a codec, so that different codecs can be implemented for the same
@@ -80,7 +122,7 @@ pub trait References<C: Codec>: Sized {
 pub fn assert_roundtrip<C, T>(c: C, data: &T, ipld: &Ipld)
 where
     C: Codec,
-    T: Decode<C> + Encode<C> + std::fmt::Debug + PartialEq,
+    T: Decode<C> + Encode<C> + core::fmt::Debug + PartialEq,
     Ipld: Decode<C> + Encode<C>,
 {
     fn hex(bytes: &[u8]) -> String {
@@ -108,6 +150,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::Error;
     use crate::ipld::Ipld;
     use thiserror::Error;
 
@@ -138,7 +181,7 @@ mod tests {
         fn encode<W: Write>(&self, _: CodecImpl, w: &mut W) -> Result<()> {
             match self {
                 Self::Null => Ok(w.write_all(&[0])?),
-                _ => Err(NotNull.into()),
+                _ => Err(Error::other(NotNull)),
             }
         }
     }
@@ -150,7 +193,7 @@ mod tests {
             if buf[0] == 0 {
                 Ok(Ipld::Null)
             } else {
-                Err(NotNull.into())
+                Err(Error::other(NotNull))
             }
         }
     }
@@ -0,0 +1,172 @@
+//! Adapters for async readers and writers.
+use super::{Codec, Decode, Encode, References};
+use crate::cid::Cid;
+use crate::error::Result;
+use crate::io::{self, Cursor, Read, Seek, SeekFrom};
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use futures::FutureExt;
+
+/// Number of bytes read from the reader at a time.
+const CHUNK_SIZE: usize = 4096;
+
+/// Encodes `obj` and writes it to `w`.
+///
+/// Encoders are synchronous and can't wait for `w`, so the value is encoded into a buffer first.
+pub async fn encode_async<C, T, W>(c: C, obj: &T, w: &mut W) -> Result<()>
+where
+    C: Codec,
+    T: Encode<C> + ?Sized,
+    W: AsyncWrite + Unpin,
+{
+    let bytes = c.encode(obj)?;
+    w.write_all(&bytes).await?;
+    Ok(())
+}
+
+/// Reader over the buffered bytes that notes whether the decoder wanted more of them.
+struct Partial<'a> {
+    r: Cursor<&'a [u8]>,
+    exhausted: bool,
+}
+
+impl<'a> Partial<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            r: Cursor::new(bytes),
+            exhausted: false,
+        }
+    }
+
+    fn remaining(&self) -> u64 {
+        self.r.get_ref().len() as u64 - self.r.position().min(self.r.get_ref().len() as u64)
+    }
+}
+
+impl Read for Partial<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.len() as u64 > self.remaining() {
+            self.exhausted = true;
+        }
+        self.r.read(buf)
+    }
+}
+
+impl Seek for Partial<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = self.r.seek(pos)?;
+        if pos > self.r.get_ref().len() as u64 {
+            self.exhausted = true;
+        }
+        Ok(pos)
+    }
+}
+
+/// Decodes values from an async reader as their bytes arrive.
+///
+/// Whenever decoding tries to read past the bytes received so far, more bytes are read and
+/// decoding is retried from the start of the buffer, no matter which error the decoder
+/// reported. Each retry waits for the reader once and then takes whatever else is available
+/// without waiting, up to doubling the buffer. A value of `n` bytes that is available at once is
+/// decoded in `O(log n)` attempts, but a reader that is pending between `k` pieces of the value
+/// may cause up to `k` more attempts, so decoding takes up to `O(k * n)` time. A value that only ends
+/// at the end of the stream, like a top-level DAG-JSON number or a raw block, is decoded once the
+/// reader is exhausted.
+///
+/// Bytes following a decoded value stay buffered for decoding the next value.
+#[derive(Debug)]
+pub struct AsyncDecoder<C, R> {
+    codec: C,
+    reader: R,
+    buf: Vec<u8>,
+    eof: bool,
+}
+
+impl<C: Codec, R: AsyncRead + Unpin> AsyncDecoder<C, R> {
+    /// Creates a new `AsyncDecoder`.
+    pub fn new(codec: C, reader: R) -> Self {
+        Self {
+            codec,
+            reader,
+            buf: Vec::new(),
+            eof: false,
+        }
+    }
+
+    /// Returns the bytes read but not yet decoded.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Returns the reader.
+    ///
+    /// Bytes read but not yet decoded are lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Decodes the next value.
+    pub async fn decode<T: Decode<C>>(&mut self) -> Result<T> {
+        loop {
+            let mut r = Partial::new(&self.buf);
+            let res = T::decode(self.codec, &mut r);
+            if r.exhausted && !self.eof {
+                self.fill().await?;
+                continue;
+            }
+            let len = r.r.position() as usize;
+            let value = res?;
+            self.buf.drain(..len);
+            return Ok(value);
+        }
+    }
+
+    /// Scrapes the references of the next value.
+    pub async fn references<T: References<C>, E: Extend<Cid>>(
+        &mut self,
+        set: &mut E,
+    ) -> Result<()> {
+        loop {
+            // an incomplete value may already have yielded some references
+            let mut refs = Vec::new();
+            let mut r = Partial::new(&self.buf);
+            let res = T::references(self.codec, &mut r, &mut refs);
+            if r.exhausted && !self.eof {
+                self.fill().await?;
+                continue;
+            }
+            let len = r.r.position() as usize;
+            res?;
+            self.buf.drain(..len);
+            set.extend(refs);
+            return Ok(());
+        }
+    }
+
+    /// Reads more bytes.
+    ///
+    /// Waits for at least one byte and then reads whatever is available without waiting, until
+    /// the buffer doubled in size. It doesn't wait for the buffer to double, the value may be
+    /// complete before that and the reader may not send anything else until it is decoded.
+    async fn fill(&mut self) -> Result<()> {
+        let target = self.buf.len() * 2;
+        let mut chunk = [0; CHUNK_SIZE];
+        let n = self.reader.read(&mut chunk).await?;
+        if n == 0 {
+            self.eof = true;
+            return Ok(());
+        }
+        self.buf.extend_from_slice(&chunk[..n]);
+        while self.buf.len() < target {
+            match self.reader.read(&mut chunk).now_or_never() {
+                Some(Ok(0)) => {
+                    self.eof = true;
+                    break;
+                }
+                None => break,
+                Some(Ok(n)) => self.buf.extend_from_slice(&chunk[..n]),
+                Some(Err(err)) => return Err(err.into()),
+            }
+        }
+        Ok(())
+    }
+}
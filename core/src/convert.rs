@@ -1,7 +1,15 @@
 //! Conversion to and from ipld.
 use crate::cid::Cid;
+use crate::error::{InvalidIpld, TypeError, TypeErrorType};
 use crate::ipld::Ipld;
-use std::collections::BTreeMap;
+use alloc::{
+    borrow::ToOwned,
+    boxed::Box,
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::convert::TryFrom;
 
 macro_rules! derive_to_ipld_prim {
     ($enum:ident, $ty:ty, $fn:ident) => {
@@ -38,7 +46,6 @@ derive_to_ipld_prim!(Integer, i8, clone);
 derive_to_ipld_prim!(Integer, i16, clone);
 derive_to_ipld_prim!(Integer, i32, clone);
 derive_to_ipld_prim!(Integer, i64, clone);
-derive_to_ipld_prim!(Integer, i128, clone);
 derive_to_ipld_prim!(Integer, isize, clone);
 derive_to_ipld_prim!(Integer, u8, clone);
 derive_to_ipld_prim!(Integer, u16, clone);
@@ -47,6 +54,32 @@ derive_to_ipld_prim!(Integer, u64, clone);
 derive_to_ipld_prim!(Integer, usize, clone);
 derive_to_ipld_prim!(Float, f32, clone);
 derive_to_ipld_prim!(Float, f64, clone);
+
+/// Fails if the integer is outside of the data model range.
+impl TryFrom<i128> for Ipld {
+    type Error = InvalidIpld;
+
+    fn try_from(value: i128) -> Result<Self, Self::Error> {
+        if (Ipld::INTEGER_MIN..=Ipld::INTEGER_MAX).contains(&value) {
+            Ok(Ipld::Integer(value))
+        } else {
+            Err(InvalidIpld::IntegerOutOfRange {
+                path: Default::default(),
+                value,
+            })
+        }
+    }
+}
+
+/// Fails if the integer is outside of the data model range.
+impl TryFrom<u128> for Ipld {
+    type Error = InvalidIpld;
+
+    fn try_from(value: u128) -> Result<Self, Self::Error> {
+        Ipld::try_from(i128::try_from(value).unwrap_or(i128::MAX))
+    }
+}
+
 derive_to_ipld!(String, String, into);
 derive_to_ipld!(String, &str, to_string);
 derive_to_ipld!(Bytes, Box<[u8]>, into_vec);
@@ -58,3 +91,198 @@ derive_to_ipld!(StringMap, BTreeMap<String, Ipld>, to_owned);
 derive_to_ipld!(IntegerMap, BTreeMap<i64, Ipld>, to_owned);
 derive_to_ipld_generic!(Link, Cid, clone);
 derive_to_ipld_generic!(Link, &Cid, to_owned);
+
+macro_rules! derive_from_ipld {
+    ($enum:ident, $ty:ty, $($fn:ident),*) => {
+        impl TryFrom<Ipld> for $ty {
+            type Error = TypeError;
+
+            fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
+                match ipld {
+                    Ipld::$enum(t) => Ok(t$(.$fn())*),
+                    ipld => Err(TypeError::new(TypeErrorType::$enum, ipld)),
+                }
+            }
+        }
+    };
+}
+
+macro_rules! derive_from_ipld_int {
+    ($($ty:ty),*) => {
+        $(
+            impl TryFrom<Ipld> for $ty {
+                type Error = TypeError;
+
+                fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
+                    match ipld {
+                        Ipld::Integer(i) => Self::try_from(i)
+                            .map_err(|_| TypeError::new(TypeErrorType::Range(stringify!($ty)), ipld)),
+                        ipld => Err(TypeError::new(TypeErrorType::Integer, ipld)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+derive_from_ipld!(Bool, bool, into);
+derive_from_ipld!(Integer, i128, into);
+derive_from_ipld_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+derive_from_ipld!(Float, f64, into);
+derive_from_ipld!(String, String, into);
+derive_from_ipld!(Bytes, Box<[u8]>, into_boxed_slice);
+derive_from_ipld!(Link, Cid, into);
+
+impl TryFrom<Ipld> for f32 {
+    type Error = TypeError;
+
+    /// Fails unless the float converts to `f32` without loss of precision.
+    fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
+        match ipld {
+            Ipld::Float(f) if f as f32 as f64 == f || f.is_nan() => Ok(f as f32),
+            Ipld::Float(_) => Err(TypeError::new(TypeErrorType::Range("f32"), ipld)),
+            ipld => Err(TypeError::new(TypeErrorType::Float, ipld)),
+        }
+    }
+}
+
+/// Bytes convert like a list of integers, so `Vec<u8>` accepts both.
+impl<T: TryFrom<Ipld>> TryFrom<Ipld> for Vec<T>
+where
+    TypeError: From<T::Error>,
+{
+    type Error = TypeError;
+
+    fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
+        match ipld {
+            Ipld::List(list) => Ok(list
+                .into_iter()
+                .map(T::try_from)
+                .collect::<Result<_, _>>()?),
+            Ipld::Bytes(bytes) => Ok(bytes
+                .into_iter()
+                .map(|b| T::try_from(Ipld::from(b)))
+                .collect::<Result<_, _>>()?),
+            ipld => Err(TypeError::new(TypeErrorType::List, ipld)),
+        }
+    }
+}
+
+impl<T: TryFrom<Ipld>> TryFrom<Ipld> for BTreeMap<String, T>
+where
+    TypeError: From<T::Error>,
+{
+    type Error = TypeError;
+
+    fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
+        match ipld {
+            Ipld::StringMap(map) => Ok(map
+                .into_iter()
+                .map(|(k, v)| Ok((k, T::try_from(v)?)))
+                .collect::<Result<_, T::Error>>()?),
+            ipld => Err(TypeError::new(TypeErrorType::StringMap, ipld)),
+        }
+    }
+}
+
+macro_rules! derive_option_from_ipld {
+    ($($ty:ty),*) => {
+        $(
+            impl TryFrom<Ipld> for Option<$ty> {
+                type Error = TypeError;
+
+                fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
+                    match ipld {
+                        Ipld::Null => Ok(None),
+                        ipld => Ok(Some(<$ty>::try_from(ipld)?)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+derive_option_from_ipld!(bool, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, usize);
+derive_option_from_ipld!(f32, f64, String, Box<[u8]>, Cid);
+
+impl<T: TryFrom<Ipld>> TryFrom<Ipld> for Option<Vec<T>>
+where
+    TypeError: From<T::Error>,
+{
+    type Error = TypeError;
+
+    fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
+        match ipld {
+            Ipld::Null => Ok(None),
+            ipld => Ok(Some(Vec::try_from(ipld)?)),
+        }
+    }
+}
+
+impl<T: TryFrom<Ipld>> TryFrom<Ipld> for Option<BTreeMap<String, T>>
+where
+    TypeError: From<T::Error>,
+{
+    type Error = TypeError;
+
+    fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
+        match ipld {
+            Ipld::Null => Ok(None),
+            ipld => Ok(Some(BTreeMap::try_from(ipld)?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::multihash::{Code, MultihashDigest};
+    use alloc::vec;
+
+    #[test]
+    fn try_from_ipld() {
+        assert!(bool::try_from(Ipld::Bool(true)).unwrap());
+        assert_eq!(u8::try_from(Ipld::Integer(255)).unwrap(), 255);
+        let err = u8::try_from(Ipld::Integer(256)).unwrap_err();
+        assert!(matches!(err.expected, TypeErrorType::Range("u8")));
+        assert!(i64::try_from(Ipld::Float(1.0)).is_err());
+        assert_eq!(f32::try_from(Ipld::Float(0.5)).unwrap(), 0.5);
+        assert!(f32::try_from(Ipld::Float(0.1)).is_err());
+        assert_eq!(String::try_from(Ipld::from("a")).unwrap(), "a");
+        assert_eq!(Vec::<u8>::try_from(Ipld::Bytes(vec![1])).unwrap(), [1]);
+        let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"cid"));
+        assert_eq!(Cid::try_from(Ipld::Link(cid)).unwrap(), cid);
+    }
+
+    #[test]
+    fn integer_range() {
+        assert_eq!(
+            Ipld::try_from(Ipld::INTEGER_MIN).unwrap(),
+            Ipld::Integer(-(1 << 64))
+        );
+        assert_eq!(
+            Ipld::try_from(u64::MAX as u128).unwrap(),
+            Ipld::Integer(u64::MAX.into())
+        );
+        assert!(Ipld::try_from(Ipld::INTEGER_MIN - 1).is_err());
+        assert!(Ipld::try_from(Ipld::INTEGER_MAX + 1).is_err());
+        assert!(Ipld::try_from(u128::MAX).is_err());
+    }
+
+    #[test]
+    fn try_from_ipld_generic() {
+        let list = Ipld::List(vec![Ipld::Integer(1), Ipld::Null]);
+        assert_eq!(
+            Vec::<Option<u32>>::try_from(list.clone()).unwrap(),
+            [Some(1), None]
+        );
+        assert!(Vec::<u32>::try_from(list.clone()).is_err());
+        assert_eq!(Vec::<Ipld>::try_from(list.clone()).unwrap().len(), 2);
+        assert_eq!(Option::<Vec<u32>>::try_from(Ipld::Null).unwrap(), None);
+
+        let mut map = BTreeMap::new();
+        map.insert("a".to_string(), list);
+        let map = BTreeMap::<String, Vec<Option<i8>>>::try_from(Ipld::StringMap(map)).unwrap();
+        assert_eq!(map["a"], [Some(1), None]);
+    }
+}
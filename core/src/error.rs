@@ -1,9 +1,234 @@
 //! `Ipld` error definitions.
 use crate::cid::Cid;
 use crate::ipld::{Ipld, IpldIndex};
-pub use anyhow::{Error, Result};
+use crate::ipld_arc::IpldArc;
+use crate::path::Path;
+use alloc::{boxed::Box, string::String, vec::Vec};
 use thiserror::Error;
 
+pub mod cbor;
+
+pub use cbor::CborError;
+
+/// Result type of this crate.
+pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+/// Ipld error.
+///
+/// Codec specific errors are grouped in [`CborError`], [`JsonError`] and [`PbError`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// I/O error.
+    #[error(transparent)]
+    Io(#[from] crate::io::Error),
+    /// The block wasn't found.
+    #[error(transparent)]
+    BlockNotFound(#[from] BlockNotFound),
+    /// The block is too large.
+    #[error(transparent)]
+    BlockTooLarge(#[from] BlockTooLarge),
+    /// The codec is unsupported.
+    #[error(transparent)]
+    UnsupportedCodec(#[from] UnsupportedCodec),
+    /// The multihash is unsupported.
+    #[error(transparent)]
+    UnsupportedMultihash(#[from] UnsupportedMultihash),
+    /// The hash of the data doesn't match the CID.
+    #[error(transparent)]
+    InvalidMultihash(#[from] InvalidMultihash),
+    /// Type error.
+    #[error(transparent)]
+    TypeError(#[from] TypeError),
+    /// Patch error.
+    #[error(transparent)]
+    Patch(#[from] PatchError),
+    /// The ipld isn't valid in the data model.
+    #[error(transparent)]
+    InvalidIpld(#[from] InvalidIpld),
+    /// Serde error.
+    #[error(transparent)]
+    Serde(#[from] SerdeError),
+    /// Invalid CID.
+    #[error("{0}")]
+    Cid(crate::cid::Error),
+    /// Invalid multihash.
+    #[error("{0}")]
+    Multihash(crate::multihash::Error),
+    /// Invalid UTF-8.
+    #[error(transparent)]
+    Utf8(#[from] core::str::Utf8Error),
+    /// CBOR error.
+    #[error(transparent)]
+    Cbor(#[from] CborError),
+    /// JSON error.
+    #[error(transparent)]
+    Json(#[from] JsonError),
+    /// Protobuf error.
+    #[error(transparent)]
+    Pb(#[from] PbError),
+    /// Any other error, for example from a store implementation.
+    #[error(transparent)]
+    Other(Box<dyn core::error::Error + Send + Sync>),
+}
+
+impl Error {
+    /// Creates an error from any other error.
+    pub fn other<E: Into<Box<dyn core::error::Error + Send + Sync>>>(err: E) -> Self {
+        Self::Other(err.into())
+    }
+
+    /// Returns `true` if a block wasn't found.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Self::BlockNotFound(_))
+    }
+}
+
+impl From<alloc::string::FromUtf8Error> for Error {
+    fn from(err: alloc::string::FromUtf8Error) -> Self {
+        Self::Utf8(err.utf8_error())
+    }
+}
+
+// The cid and multihash errors only implement `std::error::Error` with their `std` features.
+impl From<crate::cid::Error> for Error {
+    fn from(err: crate::cid::Error) -> Self {
+        Self::Cid(err)
+    }
+}
+
+impl From<crate::multihash::Error> for Error {
+    fn from(err: crate::multihash::Error) -> Self {
+        Self::Multihash(err)
+    }
+}
+
+/// JSON error.
+#[derive(Clone, Debug, Error)]
+#[non_exhaustive]
+pub enum JsonError {
+    /// The input isn't valid JSON.
+    #[error("Invalid json at line {line} column {column}: {msg}")]
+    Syntax {
+        /// Message.
+        msg: String,
+        /// Line.
+        line: usize,
+        /// Column.
+        column: usize,
+    },
+    /// The input is valid JSON but not valid DAG-JSON.
+    #[error("Invalid dag-json at line {line} column {column}: {msg}")]
+    Data {
+        /// Message.
+        msg: String,
+        /// Line.
+        line: usize,
+        /// Column.
+        column: usize,
+    },
+    /// A link isn't a valid cid string.
+    #[error("Invalid cid at line {line} column {column}: {msg}")]
+    InvalidCid {
+        /// Message.
+        msg: String,
+        /// Line.
+        line: usize,
+        /// Column.
+        column: usize,
+    },
+    /// Bytes aren't valid base64.
+    #[error("Invalid bytes at line {line} column {column}: {msg}")]
+    InvalidBytes {
+        /// Message.
+        msg: String,
+        /// Line.
+        line: usize,
+        /// Column.
+        column: usize,
+    },
+    /// A map with the single key `/`, which is reserved for links and bytes, is neither.
+    #[error("Invalid use of the reserved key `/` at line {line} column {column}: {msg}")]
+    ReservedKey {
+        /// Message.
+        msg: String,
+        /// Line.
+        line: usize,
+        /// Column.
+        column: usize,
+    },
+    /// The input ended unexpectedly.
+    #[error("Unexpected end of json at line {line} column {column}.")]
+    Eof {
+        /// Line.
+        line: usize,
+        /// Column.
+        column: usize,
+    },
+}
+
+/// Protobuf error.
+#[derive(Clone, Debug, Error)]
+#[non_exhaustive]
+pub enum PbError {
+    /// The input isn't a valid DAG-PB node.
+    #[error("Invalid protobuf: {0}")]
+    Protobuf(String),
+}
+
+/// The ipld isn't valid in the IPLD data model.
+#[derive(Clone, Debug, Error)]
+#[non_exhaustive]
+pub enum InvalidIpld {
+    /// An integer is outside of `Ipld::INTEGER_MIN..=Ipld::INTEGER_MAX`.
+    #[error("Integer {value} at path `{path}` is out of range.")]
+    IntegerOutOfRange {
+        /// Path of the integer.
+        path: Path,
+        /// The integer.
+        value: i128,
+    },
+    /// A float is `NaN` or infinite.
+    #[error("Float {value} at path `{path}` is not finite.")]
+    NonFiniteFloat {
+        /// Path of the float.
+        path: Path,
+        /// The float.
+        value: f64,
+    },
+    /// A value isn't part of the data model, like integer map keys or tags.
+    #[error("{found:?} at path `{path}` is not part of the data model.")]
+    Unsupported {
+        /// Path of the value.
+        path: Path,
+        /// The kind of the value.
+        found: TypeErrorType,
+    },
+}
+
+/// Patch error.
+#[derive(Clone, Debug, Error)]
+#[non_exhaustive]
+pub enum PatchError {
+    /// A path of an operation doesn't resolve.
+    #[error(transparent)]
+    TypeError(#[from] TypeError),
+    /// A test operation failed.
+    #[error("Test of {0} failed.")]
+    TestFailed(Path),
+    /// A move operation would move a value into itself.
+    #[error("Can't move {from} into {path}.")]
+    MoveIntoSelf {
+        /// Path moved from.
+        from: Path,
+        /// Path moved to.
+        path: Path,
+    },
+    /// The ipld isn't a valid patch.
+    #[error("Invalid patch: {0}")]
+    Invalid(String),
+}
+
 /// Block exceeds 1MiB.
 #[derive(Clone, Copy, Debug, Error)]
 #[error("Block size {0} exceeds 1MiB.")]
@@ -25,10 +250,29 @@ pub struct UnsupportedMultihash(pub u64);
 pub struct InvalidMultihash(pub Vec<u8>);
 
 /// The block wasn't found. The supplied string is a CID.
-#[derive(Clone, Copy, Debug, Error)]
-#[error("Failed to retrieve block {0}.")]
+#[derive(Clone, Copy, Error)]
+#[cfg_attr(feature = "std", derive(Debug))]
+#[cfg_attr(feature = "std", error("Failed to retrieve block {0}."))]
+#[cfg_attr(
+    not(feature = "std"),
+    error("Failed to retrieve block {:?}.", crate::ipld::CidDebug(.0))
+)]
 pub struct BlockNotFound(pub Cid);
 
+#[cfg(not(feature = "std"))]
+impl core::fmt::Debug for BlockNotFound {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_tuple("BlockNotFound")
+            .field(&crate::ipld::CidDebug(&self.0))
+            .finish()
+    }
+}
+
+/// Serde (de)serialization error.
+#[derive(Clone, Debug, Error)]
+#[error("Serde error: {0}")]
+pub struct SerdeError(pub String);
+
 /// Type error.
 #[derive(Clone, Debug, Error)]
 #[error("Expected {expected:?} but found {found:?}")]
@@ -49,6 +293,12 @@ impl TypeError {
     }
 }
 
+impl From<core::convert::Infallible> for TypeError {
+    fn from(err: core::convert::Infallible) -> Self {
+        match err {}
+    }
+}
+
 /// Type error type.
 #[derive(Clone, Debug)]
 pub enum TypeErrorType {
@@ -78,6 +328,8 @@ pub enum TypeErrorType {
     Key(String),
     /// Index type.
     Index(usize),
+    /// A number in the range of the named type.
+    Range(&'static str),
 }
 
 impl From<Ipld> for TypeErrorType {
@@ -106,6 +358,26 @@ impl From<&Ipld> for TypeErrorType {
     }
 }
 
+impl From<&IpldArc> for TypeErrorType {
+    fn from(ipld: &IpldArc) -> Self {
+        match ipld {
+            IpldArc::Null => Self::Null,
+            IpldArc::Bool(_) => Self::Bool,
+            IpldArc::Integer(_) => Self::Integer,
+            IpldArc::Float(_) => Self::Float,
+            IpldArc::String(_) => Self::String,
+            IpldArc::Bytes(_) => Self::Bytes,
+            IpldArc::List(_) => Self::List,
+            IpldArc::StringMap(_) => Self::StringMap,
+            #[cfg(feature = "unleashed")]
+            IpldArc::IntegerMap(_) => Self::IntegerMap,
+            IpldArc::Link(_) => Self::Link,
+            #[cfg(feature = "unleashed")]
+            IpldArc::Tag(_, _) => Self::Tag,
+        }
+    }
+}
+
 impl From<IpldIndex<'_>> for TypeErrorType {
     fn from(index: IpldIndex<'_>) -> Self {
         match index {
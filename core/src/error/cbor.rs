@@ -0,0 +1,314 @@
+//! CBOR error types.
+use super::Error as IpldError;
+use alloc::string::String;
+use core::any::type_name;
+use thiserror::Error;
+
+/// Number larger than u64.
+#[derive(Debug, Error)]
+#[error("Number larger than {ty}.")]
+pub struct NumberOutOfRange {
+    /// Type.
+    pub ty: &'static str,
+}
+
+impl NumberOutOfRange {
+    /// Creates a new `NumberOutOfRange` error.
+    pub fn new<T>() -> Self {
+        Self {
+            ty: type_name::<T>(),
+        }
+    }
+}
+
+/// Length larger than usize or too small, for example zero length cid field.
+#[derive(Debug, Error)]
+#[error("Length out of range when decoding {ty}.")]
+pub struct LengthOutOfRange {
+    /// Type.
+    pub ty: &'static str,
+}
+
+impl LengthOutOfRange {
+    /// Creates a new `LengthOutOfRange` error.
+    pub fn new<T>() -> Self {
+        Self {
+            ty: type_name::<T>(),
+        }
+    }
+}
+
+/// Unexpected cbor code.
+#[derive(Debug, Error)]
+#[error("Unexpected cbor code `0x{code:x}` when decoding `{ty}`.")]
+pub struct UnexpectedCode {
+    /// Code.
+    pub code: u8,
+    /// Type.
+    pub ty: &'static str,
+}
+
+impl UnexpectedCode {
+    /// Creates a new `UnexpectedCode` error.
+    pub fn new<T>(code: u8) -> Self {
+        Self {
+            code,
+            ty: type_name::<T>(),
+        }
+    }
+}
+
+/// Unexpected key.
+#[derive(Debug, Error)]
+#[error("Unexpected key `{key}` when decoding `{ty}`.")]
+pub struct UnexpectedKey {
+    /// Key.
+    pub key: String,
+    /// Type.
+    pub ty: &'static str,
+}
+
+impl UnexpectedKey {
+    /// Creates a new `UnexpectedKey` error.
+    pub fn new<T>(key: String) -> Self {
+        Self {
+            key,
+            ty: type_name::<T>(),
+        }
+    }
+}
+
+/// Missing key.
+#[derive(Debug, Error)]
+#[error("Missing key `{key}` for decoding `{ty}`.")]
+pub struct MissingKey {
+    /// Key.
+    pub key: &'static str,
+    /// Type.
+    pub ty: &'static str,
+}
+
+impl MissingKey {
+    /// Creates a new `MissingKey` error.
+    pub fn new<T>(key: &'static str) -> Self {
+        Self {
+            key,
+            ty: type_name::<T>(),
+        }
+    }
+}
+
+/// Unknown cbor tag.
+#[derive(Debug, Error)]
+#[error("Unkown cbor tag `{0}`.")]
+pub struct UnknownTag(pub u8);
+
+/// Unexpected eof.
+#[derive(Debug, Error)]
+#[error("Unexpected end of file.")]
+pub struct UnexpectedEof;
+
+/// The byte before Cid was not multibase identity prefix.
+#[derive(Debug, Error)]
+#[error("Invalid Cid prefix: {0}")]
+pub struct InvalidCidPrefix(pub u8);
+
+/// Indefinite length items are not allowed in strict mode.
+#[derive(Debug, Error)]
+#[error("Indefinite length item when decoding `{ty}`.")]
+pub struct IndefiniteLength {
+    /// Type.
+    pub ty: &'static str,
+}
+
+impl IndefiniteLength {
+    /// Creates a new `IndefiniteLength` error.
+    pub fn new<T>() -> Self {
+        Self {
+            ty: type_name::<T>(),
+        }
+    }
+}
+
+/// Integer or length that is not encoded in its shortest form.
+#[derive(Debug, Error)]
+#[error("Number `{0}` is not minimally encoded.")]
+pub struct NonMinimalInteger(pub u64);
+
+/// Float that is not encoded as a 64-bit float.
+#[derive(Debug, Error)]
+#[error("Float with cbor code `0x{0:x}` is not a 64-bit float.")]
+pub struct NonCanonicalFloat(pub u8);
+
+/// `NaN` or infinity.
+#[derive(Debug, Error)]
+#[error("Float `{0}` is not finite.")]
+pub struct NonFiniteFloat(pub f64);
+
+/// The cbor `undefined` value.
+#[derive(Debug, Error)]
+#[error("Undefined is not allowed.")]
+pub struct Undefined;
+
+/// Map keys are not sorted length first.
+#[derive(Debug, Error)]
+#[error("Unsorted map keys when decoding `{ty}`.")]
+pub struct UnsortedMapKeys {
+    /// Type.
+    pub ty: &'static str,
+}
+
+impl UnsortedMapKeys {
+    /// Creates a new `UnsortedMapKeys` error.
+    pub fn new<T>() -> Self {
+        Self {
+            ty: type_name::<T>(),
+        }
+    }
+}
+
+/// Map contains the same key more than once.
+#[derive(Debug, Error)]
+#[error("Duplicate map key when decoding `{ty}`.")]
+pub struct DuplicateMapKey {
+    /// Type.
+    pub ty: &'static str,
+}
+
+impl DuplicateMapKey {
+    /// Creates a new `DuplicateMapKey` error.
+    pub fn new<T>() -> Self {
+        Self {
+            ty: type_name::<T>(),
+        }
+    }
+}
+
+/// A limit of the DAG-CBOR `DecodeLimits`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    /// `max_allocation`.
+    Allocation,
+    /// `max_depth`.
+    Depth,
+    /// `max_collection_len`.
+    CollectionLen,
+    /// `max_string_len`.
+    StringLen,
+}
+
+impl core::fmt::Display for Limit {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(match self {
+            Self::Allocation => "Allocation",
+            Self::Depth => "Depth",
+            Self::CollectionLen => "Collection length",
+            Self::StringLen => "String length",
+        })
+    }
+}
+
+/// A decode limit was exceeded.
+#[derive(Debug, Error)]
+#[error("{limit} limit exceeded when decoding `{ty}`.")]
+pub struct LimitExceeded {
+    /// Limit.
+    pub limit: Limit,
+    /// Type.
+    pub ty: &'static str,
+}
+
+impl LimitExceeded {
+    /// Creates a new `LimitExceeded` error.
+    pub fn new<T>(limit: Limit) -> Self {
+        Self {
+            limit,
+            ty: type_name::<T>(),
+        }
+    }
+}
+
+/// CBOR error.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum CborError {
+    /// Number larger than the decoded type.
+    #[error(transparent)]
+    NumberOutOfRange(#[from] NumberOutOfRange),
+    /// Length out of range.
+    #[error(transparent)]
+    LengthOutOfRange(#[from] LengthOutOfRange),
+    /// Unexpected cbor code.
+    #[error(transparent)]
+    UnexpectedCode(#[from] UnexpectedCode),
+    /// Unexpected key.
+    #[error(transparent)]
+    UnexpectedKey(#[from] UnexpectedKey),
+    /// Missing key.
+    #[error(transparent)]
+    MissingKey(#[from] MissingKey),
+    /// Unknown tag.
+    #[error(transparent)]
+    UnknownTag(#[from] UnknownTag),
+    /// Unexpected end of file.
+    #[error(transparent)]
+    UnexpectedEof(#[from] UnexpectedEof),
+    /// Invalid cid prefix.
+    #[error(transparent)]
+    InvalidCidPrefix(#[from] InvalidCidPrefix),
+    /// Indefinite length item in strict mode.
+    #[error(transparent)]
+    IndefiniteLength(#[from] IndefiniteLength),
+    /// Integer that isn't minimally encoded in strict mode.
+    #[error(transparent)]
+    NonMinimalInteger(#[from] NonMinimalInteger),
+    /// Float that isn't 64-bit in strict mode.
+    #[error(transparent)]
+    NonCanonicalFloat(#[from] NonCanonicalFloat),
+    /// `NaN` or infinity.
+    #[error(transparent)]
+    NonFiniteFloat(#[from] NonFiniteFloat),
+    /// `undefined` in strict mode.
+    #[error(transparent)]
+    Undefined(#[from] Undefined),
+    /// Unsorted map keys in strict mode.
+    #[error(transparent)]
+    UnsortedMapKeys(#[from] UnsortedMapKeys),
+    /// Duplicate map key in strict mode.
+    #[error(transparent)]
+    DuplicateMapKey(#[from] DuplicateMapKey),
+    /// Decode limit exceeded.
+    #[error(transparent)]
+    LimitExceeded(#[from] LimitExceeded),
+}
+
+macro_rules! impl_into_ipld_error {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for IpldError {
+                fn from(err: $ty) -> Self {
+                    Self::Cbor(err.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_into_ipld_error!(
+    NumberOutOfRange,
+    LengthOutOfRange,
+    UnexpectedCode,
+    UnexpectedKey,
+    MissingKey,
+    UnknownTag,
+    UnexpectedEof,
+    InvalidCidPrefix,
+    IndefiniteLength,
+    NonMinimalInteger,
+    NonCanonicalFloat,
+    NonFiniteFloat,
+    Undefined,
+    UnsortedMapKeys,
+    DuplicateMapKey,
+    LimitExceeded
+);
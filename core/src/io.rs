@@ -0,0 +1,11 @@
+//! Reader and writer traits used by codecs.
+//!
+//! With the `std` feature these are the `std::io` types. Without it a small subset with the same
+//! names and signatures is provided, so that codecs work with only `alloc`.
+#[cfg(feature = "std")]
+pub use std::io::{Cursor, Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+
+#[cfg(not(feature = "std"))]
+mod no_std;
+#[cfg(not(feature = "std"))]
+pub use no_std::*;
@@ -0,0 +1,265 @@
+//! Subset of `std::io` for `no_std` environments.
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+
+/// Result type of I/O operations.
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Kind of an I/O error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The data isn't valid.
+    InvalidData,
+    /// A parameter was incorrect.
+    InvalidInput,
+    /// The reader ended before the expected number of bytes was read.
+    UnexpectedEof,
+    /// The writer didn't accept any more bytes.
+    WriteZero,
+    /// Any other error.
+    Other,
+}
+
+impl ErrorKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::InvalidData => "invalid data",
+            Self::InvalidInput => "invalid input parameter",
+            Self::UnexpectedEof => "unexpected end of file",
+            Self::WriteZero => "write zero",
+            Self::Other => "other error",
+        }
+    }
+}
+
+/// I/O error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    msg: &'static str,
+}
+
+impl Error {
+    /// Creates a new error.
+    pub fn new(kind: ErrorKind, msg: &'static str) -> Self {
+        Self { kind, msg }
+    }
+
+    /// Creates a new error of kind [`ErrorKind::Other`].
+    pub fn other(msg: &'static str) -> Self {
+        Self::new(ErrorKind::Other, msg)
+    }
+
+    /// Returns the kind of the error.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Self::new(kind, kind.as_str())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.msg)
+    }
+}
+
+impl core::error::Error for Error {}
+
+/// Source of bytes.
+pub trait Read {
+    /// Reads some bytes into `buf` and returns how many were read.
+    ///
+    /// Returns `0` if the end of the reader was reached.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
+
+    /// Fills `buf` or fails with [`ErrorKind::UnexpectedEof`].
+    fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<()> {
+        while !buf.is_empty() {
+            match self.read(buf)? {
+                0 => return Err(ErrorKind::UnexpectedEof.into()),
+                n => buf = &mut buf[n..],
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads all bytes until the end of the reader and appends them to `buf`.
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize> {
+        let start = buf.len();
+        let mut chunk = [0; 512];
+        loop {
+            match self.read(&mut chunk)? {
+                0 => return Ok(buf.len() - start),
+                n => buf.extend_from_slice(&chunk[..n]),
+            }
+        }
+    }
+}
+
+/// Sink of bytes.
+pub trait Write {
+    /// Writes some bytes from `buf` and returns how many were written.
+    fn write(&mut self, buf: &[u8]) -> Result<usize>;
+
+    /// Flushes buffered bytes.
+    fn flush(&mut self) -> Result<()>;
+
+    /// Writes all of `buf` or fails with [`ErrorKind::WriteZero`].
+    fn write_all(&mut self, mut buf: &[u8]) -> Result<()> {
+        while !buf.is_empty() {
+            match self.write(buf)? {
+                0 => return Err(ErrorKind::WriteZero.into()),
+                n => buf = &buf[n..],
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Position to seek to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SeekFrom {
+    /// Offset from the start.
+    Start(u64),
+    /// Offset from the end.
+    End(i64),
+    /// Offset from the current position.
+    Current(i64),
+}
+
+/// Reader or writer with a position that can be moved.
+pub trait Seek {
+    /// Moves to `pos` and returns the new position from the start.
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64>;
+
+    /// Returns the current position from the start.
+    fn stream_position(&mut self) -> Result<u64> {
+        self.seek(SeekFrom::Current(0))
+    }
+}
+
+impl<R: Read + ?Sized> Read for &mut R {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        (**self).read(buf)
+    }
+}
+
+impl<W: Write + ?Sized> Write for &mut W {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        (**self).write(buf)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        (**self).flush()
+    }
+}
+
+impl<S: Seek + ?Sized> Seek for &mut S {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        (**self).seek(pos)
+    }
+}
+
+impl Read for &[u8] {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = self.len().min(buf.len());
+        let (head, tail) = self.split_at(n);
+        buf[..n].copy_from_slice(head);
+        *self = tail;
+        Ok(n)
+    }
+}
+
+impl Write for Vec<u8> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Adds a position to an in-memory buffer to make it readable and seekable.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cursor<T> {
+    inner: T,
+    pos: u64,
+}
+
+impl<T> Cursor<T> {
+    /// Creates a new cursor at position `0`.
+    pub const fn new(inner: T) -> Self {
+        Self { inner, pos: 0 }
+    }
+
+    /// Returns the buffer.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Returns a reference to the buffer.
+    pub const fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the buffer.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Returns the position.
+    pub const fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Sets the position.
+    pub fn set_position(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+}
+
+impl<T: AsRef<[u8]>> Read for Cursor<T> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let data = self.inner.as_ref();
+        let start = usize::try_from(self.pos)
+            .unwrap_or(usize::MAX)
+            .min(data.len());
+        let n = (&data[start..]).read(buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<T: AsRef<[u8]>> Seek for Cursor<T> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(pos) => {
+                self.pos = pos;
+                return Ok(pos);
+            }
+            SeekFrom::End(offset) => (self.inner.as_ref().len() as u64, offset),
+            SeekFrom::Current(offset) => (self.pos, offset),
+        };
+        let pos = if offset >= 0 {
+            base.checked_add(offset as u64)
+        } else {
+            base.checked_sub(offset.unsigned_abs())
+        };
+        self.pos = pos.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.pos)
+    }
+}
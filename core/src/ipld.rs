@@ -1,10 +1,31 @@
 //! Ipld representation.
 use crate::cid::Cid;
-use crate::error::TypeError;
-use std::collections::BTreeMap;
+use crate::error::{InvalidIpld, TypeError, TypeErrorType};
+use crate::path::Path;
+use crate::walk::{self, Control, Visitor, VisitorMut, Walk};
+use alloc::{
+    borrow::ToOwned,
+    boxed::Box,
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::hash::{Hash, Hasher};
 
 /// Ipld
-#[derive(Clone, PartialEq)]
+///
+/// Integers of the data model range from [`Ipld::INTEGER_MIN`] to [`Ipld::INTEGER_MAX`], which
+/// is what DAG-CBOR can encode. Use `Ipld::try_from` to construct integers from an `i128` and
+/// [`Ipld::validate`] to check values constructed otherwise.
+///
+/// `Ipld` is totally ordered and hashable, so it can be used in sets and as a map key. Floats
+/// are compared by their bits, so `NaN` equals itself and `0.0` doesn't equal `-0.0`. Values of
+/// different kinds are ordered by their position in this enum and string maps are ordered by
+/// their entries in DAG-CBOR key order, shorter keys first.
+#[derive(Clone)]
 pub enum Ipld {
     /// Represents the absence of a value or the value undefined.
     Null,
@@ -32,8 +53,8 @@ pub enum Ipld {
     Tag(u64, Box<Ipld>),
 }
 
-impl std::fmt::Debug for Ipld {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl core::fmt::Debug for Ipld {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         use Ipld::*;
         match self {
             Null => write!(f, "null"),
@@ -46,13 +67,146 @@ impl std::fmt::Debug for Ipld {
             StringMap(m) => write!(f, "{:?}", m),
             #[cfg(feature = "unleashed")]
             IntegerMap(m) => write!(f, "{:?}", m),
+            #[cfg(feature = "std")]
             Link(cid) => write!(f, "{}", cid),
+            #[cfg(not(feature = "std"))]
+            Link(cid) => write!(f, "{:?}", CidDebug(cid)),
             #[cfg(feature = "unleashed")]
             Tag(tag, ipld) => write!(f, "({}, {:?})", tag, ipld),
         }
     }
 }
 
+impl Ipld {
+    /// Returns the position of the kind of `self` in the enum.
+    fn rank(&self) -> u8 {
+        match self {
+            Ipld::Null => 0,
+            Ipld::Bool(_) => 1,
+            Ipld::Integer(_) => 2,
+            Ipld::Float(_) => 3,
+            Ipld::String(_) => 4,
+            Ipld::Bytes(_) => 5,
+            Ipld::List(_) => 6,
+            Ipld::StringMap(_) => 7,
+            #[cfg(feature = "unleashed")]
+            Ipld::IntegerMap(_) => 8,
+            Ipld::Link(_) => 9,
+            #[cfg(feature = "unleashed")]
+            Ipld::Tag(_, _) => 10,
+        }
+    }
+}
+
+impl PartialEq for Ipld {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Ipld::Null, Ipld::Null) => true,
+            (Ipld::Bool(a), Ipld::Bool(b)) => a == b,
+            (Ipld::Integer(a), Ipld::Integer(b)) => a == b,
+            (Ipld::Float(a), Ipld::Float(b)) => a.to_bits() == b.to_bits(),
+            (Ipld::String(a), Ipld::String(b)) => a == b,
+            (Ipld::Bytes(a), Ipld::Bytes(b)) => a == b,
+            (Ipld::List(a), Ipld::List(b)) => a == b,
+            (Ipld::StringMap(a), Ipld::StringMap(b)) => a == b,
+            #[cfg(feature = "unleashed")]
+            (Ipld::IntegerMap(a), Ipld::IntegerMap(b)) => a == b,
+            (Ipld::Link(a), Ipld::Link(b)) => a == b,
+            #[cfg(feature = "unleashed")]
+            (Ipld::Tag(a, x), Ipld::Tag(b, y)) => a == b && x == y,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Ipld {}
+
+impl PartialOrd for Ipld {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Iterates the entries of `map` with keys ordered length first, as in canonical DAG-CBOR.
+///
+/// Entries are visited lazily, one key length at a time, so comparing maps stops at the first
+/// difference without collecting their entries.
+pub(crate) fn length_first<K: AsRef<str> + Ord, V>(
+    map: &BTreeMap<K, V>,
+) -> impl Iterator<Item = ((usize, &str), &V)> {
+    let key_len = |k: &K| k.as_ref().len();
+    let mut len = map.keys().map(key_len).min();
+    core::iter::from_fn(move || {
+        let current = len?;
+        len = map.keys().map(key_len).filter(|&n| n > current).min();
+        Some(current)
+    })
+    .flat_map(move |len| {
+        map.iter()
+            .filter(move |(k, _)| k.as_ref().len() == len)
+            .map(move |(k, v)| ((len, k.as_ref()), v))
+    })
+}
+
+impl Ord for Ipld {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Ipld::Bool(a), Ipld::Bool(b)) => a.cmp(b),
+            (Ipld::Integer(a), Ipld::Integer(b)) => a.cmp(b),
+            (Ipld::Float(a), Ipld::Float(b)) => a.total_cmp(b),
+            (Ipld::String(a), Ipld::String(b)) => a.cmp(b),
+            (Ipld::Bytes(a), Ipld::Bytes(b)) => a.cmp(b),
+            (Ipld::List(a), Ipld::List(b)) => a.cmp(b),
+            (Ipld::StringMap(a), Ipld::StringMap(b)) => length_first(a).cmp(length_first(b)),
+            #[cfg(feature = "unleashed")]
+            (Ipld::IntegerMap(a), Ipld::IntegerMap(b)) => a.cmp(b),
+            (Ipld::Link(a), Ipld::Link(b)) => a.cmp(b),
+            #[cfg(feature = "unleashed")]
+            (Ipld::Tag(a, x), Ipld::Tag(b, y)) => a.cmp(b).then_with(|| x.cmp(y)),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl Hash for Ipld {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            Ipld::Null => {}
+            Ipld::Bool(b) => b.hash(state),
+            Ipld::Integer(i) => i.hash(state),
+            Ipld::Float(f) => f.to_bits().hash(state),
+            Ipld::String(s) => s.hash(state),
+            Ipld::Bytes(b) => b.hash(state),
+            Ipld::List(l) => l.hash(state),
+            Ipld::StringMap(m) => m.hash(state),
+            #[cfg(feature = "unleashed")]
+            Ipld::IntegerMap(m) => m.hash(state),
+            Ipld::Link(cid) => Hash::hash(cid, state),
+            #[cfg(feature = "unleashed")]
+            Ipld::Tag(tag, ipld) => {
+                tag.hash(state);
+                ipld.hash(state);
+            }
+        }
+    }
+}
+
+/// Formats a cid without std, where it doesn't implement `Debug`.
+#[cfg(not(feature = "std"))]
+pub(crate) struct CidDebug<'a>(pub &'a Cid);
+
+#[cfg(not(feature = "std"))]
+impl core::fmt::Debug for CidDebug<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("Cid")
+            .field("version", &self.0.version())
+            .field("codec", &self.0.codec())
+            .field("hash", self.0.hash())
+            .finish()
+    }
+}
+
 /// An index into ipld
 pub enum IpldIndex<'a> {
     /// An index into an ipld list.
@@ -82,6 +236,125 @@ impl<'a> From<&'a str> for IpldIndex<'a> {
 }
 
 impl Ipld {
+    /// Smallest integer of the data model, `-2^64`.
+    pub const INTEGER_MIN: i128 = -(1 << 64);
+
+    /// Largest integer of the data model, `2^64 - 1`.
+    pub const INTEGER_MAX: i128 = u64::MAX as i128;
+
+    /// Checks that `self` only contains values of the data model.
+    ///
+    /// Integers must be in the data model range, floats must be finite and maps must have
+    /// string keys. Tags aren't part of the data model either.
+    pub fn validate(&self) -> Result<(), InvalidIpld> {
+        for (path, ipld) in self.walk() {
+            match ipld {
+                Ipld::Integer(value) if Ipld::try_from(*value).is_err() => {
+                    return Err(InvalidIpld::IntegerOutOfRange {
+                        path,
+                        value: *value,
+                    })
+                }
+                Ipld::Float(value) if !value.is_finite() => {
+                    return Err(InvalidIpld::NonFiniteFloat {
+                        path,
+                        value: *value,
+                    })
+                }
+                #[cfg(feature = "unleashed")]
+                Ipld::IntegerMap(_) | Ipld::Tag(_, _) => {
+                    return Err(InvalidIpld::Unsupported {
+                        path,
+                        found: ipld.into(),
+                    })
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the ipld is null.
+    pub fn is_null(&self) -> bool {
+        matches!(self, Ipld::Null)
+    }
+
+    /// Returns the boolean.
+    pub fn as_bool(&self) -> Result<bool, TypeError> {
+        match self {
+            Ipld::Bool(b) => Ok(*b),
+            _ => Err(TypeError::new(TypeErrorType::Bool, self)),
+        }
+    }
+
+    /// Returns the integer.
+    pub fn as_integer(&self) -> Result<i128, TypeError> {
+        match self {
+            Ipld::Integer(i) => Ok(*i),
+            _ => Err(TypeError::new(TypeErrorType::Integer, self)),
+        }
+    }
+
+    /// Returns the integer if it fits into an `i64`.
+    pub fn as_i64(&self) -> Result<i64, TypeError> {
+        i64::try_from(self.as_integer()?)
+            .map_err(|_| TypeError::new(TypeErrorType::Range("i64"), self))
+    }
+
+    /// Returns the integer if it fits into an `u64`.
+    pub fn as_u64(&self) -> Result<u64, TypeError> {
+        u64::try_from(self.as_integer()?)
+            .map_err(|_| TypeError::new(TypeErrorType::Range("u64"), self))
+    }
+
+    /// Returns the float.
+    pub fn as_f64(&self) -> Result<f64, TypeError> {
+        match self {
+            Ipld::Float(f) => Ok(*f),
+            _ => Err(TypeError::new(TypeErrorType::Float, self)),
+        }
+    }
+
+    /// Returns the string.
+    pub fn as_str(&self) -> Result<&str, TypeError> {
+        match self {
+            Ipld::String(s) => Ok(s),
+            _ => Err(TypeError::new(TypeErrorType::String, self)),
+        }
+    }
+
+    /// Returns the bytes.
+    pub fn as_bytes(&self) -> Result<&[u8], TypeError> {
+        match self {
+            Ipld::Bytes(b) => Ok(b),
+            _ => Err(TypeError::new(TypeErrorType::Bytes, self)),
+        }
+    }
+
+    /// Returns the items of the list.
+    pub fn as_list(&self) -> Result<&[Ipld], TypeError> {
+        match self {
+            Ipld::List(l) => Ok(l),
+            _ => Err(TypeError::new(TypeErrorType::List, self)),
+        }
+    }
+
+    /// Returns the string map.
+    pub fn as_map(&self) -> Result<&BTreeMap<String, Ipld>, TypeError> {
+        match self {
+            Ipld::StringMap(m) => Ok(m),
+            _ => Err(TypeError::new(TypeErrorType::StringMap, self)),
+        }
+    }
+
+    /// Returns the link.
+    pub fn as_link(&self) -> Result<&Cid, TypeError> {
+        match self {
+            Ipld::Link(cid) => Ok(cid),
+            _ => Err(TypeError::new(TypeErrorType::Link, self)),
+        }
+    }
+
     /// Destructs an ipld list or map
     pub fn take<'a, T: Into<IpldIndex<'a>>>(mut self, index: T) -> Result<Self, TypeError> {
         let index = index.into();
@@ -152,6 +425,184 @@ impl Ipld {
             .ok_or_else(|| TypeError::new(index, self))
     }
 
+    /// Mutably indexes into an ipld list or map.
+    pub fn get_mut<'a, T: Into<IpldIndex<'a>>>(
+        &mut self,
+        index: T,
+    ) -> Result<&mut Self, TypeError> {
+        let index = index.into();
+        let found = TypeErrorType::from(&*self);
+        let ipld = match self {
+            #[cfg(feature = "unleashed")]
+            Ipld::Tag(_, inner) => return inner.get_mut(index),
+            Ipld::List(l) => match index {
+                IpldIndex::List(i) => Some(i),
+                IpldIndex::Map(ref key) => key.parse().ok(),
+                IpldIndex::MapRef(key) => key.parse().ok(),
+            }
+            .map(move |i| l.get_mut(i)),
+            #[cfg(feature = "unleashed")]
+            Ipld::IntegerMap(m) => match index {
+                IpldIndex::List(i) => Some(i as _),
+                IpldIndex::Map(ref key) => key.parse().ok(),
+                IpldIndex::MapRef(key) => key.parse().ok(),
+            }
+            .map(move |i| m.get_mut(&i)),
+            Ipld::StringMap(m) => match index {
+                IpldIndex::Map(ref key) => Some(m.get_mut(key)),
+                IpldIndex::MapRef(key) => Some(m.get_mut(key)),
+                IpldIndex::List(i) => Some(m.get_mut(&i.to_string())),
+            },
+            _ => None,
+        };
+        ipld.unwrap_or_default()
+            .ok_or_else(|| TypeError::new(index, found))
+    }
+
+    /// Inserts `ipld` into an ipld list at `index`, shifting all items after it.
+    pub fn insert(&mut self, index: usize, ipld: Ipld) -> Result<(), TypeError> {
+        match self {
+            #[cfg(feature = "unleashed")]
+            Ipld::Tag(_, inner) => inner.insert(index, ipld),
+            Ipld::List(l) if index <= l.len() => {
+                l.insert(index, ipld);
+                Ok(())
+            }
+            _ => Err(TypeError::new(TypeErrorType::Index(index), &*self)),
+        }
+    }
+
+    /// Removes an item from an ipld list or map.
+    ///
+    /// Unlike [`Ipld::take`] the order of the remaining list items is preserved.
+    pub fn remove<'a, T: Into<IpldIndex<'a>>>(&mut self, index: T) -> Result<Self, TypeError> {
+        let index = index.into();
+        let found = TypeErrorType::from(&*self);
+        let ipld = match self {
+            #[cfg(feature = "unleashed")]
+            Ipld::Tag(_, inner) => return inner.remove(index),
+            Ipld::List(l) => match index {
+                IpldIndex::List(i) => Some(i),
+                IpldIndex::Map(ref key) => key.parse().ok(),
+                IpldIndex::MapRef(key) => key.parse().ok(),
+            }
+            .map(|i| if i < l.len() { Some(l.remove(i)) } else { None }),
+            #[cfg(feature = "unleashed")]
+            Ipld::IntegerMap(m) => match index {
+                IpldIndex::List(i) => Some(i as _),
+                IpldIndex::Map(ref key) => key.parse().ok(),
+                IpldIndex::MapRef(key) => key.parse().ok(),
+            }
+            .map(|i| m.remove(&i)),
+            Ipld::StringMap(m) => match index {
+                IpldIndex::Map(ref key) => Some(m.remove(key)),
+                IpldIndex::MapRef(key) => Some(m.remove(key)),
+                IpldIndex::List(i) => Some(m.remove(&i.to_string())),
+            },
+            _ => None,
+        };
+        ipld.unwrap_or_default()
+            .ok_or_else(|| TypeError::new(index, found))
+    }
+
+    /// Indexes into nested ipld lists and maps.
+    ///
+    /// Fails with the type error of the first segment that can't be resolved.
+    pub fn get_path(&self, path: &Path) -> Result<&Self, TypeError> {
+        path.iter()
+            .try_fold(self, |ipld, segment| ipld.get(segment))
+    }
+
+    /// Mutably indexes into nested ipld lists and maps.
+    pub fn get_path_mut(&mut self, path: &Path) -> Result<&mut Self, TypeError> {
+        let mut ipld = self;
+        for segment in path.iter() {
+            ipld = ipld.get_mut(segment)?;
+        }
+        Ok(ipld)
+    }
+
+    /// Sets the value at `path` and returns the previous value.
+    ///
+    /// Missing map entries on the way are created as empty string maps. List indices must
+    /// exist, except for the last segment, which may append to a list.
+    pub fn set_path(&mut self, path: &Path, ipld: Ipld) -> Result<Option<Self>, TypeError> {
+        let segments: Vec<&str> = path.iter().collect();
+        let (last, parents) = match segments.split_last() {
+            Some(split) => split,
+            None => return Ok(Some(core::mem::replace(self, ipld))),
+        };
+        let mut node = self;
+        for segment in parents {
+            node = node.entry(segment)?;
+        }
+        node.set(last, ipld)
+    }
+
+    /// Removes the value at `path` and returns it.
+    ///
+    /// Removing the empty path replaces `self` with null.
+    pub fn remove_path(&mut self, path: &Path) -> Result<Self, TypeError> {
+        let segments: Vec<&str> = path.iter().collect();
+        match segments.split_last() {
+            Some((last, parents)) => {
+                let mut node = self;
+                for segment in parents {
+                    node = node.get_mut(*segment)?;
+                }
+                node.remove(*last)
+            }
+            None => Ok(core::mem::replace(self, Ipld::Null)),
+        }
+    }
+
+    /// Returns the child at `segment`, inserting an empty string map into maps if missing.
+    fn entry(&mut self, segment: &str) -> Result<&mut Self, TypeError> {
+        let found = TypeErrorType::from(&*self);
+        let ipld = match self {
+            #[cfg(feature = "unleashed")]
+            Ipld::Tag(_, inner) => return inner.entry(segment),
+            Ipld::StringMap(m) => Some(
+                m.entry(segment.to_owned())
+                    .or_insert_with(|| Ipld::StringMap(BTreeMap::new())),
+            ),
+            #[cfg(feature = "unleashed")]
+            Ipld::IntegerMap(m) => segment.parse().ok().map(move |i| {
+                m.entry(i)
+                    .or_insert_with(|| Ipld::StringMap(BTreeMap::new()))
+            }),
+            Ipld::List(l) => segment.parse().ok().and_then(move |i: usize| l.get_mut(i)),
+            _ => None,
+        };
+        ipld.ok_or_else(|| TypeError::new(IpldIndex::MapRef(segment), found))
+    }
+
+    /// Sets the child at `segment`, appending to lists if `segment` is their length.
+    pub(crate) fn set(&mut self, segment: &str, ipld: Ipld) -> Result<Option<Self>, TypeError> {
+        let found = TypeErrorType::from(&*self);
+        match self {
+            #[cfg(feature = "unleashed")]
+            Ipld::Tag(_, inner) => return inner.set(segment, ipld),
+            Ipld::StringMap(m) => return Ok(m.insert(segment.to_owned(), ipld)),
+            #[cfg(feature = "unleashed")]
+            Ipld::IntegerMap(m) => {
+                if let Ok(i) = segment.parse() {
+                    return Ok(m.insert(i, ipld));
+                }
+            }
+            Ipld::List(l) => match segment.parse::<usize>() {
+                Ok(i) if i < l.len() => return Ok(Some(core::mem::replace(&mut l[i], ipld))),
+                Ok(i) if i == l.len() => {
+                    l.push(ipld);
+                    return Ok(None);
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        Err(TypeError::new(IpldIndex::MapRef(segment), found))
+    }
+
     /// Returns an iterator.
     pub fn iter(&self) -> IpldIter<'_> {
         IpldIter {
@@ -163,10 +614,32 @@ impl Ipld {
     pub fn references<E: Extend<Cid>>(&self, set: &mut E) {
         for ipld in self.iter() {
             if let Ipld::Link(cid) = ipld {
-                set.extend(std::iter::once(cid.to_owned()));
+                set.extend(core::iter::once(cid.to_owned()));
             }
         }
     }
+
+    /// Returns an iterator over all nodes and their paths in pre-order.
+    pub fn walk(&self) -> Walk<'_> {
+        Walk::new(self)
+    }
+
+    /// Calls the `visitor` for all nodes in pre-order.
+    pub fn visit<'a, V: Visitor<'a>>(&'a self, visitor: &mut V) {
+        walk::visit(&mut Path::default(), self, visitor);
+    }
+
+    /// Calls the `visitor` for all nodes in pre-order, allowing them to be rewritten.
+    pub fn visit_mut<V: VisitorMut>(&mut self, visitor: &mut V) {
+        walk::visit_mut(&mut Path::default(), self, visitor);
+    }
+
+    /// Calls `f` for all nodes in pre-order, allowing them to be rewritten.
+    ///
+    /// If a node is replaced, the children of the replacement are walked.
+    pub fn walk_mut<F: FnMut(&Path, &mut Ipld) -> Control>(&mut self, mut f: F) {
+        self.visit_mut(&mut f);
+    }
 }
 
 /// Ipld iterator.
@@ -227,7 +700,7 @@ mod tests {
         assert_eq!(Ipld::Integer(1), Ipld::from(1i16));
         assert_eq!(Ipld::Integer(1), Ipld::from(1i32));
         assert_eq!(Ipld::Integer(1), Ipld::from(1i64));
-        assert_eq!(Ipld::Integer(1), Ipld::from(1i128));
+        assert_eq!(Ipld::Integer(1), Ipld::try_from(1i128).unwrap());
 
         //assert_eq!(Ipld::Integer(1), 1u8.to_ipld().to_owned());
         assert_eq!(Ipld::Integer(1), Ipld::from(1u16));
@@ -299,4 +772,133 @@ mod tests {
         let ipld = Ipld::StringMap(map);
         assert_eq!(ipld.get("a").unwrap(), &Ipld::Integer(0));
     }
+
+    #[test]
+    fn test_get_mut() {
+        let mut ipld = Ipld::List(vec![Ipld::Integer(0), Ipld::Integer(1)]);
+        *ipld.get_mut(1).unwrap() = Ipld::Null;
+        assert_eq!(ipld, Ipld::List(vec![Ipld::Integer(0), Ipld::Null]));
+        assert!(ipld.get_mut(2).is_err());
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut ipld = Ipld::List(vec![Ipld::Integer(0), Ipld::Integer(2)]);
+        ipld.insert(1, Ipld::Integer(1)).unwrap();
+        ipld.insert(3, Ipld::Integer(3)).unwrap();
+        assert!(ipld.insert(5, Ipld::Null).is_err());
+        assert_eq!(ipld.remove(0).unwrap(), Ipld::Integer(0));
+        assert_eq!(
+            ipld,
+            Ipld::List(vec![Ipld::Integer(1), Ipld::Integer(2), Ipld::Integer(3)])
+        );
+        assert!(Ipld::Null.insert(0, Ipld::Null).is_err());
+    }
+
+    #[test]
+    fn test_path() {
+        let mut ipld = Ipld::StringMap(BTreeMap::new());
+        let path = Path::from("a/b/c");
+        assert_eq!(ipld.set_path(&path, Ipld::List(vec![])).unwrap(), None);
+        ipld.set_path(&Path::from("a/b/c/0"), Ipld::Integer(0))
+            .unwrap();
+        assert_eq!(
+            ipld.set_path(&Path::from("a/b/c/0"), Ipld::Integer(1))
+                .unwrap(),
+            Some(Ipld::Integer(0))
+        );
+        assert_eq!(
+            ipld.get_path(&path).unwrap(),
+            &Ipld::List(vec![Ipld::Integer(1)])
+        );
+
+        let err = ipld
+            .set_path(&Path::from("a/b/c/x/y"), Ipld::Null)
+            .unwrap_err();
+        assert!(matches!(err.expected, TypeErrorType::Key(ref key) if key == "x"));
+        assert!(matches!(err.found, TypeErrorType::List));
+        let err = ipld.get_path(&Path::from("a/d")).unwrap_err();
+        assert!(matches!(err.expected, TypeErrorType::Key(ref key) if key == "d"));
+
+        assert_eq!(
+            ipld.remove_path(&Path::from("a/b/c/0")).unwrap(),
+            Ipld::Integer(1)
+        );
+        ipld.remove_path(&Path::from("a/b")).unwrap();
+        assert!(ipld.get_path(&Path::from("a/b")).is_err());
+        assert!(ipld.remove_path(&Path::from("a/b")).is_err());
+    }
+
+    #[test]
+    fn test_eq_ord_hash() {
+        use std::collections::{BTreeSet, HashSet};
+
+        assert_eq!(Ipld::Float(f64::NAN), Ipld::Float(f64::NAN));
+        assert_ne!(Ipld::Float(0.0), Ipld::Float(-0.0));
+        assert!(Ipld::Float(-0.0) < Ipld::Float(0.0));
+        assert!(Ipld::Null < Ipld::Bool(false));
+        assert!(Ipld::Integer(i128::MAX) < Ipld::Float(f64::NEG_INFINITY));
+
+        let map = |keys: &[&str]| {
+            Ipld::StringMap(keys.iter().map(|k| (k.to_string(), Ipld::Null)).collect())
+        };
+        // "b" sorts before "aa" in DAG-CBOR key order
+        assert!(map(&["b"]) < map(&["aa"]));
+        assert!(map(&["b"]) < map(&["b", "aa"]));
+        assert!(map(&["c", "aa"]) < map(&["aa", "d"]));
+        assert!(map(&["aa", "b", "ccc"]) > map(&["aa", "b", "bb"]));
+        assert_eq!(map(&["bb", "a"]).cmp(&map(&["a", "bb"])), Ordering::Equal);
+
+        let values = vec![
+            Ipld::Float(f64::NAN),
+            Ipld::Float(f64::NAN),
+            Ipld::Float(1.0),
+            map(&["a"]),
+            map(&["a"]),
+            Ipld::List(vec![Ipld::Null]),
+        ];
+        let hashed: HashSet<_> = values.iter().cloned().collect();
+        let ordered: BTreeSet<_> = values.into_iter().collect();
+        assert_eq!(hashed.len(), 4);
+        assert_eq!(ordered.len(), 4);
+        assert!(ordered.iter().all(|ipld| hashed.contains(ipld)));
+    }
+
+    #[test]
+    fn test_accessors() {
+        let ipld = Ipld::List(vec![Ipld::Integer(-1), Ipld::from("a"), Ipld::Null]);
+        let list = ipld.as_list().unwrap();
+        assert_eq!(list[0].as_i64().unwrap(), -1);
+        assert!(matches!(
+            list[0].as_u64().unwrap_err().expected,
+            TypeErrorType::Range("u64")
+        ));
+        assert_eq!(list[1].as_str().unwrap(), "a");
+        assert!(list[2].is_null());
+        let err = list[1].as_map().unwrap_err();
+        assert!(matches!(err.expected, TypeErrorType::StringMap));
+        assert!(matches!(err.found, TypeErrorType::String));
+        assert!(ipld.as_link().is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut map = BTreeMap::new();
+        map.insert("a".to_string(), Ipld::List(vec![Ipld::Float(1.0)]));
+        let mut ipld = Ipld::StringMap(map);
+        ipld.validate().unwrap();
+
+        ipld.set_path(&"a/1".into(), Ipld::Integer(Ipld::INTEGER_MAX + 1))
+            .unwrap();
+        let err = ipld.validate().unwrap_err();
+        assert!(matches!(
+            err,
+            InvalidIpld::IntegerOutOfRange { ref path, .. } if path.to_string() == "a/1"
+        ));
+
+        ipld.set_path(&"a/1".into(), Ipld::Float(f64::INFINITY))
+            .unwrap();
+        let err = ipld.validate().unwrap_err();
+        assert!(matches!(err, InvalidIpld::NonFiniteFloat { .. }));
+    }
 }
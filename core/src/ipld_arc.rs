@@ -0,0 +1,346 @@
+//! Reference counted ipld representation.
+use crate::cid::Cid;
+use crate::codec::{Codec, Decode};
+use crate::error::{Result, TypeError};
+use crate::io::{Read, Seek};
+use crate::ipld::{length_first, Ipld, IpldIndex};
+#[cfg(feature = "unleashed")]
+use alloc::boxed::Box;
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    sync::Arc,
+};
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+/// Ipld sharing its strings, bytes, lists and maps, so clones are cheap.
+///
+/// Use it to cache large documents or to share them between threads. Convert from and to
+/// [`Ipld`] with `From`. The codecs encode it directly and decode it by converting from [`Ipld`].
+/// It is compared, ordered and hashed the same way as [`Ipld`].
+#[derive(Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum IpldArc {
+    /// Represents the absence of a value or the value undefined.
+    Null,
+    /// Represents a boolean value.
+    Bool(bool),
+    /// Represents an integer.
+    Integer(i128),
+    /// Represents a floating point value.
+    Float(f64),
+    /// Represents an UTF-8 string.
+    String(Arc<str>),
+    /// Represents a sequence of bytes.
+    Bytes(Arc<[u8]>),
+    /// Represents a list.
+    List(Arc<[IpldArc]>),
+    /// Represents a map of strings.
+    StringMap(Arc<BTreeMap<Arc<str>, IpldArc>>),
+    /// Represents a map of integers.
+    #[cfg(feature = "unleashed")]
+    IntegerMap(Arc<BTreeMap<i64, IpldArc>>),
+    /// Represents a link to an Ipld node.
+    Link(Cid),
+    /// A cbor tag.
+    #[cfg(feature = "unleashed")]
+    Tag(u64, Arc<IpldArc>),
+}
+
+#[cfg(not(feature = "std"))]
+impl core::fmt::Debug for IpldArc {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::Null => f.write_str("Null"),
+            Self::Bool(b) => f.debug_tuple("Bool").field(b).finish(),
+            Self::Integer(i) => f.debug_tuple("Integer").field(i).finish(),
+            Self::Float(n) => f.debug_tuple("Float").field(n).finish(),
+            Self::String(s) => f.debug_tuple("String").field(s).finish(),
+            Self::Bytes(b) => f.debug_tuple("Bytes").field(b).finish(),
+            Self::List(l) => f.debug_tuple("List").field(l).finish(),
+            Self::StringMap(m) => f.debug_tuple("StringMap").field(m).finish(),
+            #[cfg(feature = "unleashed")]
+            Self::IntegerMap(m) => f.debug_tuple("IntegerMap").field(m).finish(),
+            Self::Link(cid) => f
+                .debug_tuple("Link")
+                .field(&crate::ipld::CidDebug(cid))
+                .finish(),
+            #[cfg(feature = "unleashed")]
+            Self::Tag(tag, ipld) => f.debug_tuple("Tag").field(tag).field(ipld).finish(),
+        }
+    }
+}
+
+impl IpldArc {
+    /// Indexes into an ipld list or map.
+    pub fn get<'a, T: Into<IpldIndex<'a>>>(&self, index: T) -> Result<&Self, TypeError> {
+        let index = index.into();
+        let ipld = match self {
+            #[cfg(feature = "unleashed")]
+            Self::Tag(_, inner) => return inner.get(index),
+            Self::List(l) => match index {
+                IpldIndex::List(i) => Some(i),
+                IpldIndex::Map(ref key) => key.parse().ok(),
+                IpldIndex::MapRef(key) => key.parse().ok(),
+            }
+            .map(|i| l.get(i)),
+            #[cfg(feature = "unleashed")]
+            Self::IntegerMap(m) => match index {
+                IpldIndex::List(i) => Some(i as _),
+                IpldIndex::Map(ref key) => key.parse().ok(),
+                IpldIndex::MapRef(key) => key.parse().ok(),
+            }
+            .map(|i| m.get(&i)),
+            Self::StringMap(m) => match index {
+                IpldIndex::Map(ref key) => Some(m.get(key.as_str())),
+                IpldIndex::MapRef(key) => Some(m.get(key)),
+                IpldIndex::List(i) => Some(m.get(i.to_string().as_str())),
+            },
+            _ => None,
+        };
+        ipld.unwrap_or_default()
+            .ok_or_else(|| TypeError::new(index, self))
+    }
+}
+
+impl IpldArc {
+    /// Returns the position of the kind of `self` in the enum.
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Null => 0,
+            Self::Bool(_) => 1,
+            Self::Integer(_) => 2,
+            Self::Float(_) => 3,
+            Self::String(_) => 4,
+            Self::Bytes(_) => 5,
+            Self::List(_) => 6,
+            Self::StringMap(_) => 7,
+            #[cfg(feature = "unleashed")]
+            Self::IntegerMap(_) => 8,
+            Self::Link(_) => 9,
+            #[cfg(feature = "unleashed")]
+            Self::Tag(_, _) => 10,
+        }
+    }
+}
+
+impl PartialEq for IpldArc {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Null, Self::Null) => true,
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            (Self::Integer(a), Self::Integer(b)) => a == b,
+            (Self::Float(a), Self::Float(b)) => a.to_bits() == b.to_bits(),
+            (Self::String(a), Self::String(b)) => a == b,
+            (Self::Bytes(a), Self::Bytes(b)) => a == b,
+            (Self::List(a), Self::List(b)) => a == b,
+            (Self::StringMap(a), Self::StringMap(b)) => a == b,
+            #[cfg(feature = "unleashed")]
+            (Self::IntegerMap(a), Self::IntegerMap(b)) => a == b,
+            (Self::Link(a), Self::Link(b)) => a == b,
+            #[cfg(feature = "unleashed")]
+            (Self::Tag(a, x), Self::Tag(b, y)) => a == b && x == y,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for IpldArc {}
+
+impl PartialOrd for IpldArc {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for IpldArc {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Bool(a), Self::Bool(b)) => a.cmp(b),
+            (Self::Integer(a), Self::Integer(b)) => a.cmp(b),
+            (Self::Float(a), Self::Float(b)) => a.total_cmp(b),
+            (Self::String(a), Self::String(b)) => a.cmp(b),
+            (Self::Bytes(a), Self::Bytes(b)) => a.cmp(b),
+            (Self::List(a), Self::List(b)) => a.cmp(b),
+            (Self::StringMap(a), Self::StringMap(b)) => length_first(a).cmp(length_first(b)),
+            #[cfg(feature = "unleashed")]
+            (Self::IntegerMap(a), Self::IntegerMap(b)) => a.cmp(b),
+            (Self::Link(a), Self::Link(b)) => a.cmp(b),
+            #[cfg(feature = "unleashed")]
+            (Self::Tag(a, x), Self::Tag(b, y)) => a.cmp(b).then_with(|| x.cmp(y)),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl Hash for IpldArc {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            Self::Null => {}
+            Self::Bool(b) => b.hash(state),
+            Self::Integer(i) => i.hash(state),
+            Self::Float(f) => f.to_bits().hash(state),
+            Self::String(s) => s.hash(state),
+            Self::Bytes(b) => b.hash(state),
+            Self::List(l) => l.hash(state),
+            Self::StringMap(m) => m.hash(state),
+            #[cfg(feature = "unleashed")]
+            Self::IntegerMap(m) => m.hash(state),
+            Self::Link(cid) => Hash::hash(cid, state),
+            #[cfg(feature = "unleashed")]
+            Self::Tag(tag, ipld) => {
+                tag.hash(state);
+                ipld.hash(state);
+            }
+        }
+    }
+}
+
+impl From<&Ipld> for IpldArc {
+    fn from(ipld: &Ipld) -> Self {
+        match ipld {
+            Ipld::Null => Self::Null,
+            Ipld::Bool(b) => Self::Bool(*b),
+            Ipld::Integer(i) => Self::Integer(*i),
+            Ipld::Float(f) => Self::Float(*f),
+            Ipld::String(s) => Self::String(s.as_str().into()),
+            Ipld::Bytes(b) => Self::Bytes(b.as_slice().into()),
+            Ipld::List(l) => Self::List(l.iter().map(Into::into).collect()),
+            Ipld::StringMap(m) => Self::StringMap(Arc::new(
+                m.iter()
+                    .map(|(k, v)| (k.as_str().into(), v.into()))
+                    .collect(),
+            )),
+            #[cfg(feature = "unleashed")]
+            Ipld::IntegerMap(m) => {
+                Self::IntegerMap(Arc::new(m.iter().map(|(k, v)| (*k, v.into())).collect()))
+            }
+            Ipld::Link(cid) => Self::Link(*cid),
+            #[cfg(feature = "unleashed")]
+            Ipld::Tag(tag, ipld) => Self::Tag(*tag, Arc::new((&**ipld).into())),
+        }
+    }
+}
+
+impl From<Ipld> for IpldArc {
+    fn from(ipld: Ipld) -> Self {
+        match ipld {
+            Ipld::String(s) => Self::String(s.into()),
+            Ipld::Bytes(b) => Self::Bytes(b.into()),
+            Ipld::List(l) => Self::List(l.into_iter().map(Into::into).collect()),
+            Ipld::StringMap(m) => Self::StringMap(Arc::new(
+                m.into_iter().map(|(k, v)| (k.into(), v.into())).collect(),
+            )),
+            #[cfg(feature = "unleashed")]
+            Ipld::IntegerMap(m) => Self::IntegerMap(Arc::new(
+                m.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            )),
+            #[cfg(feature = "unleashed")]
+            Ipld::Tag(tag, ipld) => Self::Tag(tag, Arc::new((*ipld).into())),
+            ipld => Self::from(&ipld),
+        }
+    }
+}
+
+impl From<&IpldArc> for Ipld {
+    fn from(ipld: &IpldArc) -> Self {
+        match ipld {
+            IpldArc::Null => Self::Null,
+            IpldArc::Bool(b) => Self::Bool(*b),
+            IpldArc::Integer(i) => Self::Integer(*i),
+            IpldArc::Float(f) => Self::Float(*f),
+            IpldArc::String(s) => Self::String(s.to_string()),
+            IpldArc::Bytes(b) => Self::Bytes(b.to_vec()),
+            IpldArc::List(l) => Self::List(l.iter().map(Into::into).collect()),
+            IpldArc::StringMap(m) => Self::StringMap(
+                m.iter()
+                    .map(|(k, v)| (String::from(&**k), v.into()))
+                    .collect(),
+            ),
+            #[cfg(feature = "unleashed")]
+            IpldArc::IntegerMap(m) => {
+                Self::IntegerMap(m.iter().map(|(k, v)| (*k, v.into())).collect())
+            }
+            IpldArc::Link(cid) => Self::Link(*cid),
+            #[cfg(feature = "unleashed")]
+            IpldArc::Tag(tag, ipld) => Self::Tag(*tag, Box::new((&**ipld).into())),
+        }
+    }
+}
+
+impl From<IpldArc> for Ipld {
+    fn from(ipld: IpldArc) -> Self {
+        Self::from(&ipld)
+    }
+}
+
+impl<C: Codec> Decode<C> for IpldArc
+where
+    Ipld: Decode<C>,
+{
+    fn decode<R: Read + Seek>(c: C, r: &mut R) -> Result<Self> {
+        Ipld::decode(c, r).map(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ipld_arc_roundtrip() {
+        let mut map = BTreeMap::new();
+        map.insert("a".to_string(), Ipld::Bytes(vec![1, 2]));
+        map.insert("b".to_string(), Ipld::List(vec![Ipld::Null, 1.into()]));
+        let ipld = Ipld::StringMap(map);
+        let arc = IpldArc::from(&ipld);
+        assert_eq!(arc, IpldArc::from(ipld.clone()));
+        assert_eq!(Ipld::from(arc.clone()), ipld);
+        assert_eq!(arc.get("b").unwrap().get(1).unwrap(), &IpldArc::Integer(1));
+        let err = arc.get("c").unwrap_err();
+        assert!(matches!(err.found, crate::error::TypeErrorType::StringMap));
+    }
+
+    #[test]
+    fn eq_ord_hash_like_ipld() {
+        use std::collections::HashSet;
+
+        let map = |keys: &[&str]| {
+            Ipld::StringMap(keys.iter().map(|k| (k.to_string(), Ipld::Null)).collect())
+        };
+        let values = [
+            Ipld::Null,
+            Ipld::Bool(true),
+            Ipld::Integer(i128::MAX),
+            Ipld::Float(f64::NAN),
+            Ipld::Float(-0.0),
+            Ipld::Float(0.0),
+            Ipld::from("a"),
+            Ipld::List(vec![Ipld::Float(f64::NAN)]),
+            map(&["b"]),
+            map(&["aa"]),
+            map(&["b", "aa"]),
+        ];
+        for a in &values {
+            for b in &values {
+                let (x, y) = (IpldArc::from(a), IpldArc::from(b));
+                assert_eq!(x.cmp(&y), a.cmp(b), "{:?} {:?}", a, b);
+                assert_eq!(x == y, a == b, "{:?} {:?}", a, b);
+            }
+        }
+        let hashed: HashSet<_> = values.iter().chain(&values).map(IpldArc::from).collect();
+        assert_eq!(hashed.len(), values.len());
+    }
+
+    #[test]
+    fn clones_share() {
+        let arc = IpldArc::from(Ipld::List(vec![Ipld::from("shared")]));
+        let clone = arc.clone();
+        match (&arc, &clone) {
+            (IpldArc::List(a), IpldArc::List(b)) => assert!(Arc::ptr_eq(a, b)),
+            _ => unreachable!(),
+        }
+    }
+}
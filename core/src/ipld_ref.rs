@@ -0,0 +1,136 @@
+//! Borrowed ipld representation.
+use crate::cid::Cid;
+use crate::ipld::Ipld;
+#[cfg(feature = "unleashed")]
+use alloc::boxed::Box;
+use alloc::{collections::BTreeMap, string::ToString, vec::Vec};
+
+/// Ipld borrowing strings and bytes from the data it was decoded from.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum IpldRef<'a> {
+    /// Represents the absence of a value or the value undefined.
+    Null,
+    /// Represents a boolean value.
+    Bool(bool),
+    /// Represents an integer.
+    Integer(i128),
+    /// Represents a floating point value.
+    Float(f64),
+    /// Represents an UTF-8 string.
+    String(&'a str),
+    /// Represents a sequence of bytes.
+    Bytes(&'a [u8]),
+    /// Represents a list.
+    List(Vec<IpldRef<'a>>),
+    /// Represents a map of strings.
+    StringMap(BTreeMap<&'a str, IpldRef<'a>>),
+    /// Represents a map of integers.
+    #[cfg(feature = "unleashed")]
+    IntegerMap(BTreeMap<i64, IpldRef<'a>>),
+    /// Represents a link to an Ipld node.
+    Link(Cid),
+    /// A cbor tag.
+    #[cfg(feature = "unleashed")]
+    Tag(u64, Box<IpldRef<'a>>),
+}
+
+#[cfg(not(feature = "std"))]
+impl core::fmt::Debug for IpldRef<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::Null => f.write_str("Null"),
+            Self::Bool(b) => f.debug_tuple("Bool").field(b).finish(),
+            Self::Integer(i) => f.debug_tuple("Integer").field(i).finish(),
+            Self::Float(n) => f.debug_tuple("Float").field(n).finish(),
+            Self::String(s) => f.debug_tuple("String").field(s).finish(),
+            Self::Bytes(b) => f.debug_tuple("Bytes").field(b).finish(),
+            Self::List(l) => f.debug_tuple("List").field(l).finish(),
+            Self::StringMap(m) => f.debug_tuple("StringMap").field(m).finish(),
+            #[cfg(feature = "unleashed")]
+            Self::IntegerMap(m) => f.debug_tuple("IntegerMap").field(m).finish(),
+            Self::Link(cid) => f
+                .debug_tuple("Link")
+                .field(&crate::ipld::CidDebug(cid))
+                .finish(),
+            #[cfg(feature = "unleashed")]
+            Self::Tag(tag, ipld) => f.debug_tuple("Tag").field(tag).field(ipld).finish(),
+        }
+    }
+}
+
+impl<'a> From<&'a Ipld> for IpldRef<'a> {
+    fn from(ipld: &'a Ipld) -> Self {
+        match ipld {
+            Ipld::Null => Self::Null,
+            Ipld::Bool(b) => Self::Bool(*b),
+            Ipld::Integer(i) => Self::Integer(*i),
+            Ipld::Float(f) => Self::Float(*f),
+            Ipld::String(s) => Self::String(s),
+            Ipld::Bytes(b) => Self::Bytes(b),
+            Ipld::List(l) => Self::List(l.iter().map(Into::into).collect()),
+            Ipld::StringMap(m) => {
+                Self::StringMap(m.iter().map(|(k, v)| (&k[..], v.into())).collect())
+            }
+            #[cfg(feature = "unleashed")]
+            Ipld::IntegerMap(m) => {
+                Self::IntegerMap(m.iter().map(|(k, v)| (*k, v.into())).collect())
+            }
+            Ipld::Link(cid) => Self::Link(*cid),
+            #[cfg(feature = "unleashed")]
+            Ipld::Tag(tag, ipld) => Self::Tag(*tag, Box::new((&**ipld).into())),
+        }
+    }
+}
+
+impl<'a> From<IpldRef<'a>> for Ipld {
+    fn from(ipld: IpldRef<'a>) -> Self {
+        match ipld {
+            IpldRef::Null => Self::Null,
+            IpldRef::Bool(b) => Self::Bool(b),
+            IpldRef::Integer(i) => Self::Integer(i),
+            IpldRef::Float(f) => Self::Float(f),
+            IpldRef::String(s) => Self::String(s.to_string()),
+            IpldRef::Bytes(b) => Self::Bytes(b.to_vec()),
+            IpldRef::List(l) => Self::List(l.into_iter().map(Into::into).collect()),
+            IpldRef::StringMap(m) => Self::StringMap(
+                m.into_iter()
+                    .map(|(k, v)| (k.to_string(), v.into()))
+                    .collect(),
+            ),
+            #[cfg(feature = "unleashed")]
+            IpldRef::IntegerMap(m) => {
+                Self::IntegerMap(m.into_iter().map(|(k, v)| (k, v.into())).collect())
+            }
+            IpldRef::Link(cid) => Self::Link(cid),
+            #[cfg(feature = "unleashed")]
+            IpldRef::Tag(tag, ipld) => Self::Tag(tag, Box::new((*ipld).into())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ipld_ref_roundtrip() {
+        let mut map = BTreeMap::new();
+        map.insert("a".to_string(), Ipld::Bytes(vec![1, 2]));
+        map.insert("b".to_string(), Ipld::List(vec![Ipld::Null, 1.into()]));
+        let ipld = Ipld::StringMap(map);
+        let ipld_ref = IpldRef::from(&ipld);
+        assert_eq!(
+            ipld_ref,
+            IpldRef::StringMap(
+                vec![
+                    ("a", IpldRef::Bytes(&[1, 2])),
+                    ("b", IpldRef::List(vec![IpldRef::Null, IpldRef::Integer(1)])),
+                ]
+                .into_iter()
+                .collect()
+            )
+        );
+        assert_eq!(Ipld::from(ipld_ref), ipld);
+    }
+}
@@ -1,14 +1,28 @@
 //! Core ipld types used by ipld codecs.
 #![deny(missing_docs)]
 #![deny(warnings)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 pub mod codec;
 pub mod convert;
 pub mod error;
+pub mod io;
 pub mod ipld;
+pub mod ipld_arc;
+pub mod ipld_ref;
 pub mod link;
+#[cfg(feature = "std")]
+pub mod lookahead;
+pub mod patch;
+pub mod path;
 pub mod raw;
 pub mod raw_value;
+pub mod schema;
+#[cfg(feature = "serde-codec")]
+pub mod serde;
+pub mod walk;
 
 pub use cid;
 pub use multibase;
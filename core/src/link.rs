@@ -2,14 +2,14 @@
 use crate::cid::Cid;
 use crate::codec::{Codec, Decode, Encode};
 use crate::error::Result;
-use std::cmp::Ordering;
-use std::hash::{Hash, Hasher};
-use std::io::{Read, Seek, Write};
-use std::marker::PhantomData;
-use std::ops::Deref;
+use crate::io::{Read, Seek, Write};
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::ops::Deref;
 
 /// Typed cid.
-#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct Link<T> {
     cid: Cid,
     _marker: PhantomData<T>,
@@ -30,18 +30,25 @@ impl<T> Link<T> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<T> std::fmt::Display for Link<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         self.cid.fmt(f)
     }
 }
 
+#[cfg(not(feature = "std"))]
+impl<T> core::fmt::Debug for Link<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("Link")
+            .field("cid", &crate::ipld::CidDebug(&self.cid))
+            .finish()
+    }
+}
+
 impl<T> Clone for Link<T> {
     fn clone(&self) -> Self {
-        Self {
-            cid: self.cid,
-            _marker: self._marker,
-        }
+        *self
     }
 }
 
@@ -57,7 +64,7 @@ impl<T> Eq for Link<T> {}
 
 impl<T> PartialOrd for Link<T> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cid.cmp(other.cid()))
+        Some(self.cmp(other))
     }
 }
 
@@ -110,3 +117,22 @@ impl<T> From<Cid> for Link<T> {
         Self::new(cid)
     }
 }
+
+#[cfg(feature = "serde-codec")]
+impl<T> serde::Serialize for Link<T> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        crate::serde::cid::serialize(self.cid(), serializer)
+    }
+}
+
+#[cfg(feature = "serde-codec")]
+impl<'de, T> serde::Deserialize<'de> for Link<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        crate::serde::cid::deserialize(deserializer).map(Self::new)
+    }
+}
@@ -0,0 +1,191 @@
+//! Seekable adapter for readers that can't seek.
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Number of bytes kept before the current position for peeking.
+const LOOKBEHIND: u64 = 16;
+
+/// Adapter implementing `Seek` for any `Read`.
+///
+/// Decoders only look back to peek at the next few bytes, so the reader keeps the last 16 bytes
+/// read and seeking back any further fails. Seeking forward reads and
+/// drops the skipped bytes from the inner reader. Only the bytes needed by the decoder are
+/// read, so after decoding a value the inner reader is positioned right after it.
+#[derive(Debug)]
+pub struct LookaheadReader<R> {
+    inner: R,
+    /// Bytes read from the inner reader, starting at position `start`.
+    buf: Vec<u8>,
+    start: u64,
+    pos: u64,
+}
+
+impl<R: Read> LookaheadReader<R> {
+    /// Creates a new `LookaheadReader`.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buf: Vec::new(),
+            start: 0,
+            pos: 0,
+        }
+    }
+
+    /// Returns the inner reader.
+    ///
+    /// Bytes read from the inner reader after the current position are lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns the position after the last byte read from the inner reader.
+    fn end(&self) -> u64 {
+        self.start + self.buf.len() as u64
+    }
+
+    /// Reads and drops the bytes up to `pos` from the inner reader, or until it is exhausted.
+    fn skip_to(&mut self, pos: u64) -> io::Result<()> {
+        let end = self.end();
+        if pos > end {
+            let skipped = io::copy(&mut (&mut self.inner).take(pos - end), &mut io::sink())?;
+            self.buf.clear();
+            self.start = end + skipped;
+        }
+        Ok(())
+    }
+
+    /// Drops the buffered bytes before the lookbehind.
+    fn trim(&mut self) {
+        let keep = self.pos.saturating_sub(LOOKBEHIND);
+        let n = (keep.saturating_sub(self.start) as usize).min(self.buf.len());
+        // drain in bulk so that reading byte by byte doesn't shift the buffer every time
+        if n > 0 && n >= self.buf.len() / 2 {
+            self.buf.drain(..n);
+            self.start += n as u64;
+        }
+    }
+}
+
+impl<R: Read> Read for LookaheadReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let end = self.end();
+        if self.pos > end {
+            // the inner reader was exhausted when seeking past its end
+            return Ok(0);
+        }
+        let n = if self.pos == end {
+            let n = self.inner.read(out)?;
+            self.buf.extend_from_slice(&out[..n]);
+            n
+        } else {
+            let buffered = &self.buf[(self.pos - self.start) as usize..];
+            let n = buffered.len().min(out.len());
+            out[..n].copy_from_slice(&buffered[..n]);
+            n
+        };
+        self.pos += n as u64;
+        self.trim();
+        Ok(n)
+    }
+}
+
+impl<R: Read> Seek for LookaheadReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::Current(offset) => {
+                if offset >= 0 {
+                    self.pos.checked_add(offset as u64)
+                } else {
+                    self.pos.checked_sub(offset.unsigned_abs())
+                }
+            }
+            SeekFrom::End(_) => {
+                return Err(io::Error::other("can't seek from the end of a stream"))
+            }
+        };
+        let pos = pos.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative position",
+            )
+        })?;
+        if pos < self.start {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "can't seek back to a position that was dropped",
+            ));
+        }
+        self.skip_to(pos)?;
+        self.pos = pos;
+        self.trim();
+        Ok(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seek_back_and_forth() {
+        let mut r = LookaheadReader::new(&[1, 2, 3, 4, 5][..]);
+        let mut buf = [0; 2];
+        r.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [1, 2]);
+        assert_eq!(r.seek(SeekFrom::Current(-1)).unwrap(), 1);
+        r.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [2, 3]);
+        assert_eq!(r.seek(SeekFrom::Current(1)).unwrap(), 4);
+        r.read_exact(&mut buf[..1]).unwrap();
+        assert_eq!(buf[0], 5);
+        // the skipped byte isn't kept
+        assert_eq!(r.seek(SeekFrom::Current(-1)).unwrap(), 4);
+        assert!(r.seek(SeekFrom::Start(3)).is_err());
+        r.seek(SeekFrom::Start(10)).unwrap();
+        assert_eq!(r.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn reads_only_what_is_needed() {
+        let mut inner = &[1, 2, 3, 4][..];
+        let mut r = LookaheadReader::new(&mut inner);
+        let mut buf = [0; 2];
+        r.read_exact(&mut buf).unwrap();
+        r.seek(SeekFrom::Start(0)).unwrap();
+        r.read_exact(&mut buf).unwrap();
+        drop(r);
+        assert_eq!(inner, [3, 4]);
+    }
+
+    #[test]
+    fn drops_bytes_it_cant_seek_back_to() {
+        let data = vec![7; 100_000];
+        let mut r = LookaheadReader::new(&data[..]);
+        let mut byte = [0; 1];
+        for _ in 0..50_000 {
+            r.read_exact(&mut byte).unwrap();
+        }
+        assert!(r.buf.len() <= 2 * LOOKBEHIND as usize);
+        r.seek(SeekFrom::Current(-1)).unwrap();
+        let pos = r.stream_position().unwrap();
+        for _ in 0..10_000 {
+            r.read_exact(&mut byte).unwrap();
+        }
+        assert!(r.buf.len() <= 2 * LOOKBEHIND as usize);
+        assert!(r.seek(SeekFrom::Start(pos)).is_err());
+    }
+
+    #[test]
+    fn skips_without_buffering() {
+        let mut data = vec![0; 1 << 20];
+        data.push(1);
+        let mut r = LookaheadReader::new(&data[..]);
+        r.seek(SeekFrom::Current(1 << 20)).unwrap();
+        assert!(r.buf.is_empty());
+        let mut byte = [0; 1];
+        r.read_exact(&mut byte).unwrap();
+        assert_eq!(byte[0], 1);
+        assert_eq!(r.seek(SeekFrom::Current(-1)).unwrap(), 1 << 20);
+        assert!(r.seek(SeekFrom::Current(-1)).is_err());
+    }
+}
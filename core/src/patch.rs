@@ -0,0 +1,462 @@
+//! IPLD Patch, structural diffs of ipld modeled on JSON Patch.
+use crate::error::{PatchError, TypeError};
+use crate::ipld::{Ipld, IpldIndex};
+use crate::path::Path;
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::convert::TryFrom;
+
+/// Patch operation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operation {
+    /// Adds `value` at `path`.
+    ///
+    /// Adding to a list inserts before the index, the segment `-` appends.
+    Add {
+        /// Path to add at.
+        path: Path,
+        /// Value to add.
+        value: Ipld,
+    },
+    /// Removes the value at `path`.
+    Remove {
+        /// Path to remove.
+        path: Path,
+    },
+    /// Replaces the existing value at `path` with `value`.
+    Replace {
+        /// Path to replace.
+        path: Path,
+        /// New value.
+        value: Ipld,
+    },
+    /// Removes the value at `from` and adds it at `path`.
+    Move {
+        /// Path to move from.
+        from: Path,
+        /// Path to add at.
+        path: Path,
+    },
+    /// Adds a copy of the value at `from` at `path`.
+    Copy {
+        /// Path to copy from.
+        from: Path,
+        /// Path to add at.
+        path: Path,
+    },
+    /// Checks that the value at `path` equals `value`.
+    Test {
+        /// Path to check.
+        path: Path,
+        /// Expected value.
+        value: Ipld,
+    },
+}
+
+impl Operation {
+    /// Applies the operation to `ipld`.
+    ///
+    /// If the operation fails, `ipld` may be partially modified.
+    pub fn apply(&self, ipld: &mut Ipld) -> Result<(), PatchError> {
+        match self {
+            Self::Add { path, value } => add(ipld, path, value.clone()),
+            Self::Remove { path } => ipld.remove_path(path).map(drop).map_err(Into::into),
+            Self::Replace { path, value } => {
+                *ipld.get_path_mut(path)? = value.clone();
+                Ok(())
+            }
+            Self::Move { from, path } => {
+                if from.len() < path.len() && from.iter().zip(path.iter()).all(|(a, b)| a == b) {
+                    return Err(PatchError::MoveIntoSelf {
+                        from: from.clone(),
+                        path: path.clone(),
+                    });
+                }
+                let value = ipld.remove_path(from)?;
+                add(ipld, path, value)
+            }
+            Self::Copy { from, path } => {
+                let value = ipld.get_path(from)?.clone();
+                add(ipld, path, value)
+            }
+            Self::Test { path, value } => {
+                if ipld.get_path(path)? == value {
+                    Ok(())
+                } else {
+                    Err(PatchError::TestFailed(path.clone()))
+                }
+            }
+        }
+    }
+}
+
+fn add(ipld: &mut Ipld, path: &Path, value: Ipld) -> Result<(), PatchError> {
+    let mut parent = path.clone();
+    let last = match parent.pop() {
+        Some(last) => last,
+        None => {
+            *ipld = value;
+            return Ok(());
+        }
+    };
+    let parent = ipld.get_path_mut(&parent)?;
+    if let Ipld::List(list) = parent {
+        let index = if last == "-" {
+            Some(list.len())
+        } else {
+            last.parse().ok()
+        };
+        match index {
+            Some(index) => parent.insert(index, value)?,
+            None => return Err(TypeError::new(IpldIndex::Map(last), &*parent).into()),
+        }
+    } else {
+        parent.set(&last, value)?;
+    }
+    Ok(())
+}
+
+/// IPLD Patch, a list of operations applied in order.
+///
+/// A patch converts to and from ipld, so it can be stored as a block. Each operation is a map
+/// with the keys `op`, `path`, `from` and `value` as in JSON Patch. Paths are encoded as JSON
+/// Pointers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Patch(pub Vec<Operation>);
+
+impl Patch {
+    /// Returns a patch turning `from` into `to`.
+    ///
+    /// The patch only consists of add, remove and replace operations.
+    pub fn diff(from: &Ipld, to: &Ipld) -> Self {
+        let mut ops = Vec::new();
+        diff(&mut Path::default(), from, to, &mut ops);
+        Self(ops)
+    }
+
+    /// Applies all operations to `ipld`.
+    ///
+    /// If an operation fails, `ipld` is left unchanged.
+    pub fn apply(&self, ipld: &mut Ipld) -> Result<(), PatchError> {
+        let mut patched = ipld.clone();
+        for op in &self.0 {
+            op.apply(&mut patched)?;
+        }
+        *ipld = patched;
+        Ok(())
+    }
+}
+
+fn diff(path: &mut Path, from: &Ipld, to: &Ipld, ops: &mut Vec<Operation>) {
+    if from == to {
+        return;
+    }
+    match (from, to) {
+        (Ipld::StringMap(from), Ipld::StringMap(to)) => diff_maps(path, from, to, ops),
+        #[cfg(feature = "unleashed")]
+        (Ipld::IntegerMap(from), Ipld::IntegerMap(to)) => diff_maps(path, from, to, ops),
+        (Ipld::List(from), Ipld::List(to)) => {
+            let common = from.len().min(to.len());
+            for i in 0..common {
+                path.push(i.to_string());
+                diff(path, &from[i], &to[i], ops);
+                path.pop();
+            }
+            // removing from the end keeps the indices valid
+            for i in (common..from.len()).rev() {
+                path.push(i.to_string());
+                ops.push(Operation::Remove { path: path.clone() });
+                path.pop();
+            }
+            for (i, value) in to.iter().enumerate().skip(common) {
+                path.push(i.to_string());
+                ops.push(Operation::Add {
+                    path: path.clone(),
+                    value: value.clone(),
+                });
+                path.pop();
+            }
+        }
+        _ => ops.push(Operation::Replace {
+            path: path.clone(),
+            value: to.clone(),
+        }),
+    }
+}
+
+fn diff_maps<K: Ord + ToString>(
+    path: &mut Path,
+    from: &BTreeMap<K, Ipld>,
+    to: &BTreeMap<K, Ipld>,
+    ops: &mut Vec<Operation>,
+) {
+    for key in from.keys().filter(|key| !to.contains_key(key)) {
+        path.push(key.to_string());
+        ops.push(Operation::Remove { path: path.clone() });
+        path.pop();
+    }
+    for (key, value) in to {
+        path.push(key.to_string());
+        match from.get(key) {
+            Some(old) => diff(path, old, value, ops),
+            None => ops.push(Operation::Add {
+                path: path.clone(),
+                value: value.clone(),
+            }),
+        }
+        path.pop();
+    }
+}
+
+/// Encodes a path as a JSON Pointer.
+fn encode_path(path: &Path) -> Ipld {
+    let mut pointer = String::new();
+    for segment in path.iter() {
+        pointer.push('/');
+        pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+    }
+    Ipld::String(pointer)
+}
+
+/// Decodes a path from a JSON Pointer.
+fn decode_path(pointer: &str) -> Result<Path, PatchError> {
+    if pointer.is_empty() {
+        return Ok(Path::default());
+    }
+    let segments = pointer
+        .strip_prefix('/')
+        .ok_or_else(|| PatchError::Invalid(format!("path {:?} doesn't start with /", pointer)))?;
+    let segments: Vec<String> = segments
+        .split('/')
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    Ok(segments.into())
+}
+
+impl From<&Operation> for Ipld {
+    fn from(op: &Operation) -> Self {
+        let mut map = BTreeMap::new();
+        let (name, path) = match op {
+            Operation::Add { path, value } => {
+                map.insert("value".to_string(), value.clone());
+                ("add", path)
+            }
+            Operation::Remove { path } => ("remove", path),
+            Operation::Replace { path, value } => {
+                map.insert("value".to_string(), value.clone());
+                ("replace", path)
+            }
+            Operation::Move { from, path } => {
+                map.insert("from".to_string(), encode_path(from));
+                ("move", path)
+            }
+            Operation::Copy { from, path } => {
+                map.insert("from".to_string(), encode_path(from));
+                ("copy", path)
+            }
+            Operation::Test { path, value } => {
+                map.insert("value".to_string(), value.clone());
+                ("test", path)
+            }
+        };
+        map.insert("op".to_string(), name.into());
+        map.insert("path".to_string(), encode_path(path));
+        Ipld::StringMap(map)
+    }
+}
+
+impl TryFrom<&Ipld> for Operation {
+    type Error = PatchError;
+
+    fn try_from(ipld: &Ipld) -> Result<Self, Self::Error> {
+        let map = match ipld {
+            Ipld::StringMap(map) => map,
+            _ => return Err(PatchError::Invalid("operation is not a map".into())),
+        };
+        let string = |key: &str| match map.get(key) {
+            Some(Ipld::String(s)) => Ok(s.as_str()),
+            _ => Err(PatchError::Invalid(format!("missing string {:?}", key))),
+        };
+        let path = decode_path(string("path")?)?;
+        let from = || decode_path(string("from")?);
+        let value = || {
+            map.get("value")
+                .cloned()
+                .ok_or_else(|| PatchError::Invalid("missing value".into()))
+        };
+        Ok(match string("op")? {
+            "add" => Self::Add {
+                path,
+                value: value()?,
+            },
+            "remove" => Self::Remove { path },
+            "replace" => Self::Replace {
+                path,
+                value: value()?,
+            },
+            "move" => Self::Move {
+                from: from()?,
+                path,
+            },
+            "copy" => Self::Copy {
+                from: from()?,
+                path,
+            },
+            "test" => Self::Test {
+                path,
+                value: value()?,
+            },
+            op => return Err(PatchError::Invalid(format!("unknown op {:?}", op))),
+        })
+    }
+}
+
+impl From<&Patch> for Ipld {
+    fn from(patch: &Patch) -> Self {
+        Ipld::List(patch.0.iter().map(Ipld::from).collect())
+    }
+}
+
+impl From<Patch> for Ipld {
+    fn from(patch: Patch) -> Self {
+        Self::from(&patch)
+    }
+}
+
+impl TryFrom<&Ipld> for Patch {
+    type Error = PatchError;
+
+    fn try_from(ipld: &Ipld) -> Result<Self, Self::Error> {
+        match ipld {
+            Ipld::List(ops) => Ok(Self(
+                ops.iter()
+                    .map(Operation::try_from)
+                    .collect::<Result<_, _>>()?,
+            )),
+            _ => Err(PatchError::Invalid("patch is not a list".into())),
+        }
+    }
+}
+
+impl TryFrom<Ipld> for Patch {
+    type Error = PatchError;
+
+    fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
+        Self::try_from(&ipld)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn doc() -> Ipld {
+        let mut inner = BTreeMap::new();
+        inner.insert("a/b".to_string(), Ipld::Integer(1));
+        inner.insert("list".to_string(), Ipld::List(vec![0.into(), 1.into()]));
+        let mut map = BTreeMap::new();
+        map.insert("name".to_string(), "config".into());
+        map.insert("inner".to_string(), Ipld::StringMap(inner));
+        Ipld::StringMap(map)
+    }
+
+    #[test]
+    fn diff_apply() {
+        let from = doc();
+        let mut to = doc();
+        to.set_path(&"inner/list/0".into(), 5.into()).unwrap();
+        to.get_path_mut(&"inner/list".into())
+            .unwrap()
+            .insert(2, 2.into())
+            .unwrap();
+        to.remove_path(&"name".into()).unwrap();
+        to.set_path(&"version".into(), 2.into()).unwrap();
+
+        let patch = Patch::diff(&from, &to);
+        assert_eq!(patch.0.len(), 4);
+        let mut patched = from.clone();
+        patch.apply(&mut patched).unwrap();
+        assert_eq!(patched, to);
+        assert_eq!(Patch::diff(&to, &to), Patch::default());
+
+        let mut patched = from;
+        Patch::diff(&Ipld::Null, &to).apply(&mut patched).unwrap();
+        assert_eq!(patched, to);
+    }
+
+    #[test]
+    fn operations() {
+        let mut ipld = doc();
+        let patch = Patch(vec![
+            Operation::Move {
+                from: "name".into(),
+                path: "inner/list/-".into(),
+            },
+            Operation::Copy {
+                from: "inner/list/2".into(),
+                path: "inner/list/0".into(),
+            },
+            Operation::Test {
+                path: "inner/list".into(),
+                value: Ipld::List(vec!["config".into(), 0.into(), 1.into(), "config".into()]),
+            },
+        ]);
+        patch.apply(&mut ipld).unwrap();
+        assert!(ipld.get("name").is_err());
+
+        let original = ipld.clone();
+        let err = Patch(vec![
+            Operation::Remove {
+                path: "inner".into(),
+            },
+            Operation::Test {
+                path: "inner".into(),
+                value: Ipld::Null,
+            },
+        ])
+        .apply(&mut ipld)
+        .unwrap_err();
+        assert!(matches!(err, PatchError::TypeError(_)));
+        assert_eq!(ipld, original);
+
+        let err = Operation::Move {
+            from: "inner".into(),
+            path: "inner/list".into(),
+        }
+        .apply(&mut ipld)
+        .unwrap_err();
+        assert!(matches!(err, PatchError::MoveIntoSelf { .. }));
+    }
+
+    #[test]
+    fn ipld_roundtrip() {
+        let mut segments = Path::default();
+        segments.push("a/b");
+        segments.push("~");
+        let patch = Patch(vec![
+            Operation::Add {
+                path: segments.clone(),
+                value: Ipld::Null,
+            },
+            Operation::Remove {
+                path: Path::default(),
+            },
+            Operation::Move {
+                from: "a".into(),
+                path: "b".into(),
+            },
+        ]);
+        let ipld = Ipld::from(&patch);
+        assert_eq!(
+            ipld.get(0).unwrap().get("path").unwrap(),
+            &Ipld::String("/a~1b/~0".into())
+        );
+        assert_eq!(Patch::try_from(ipld).unwrap(), patch);
+        assert!(Patch::try_from(Ipld::List(vec![Ipld::Null])).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+//! Path
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+
+/// Represents a path in an ipld dag.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Path(Vec<String>);
+
+impl Path {
+    /// Iterate over path segments.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|s| &**s)
+    }
+
+    /// Join segment.
+    pub fn join<T: AsRef<str>>(&mut self, segment: T) {
+        for seg in segment.as_ref().split('/').filter(|s| !s.is_empty()) {
+            self.0.push(seg.to_owned())
+        }
+    }
+
+    /// Appends a single segment, which may contain slashes.
+    pub fn push<T: Into<String>>(&mut self, segment: T) {
+        self.0.push(segment.into())
+    }
+
+    /// Removes the last segment.
+    pub fn pop(&mut self) -> Option<String> {
+        self.0.pop()
+    }
+
+    /// Returns the number of segments.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if the path has no segments.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<Vec<String>> for Path {
+    fn from(segments: Vec<String>) -> Self {
+        Path(segments)
+    }
+}
+
+impl From<Vec<&str>> for Path {
+    fn from(segments: Vec<&str>) -> Self {
+        Path(segments.into_iter().map(String::from).collect())
+    }
+}
+
+impl From<&str> for Path {
+    fn from(s: &str) -> Self {
+        let mut path = Path::default();
+        path.join(s);
+        path
+    }
+}
+
+impl From<String> for Path {
+    fn from(s: String) -> Self {
+        Path::from(s.as_str())
+    }
+}
+
+impl core::fmt::Display for Path {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        for (i, seg) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("/")?;
+            }
+            f.write_str(seg)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsing_one_segment() {
+        assert_eq!(Path::from("0"), Path::from(vec!["0"]));
+    }
+
+    #[test]
+    fn test_parsing_three_segments() {
+        assert_eq!(Path::from("0/foo/2"), Path::from(vec!["0", "foo", "2"]));
+    }
+
+    #[test]
+    fn test_eliding_empty_segments() {
+        assert_eq!(Path::from("0//2"), Path::from(vec!["0", "2"]));
+    }
+
+    #[test]
+    fn test_eliding_leading_slashes() {
+        assert_eq!(Path::from("/0/2"), Path::from(vec!["0", "2"]));
+    }
+
+    #[test]
+    fn test_eliding_trailing_slashes() {
+        assert_eq!(Path::from("0/2/"), Path::from(vec!["0", "2"]));
+    }
+
+    #[test]
+    fn test_to_string() {
+        assert_eq!(Path::from(vec!["0", "foo", "2"]).to_string(), "0/foo/2");
+    }
+}
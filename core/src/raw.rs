@@ -2,10 +2,12 @@
 use crate::cid::Cid;
 use crate::codec::{Codec, Decode, Encode, References};
 use crate::error::{Result, TypeError, TypeErrorType, UnsupportedCodec};
+use crate::io::{Read, Seek, Write};
 use crate::ipld::Ipld;
+use crate::ipld_arc::IpldArc;
+use alloc::{boxed::Box, vec::Vec};
 use core::convert::TryFrom;
-use std::io::{Read, Seek, Write};
-use std::iter::Extend;
+use core::iter::Extend;
 
 /// Raw codec.
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
@@ -55,6 +57,16 @@ impl Encode<RawCodec> for Ipld {
     }
 }
 
+impl Encode<RawCodec> for IpldArc {
+    fn encode<W: Write>(&self, c: RawCodec, w: &mut W) -> Result<()> {
+        if let IpldArc::Bytes(bytes) = self {
+            bytes[..].encode(c, w)
+        } else {
+            Err(TypeError::new(TypeErrorType::Bytes, self).into())
+        }
+    }
+}
+
 impl Decode<RawCodec> for Box<[u8]> {
     fn decode<R: Read + Seek>(c: RawCodec, r: &mut R) -> Result<Self> {
         let buf: Vec<u8> = Decode::decode(c, r)?;
@@ -64,7 +76,7 @@ impl Decode<RawCodec> for Box<[u8]> {
 
 impl Decode<RawCodec> for Vec<u8> {
     fn decode<R: Read + Seek>(_: RawCodec, r: &mut R) -> Result<Self> {
-        let mut buf = vec![];
+        let mut buf = Vec::new();
         r.read_to_end(&mut buf)?;
         Ok(buf)
     }
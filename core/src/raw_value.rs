@@ -1,11 +1,10 @@
 //! misc stuff
-use std::{
-    io::{self, Read, Seek},
-    marker::PhantomData,
-};
+use alloc::{boxed::Box, vec::Vec};
+use core::marker::PhantomData;
 
 use crate::codec::{Codec, Decode, Encode};
-use std::convert::TryFrom;
+use crate::error::Result;
+use crate::io::{self, Read, Seek, Write};
 
 /// A raw value for a certain codec.
 ///
@@ -46,27 +45,69 @@ impl<C> From<RawValue<C>> for Vec<u8> {
 /// trait to implement to skip a single item at the current position
 pub trait SkipOne: Codec {
     /// assuming r is at the start of an item, advance r to the end
-    fn skip<R: Read + Seek>(&self, r: &mut R) -> anyhow::Result<()>;
+    fn skip<R: Read + Seek>(&self, r: &mut R) -> Result<()>;
 }
 
 impl<C: Codec + SkipOne> Decode<C> for RawValue<C> {
-    fn decode<R: std::io::Read + std::io::Seek>(c: C, r: &mut R) -> anyhow::Result<Self> {
-        let p0 = r.seek(io::SeekFrom::Current(0))?;
-        c.skip(r)?;
-        let p1 = r.seek(io::SeekFrom::Current(0))?;
-        // seeking backward is not allowed
-        anyhow::ensure!(p1 > p0);
-        // this will fail if usize is 4 bytes and an item is > 32 bit of length
-        let len = usize::try_from(p1 - p0)?;
-        r.seek(io::SeekFrom::Start(p0))?;
-        let mut buf = vec![0u8; len];
-        r.read_exact(&mut buf)?;
-        Ok(Self::new(buf.into()))
+    fn decode<R: Read + Seek>(c: C, r: &mut R) -> Result<Self> {
+        let mut recorder = Recorder {
+            inner: r,
+            data: Vec::new(),
+        };
+        c.skip(&mut recorder)?;
+        if recorder.data.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "empty raw value").into());
+        }
+        Ok(Self::new(recorder.data.into()))
+    }
+}
+
+/// Number of bytes skipped at once, so that a bogus length can't trigger a large allocation.
+const SKIP_CHUNK: u64 = 16 * 1024;
+
+/// Reader keeping the bytes of an item while it is skipped, so that they don't need to be read
+/// again by seeking back. Positions are relative to the start of the item.
+struct Recorder<'a, R> {
+    inner: &'a mut R,
+    data: Vec<u8>,
+}
+
+impl<R: Read> Read for Recorder<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.data.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> Seek for Recorder<'_, R> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let len = self.data.len() as u64;
+        let pos = match pos {
+            io::SeekFrom::Start(pos) => Some(pos),
+            io::SeekFrom::Current(offset) if offset >= 0 => len.checked_add(offset as u64),
+            io::SeekFrom::Current(offset) => len.checked_sub(offset.unsigned_abs()),
+            io::SeekFrom::End(_) => None,
+        }
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek"))?;
+        if pos < len {
+            // peeking seeks back, the bytes are recorded again when they are read again
+            self.inner
+                .seek(io::SeekFrom::Current(-((len - pos) as i64)))?;
+            self.data.truncate(pos as usize);
+        }
+        while (self.data.len() as u64) < pos {
+            let start = self.data.len();
+            let chunk = (pos - start as u64).min(SKIP_CHUNK) as usize;
+            self.data.resize(start + chunk, 0);
+            self.inner.read_exact(&mut self.data[start..])?;
+        }
+        Ok(pos)
     }
 }
 
 impl<C: Codec> Encode<C> for RawValue<C> {
-    fn encode<W: std::io::Write>(&self, _: C, w: &mut W) -> anyhow::Result<()> {
+    fn encode<W: Write>(&self, _: C, w: &mut W) -> Result<()> {
         w.write_all(&self.data)?;
         Ok(())
     }
@@ -77,7 +118,7 @@ impl<C: Codec> Encode<C> for RawValue<C> {
 pub struct IgnoredAny;
 
 impl<C: Codec + SkipOne> Decode<C> for IgnoredAny {
-    fn decode<R: std::io::Read + std::io::Seek>(c: C, r: &mut R) -> anyhow::Result<Self> {
+    fn decode<R: Read + Seek>(c: C, r: &mut R) -> Result<Self> {
         c.skip(r)?;
         Ok(Self)
     }
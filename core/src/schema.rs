@@ -0,0 +1,313 @@
+//! Describing types with IPLD schemas.
+use crate::cid::Cid;
+use crate::ipld::Ipld;
+use crate::ipld_ref::IpldRef;
+use crate::link::Link;
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use core::fmt;
+use core::marker::PhantomData;
+
+/// Types describing their representation with the IPLD Schema DSL.
+///
+/// `#[derive(DagCbor)]` implements this trait, so the schema of an encoding can be published
+/// with its Rust types as the source of truth. It isn't implemented for `int-tuple` unions,
+/// which the DSL can't express.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't be described with an IPLD schema",
+    note = "`#[derive(DagCbor)]` doesn't implement `IpldSchema` for `int-tuple` unions"
+)]
+pub trait IpldSchema {
+    /// Returns the schema type of `Self`, as used for struct fields or list and map values.
+    ///
+    /// Named types are added to `defs` together with the types they reference.
+    fn schema_type(defs: &mut SchemaDefs) -> String;
+
+    /// The data model kind of the representation if there is a single one.
+    ///
+    /// It's a constant, so that the kinds of kinded union members are checked at compile time.
+    const SCHEMA_KIND: Option<&'static str>;
+
+    /// Returns true if null represents a value.
+    fn schema_nullable() -> bool {
+        false
+    }
+
+    /// Returns the schema text defining `Self` and all types it references.
+    fn ipld_schema() -> String {
+        let mut defs = SchemaDefs::default();
+        Self::schema_type(&mut defs);
+        defs.to_string()
+    }
+}
+
+/// Named type definitions in the order they were first referenced.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SchemaDefs {
+    defs: Vec<(String, String)>,
+}
+
+impl SchemaDefs {
+    /// Defines the type `name` unless it's already defined and returns `name`.
+    ///
+    /// `def` returns the definition following `type <name>`. The name is reserved before `def`
+    /// is called, so recursive types terminate.
+    pub fn define(&mut self, name: &str, def: impl FnOnce(&mut Self) -> String) -> String {
+        if !self.contains(name) {
+            let i = self.defs.len();
+            self.defs.push((name.to_string(), String::new()));
+            self.defs[i].1 = def(self);
+        }
+        name.to_string()
+    }
+
+    /// Returns true if the type `name` is defined.
+    pub fn contains(&self, name: &str) -> bool {
+        self.defs.iter().any(|(n, _)| n == name)
+    }
+
+    /// Returns the definitions.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.defs.iter().map(|(n, d)| (n.as_str(), d.as_str()))
+    }
+}
+
+impl fmt::Display for SchemaDefs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (name, def)) in self.defs.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "type {} {}", name, def)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns true if `ty` names a type rather than being an anonymous list, map or link type.
+pub fn is_named(ty: &str) -> bool {
+    ty.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Returns `ty` prefixed with `nullable` if `T` represents a value as null.
+fn value_type<T: IpldSchema + ?Sized>(defs: &mut SchemaDefs) -> String {
+    let ty = T::schema_type(defs);
+    if T::schema_nullable() {
+        format!("nullable {}", ty)
+    } else {
+        ty
+    }
+}
+
+/// Returns the schema type of the type argument `T` as part of a type name.
+///
+/// `#[derive(DagCbor)]` appends it to the name of generic types, so that every instantiation
+/// defines its own type, e.g. `WrapperString` and `WrapperListInt`.
+pub fn type_arg_name<T: IpldSchema + ?Sized>(defs: &mut SchemaDefs) -> String {
+    let mut name = String::new();
+    let mut word = false;
+    for c in value_type::<T>(defs).chars() {
+        match c {
+            '[' => name.push_str("List"),
+            '{' => name.push_str("Map"),
+            '&' => name.push_str("Link"),
+            c if c.is_alphanumeric() || c == '_' => {
+                if word {
+                    name.push(c);
+                } else {
+                    name.extend(c.to_uppercase());
+                }
+                word = true;
+                continue;
+            }
+            _ => {}
+        }
+        word = false;
+    }
+    name
+}
+
+/// Kind of a member of a kinded union, which must have a single one.
+///
+/// Used by `#[derive(DagCbor)]`, so that a member without a kind is a compile time error.
+#[doc(hidden)]
+pub struct MemberKind<T: ?Sized>(PhantomData<T>);
+
+impl<T: IpldSchema + ?Sized> MemberKind<T> {
+    /// The kind of `T`.
+    pub const KIND: &'static str = match T::SCHEMA_KIND {
+        Some(kind) => kind,
+        None => panic!("kinded union members must have a single data model kind"),
+    };
+}
+
+macro_rules! impl_schema {
+    ($name:expr, $kind:expr, $($ty:ty),*) => {
+        $(
+            impl IpldSchema for $ty {
+                const SCHEMA_KIND: Option<&'static str> = Some($kind);
+
+                fn schema_type(_: &mut SchemaDefs) -> String {
+                    $name.into()
+                }
+            }
+        )*
+    };
+}
+
+impl_schema!("Bool", "bool", bool);
+impl_schema!("Int", "int", u8, u16, u32, u64, i8, i16, i32, i64, i128);
+impl_schema!("Float", "float", f32, f64);
+impl_schema!("String", "string", str, String);
+impl_schema!("Bytes", "bytes", [u8], Box<[u8]>);
+impl_schema!("&Any", "link", Cid);
+
+impl IpldSchema for Ipld {
+    const SCHEMA_KIND: Option<&'static str> = None;
+
+    fn schema_type(_: &mut SchemaDefs) -> String {
+        "Any".into()
+    }
+
+    fn schema_nullable() -> bool {
+        true
+    }
+}
+
+impl<'a> IpldSchema for IpldRef<'a> {
+    const SCHEMA_KIND: Option<&'static str> = Ipld::SCHEMA_KIND;
+
+    fn schema_type(defs: &mut SchemaDefs) -> String {
+        Ipld::schema_type(defs)
+    }
+
+    fn schema_nullable() -> bool {
+        Ipld::schema_nullable()
+    }
+}
+
+impl<T: IpldSchema> IpldSchema for Link<T> {
+    const SCHEMA_KIND: Option<&'static str> = Some("link");
+
+    fn schema_type(defs: &mut SchemaDefs) -> String {
+        let ty = T::schema_type(defs);
+        // links can only name their target type
+        if is_named(&ty) {
+            format!("&{}", ty)
+        } else {
+            "&Any".into()
+        }
+    }
+}
+
+impl<T: IpldSchema> IpldSchema for Option<T> {
+    const SCHEMA_KIND: Option<&'static str> = T::SCHEMA_KIND;
+
+    fn schema_type(defs: &mut SchemaDefs) -> String {
+        T::schema_type(defs)
+    }
+
+    fn schema_nullable() -> bool {
+        true
+    }
+}
+
+impl<T: IpldSchema> IpldSchema for Vec<T> {
+    const SCHEMA_KIND: Option<&'static str> = Some("list");
+
+    fn schema_type(defs: &mut SchemaDefs) -> String {
+        format!("[{}]", value_type::<T>(defs))
+    }
+}
+
+impl<K: IpldSchema, T: IpldSchema> IpldSchema for BTreeMap<K, T> {
+    const SCHEMA_KIND: Option<&'static str> = Some("map");
+
+    fn schema_type(defs: &mut SchemaDefs) -> String {
+        let key = K::schema_type(defs);
+        format!("{{{}:{}}}", key, value_type::<T>(defs))
+    }
+}
+
+macro_rules! impl_schema_deref {
+    ($($ty:ty),*) => {
+        $(
+            impl<'a, T: IpldSchema + ?Sized> IpldSchema for $ty {
+                const SCHEMA_KIND: Option<&'static str> = T::SCHEMA_KIND;
+
+                fn schema_type(defs: &mut SchemaDefs) -> String {
+                    T::schema_type(defs)
+                }
+
+                fn schema_nullable() -> bool {
+                    T::schema_nullable()
+                }
+            }
+        )*
+    };
+}
+
+impl_schema_deref!(&'a T, Arc<T>);
+
+impl<'a, T: IpldSchema + ToOwned + ?Sized> IpldSchema for Cow<'a, T> {
+    const SCHEMA_KIND: Option<&'static str> = T::SCHEMA_KIND;
+
+    fn schema_type(defs: &mut SchemaDefs) -> String {
+        T::schema_type(defs)
+    }
+
+    fn schema_nullable() -> bool {
+        T::schema_nullable()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Node;
+
+    impl IpldSchema for Node {
+        const SCHEMA_KIND: Option<&'static str> = Some("map");
+
+        fn schema_type(defs: &mut SchemaDefs) -> String {
+            defs.define("Node", |defs| {
+                let children = Vec::<Link<Node>>::schema_type(defs);
+                let data = BTreeMap::<String, Option<i64>>::schema_type(defs);
+                format!("struct {{\n  children {}\n  data {}\n}}", children, data)
+            })
+        }
+    }
+
+    #[test]
+    fn schema_types() {
+        let mut defs = SchemaDefs::default();
+        assert_eq!(String::schema_type(&mut defs), "String");
+        assert_eq!(<&str>::schema_type(&mut defs), "String");
+        assert_eq!(Vec::<Option<u8>>::schema_type(&mut defs), "[nullable Int]");
+        assert_eq!(Link::<Vec<u8>>::schema_type(&mut defs), "&Any");
+        assert_eq!(Option::<Ipld>::schema_type(&mut defs), "Any");
+        assert_eq!(defs, SchemaDefs::default());
+        assert_eq!(type_arg_name::<Option<String>>(&mut defs), "NullableString");
+        assert_eq!(
+            type_arg_name::<BTreeMap<String, Vec<u8>>>(&mut defs),
+            "MapStringListInt"
+        );
+        assert_eq!(type_arg_name::<Link<Node>>(&mut defs), "LinkNode");
+    }
+
+    #[test]
+    fn recursive_schema() {
+        assert_eq!(
+            Vec::<Node>::ipld_schema(),
+            "type Node struct {\n  children [&Node]\n  data {String:nullable Int}\n}\n"
+        );
+    }
+}
@@ -0,0 +1,411 @@
+//! Deserialization from [`Ipld`].
+use super::CID_SERDE_PRIVATE_IDENTIFIER;
+use crate::cid::Cid;
+use crate::error::SerdeError;
+use crate::ipld::Ipld;
+use core::convert::TryFrom;
+use serde::de::{self, Deserialize, DeserializeOwned, IntoDeserializer, Unexpected};
+use serde::forward_to_deserialize_any;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Deserializes a value from an [`Ipld`].
+///
+/// # Example
+///
+/// ```
+/// use libipld_core::ipld::Ipld;
+/// use libipld_core::serde::from_ipld;
+///
+/// let ipld = Ipld::List(vec![Ipld::Bool(true), Ipld::Bool(false)]);
+/// let bools: Vec<bool> = from_ipld(ipld).unwrap();
+/// assert_eq!(bools, vec![true, false]);
+/// ```
+pub fn from_ipld<T: DeserializeOwned>(ipld: Ipld) -> Result<T, SerdeError> {
+    T::deserialize(ipld)
+}
+
+impl<'de> Deserialize<'de> for Ipld {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(IpldVisitor)
+    }
+}
+
+struct IpldVisitor;
+
+impl<'de> de::Visitor<'de> for IpldVisitor {
+    type Value = Ipld;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("any valid ipld")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Self::Value, E> {
+        Ok(Ipld::Bool(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        Ok(Ipld::Integer(value.into()))
+    }
+
+    fn visit_i128<E: de::Error>(self, value: i128) -> Result<Self::Value, E> {
+        Ipld::try_from(value).map_err(E::custom)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        Ok(Ipld::Integer(value.into()))
+    }
+
+    fn visit_u128<E: de::Error>(self, value: u128) -> Result<Self::Value, E> {
+        Ipld::try_from(value).map_err(E::custom)
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        Ok(Ipld::Float(value))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(Ipld::String(value.to_owned()))
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<Self::Value, E> {
+        Ok(Ipld::String(value))
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Self::Value, E> {
+        Ok(Ipld::Bytes(value.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(Ipld::Bytes(value))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Ipld::Null)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Ipld::Null)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        Ipld::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        super::cid::deserialize(deserializer).map(Ipld::Link)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut list = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(elem) = seq.next_element()? {
+            list.push(elem);
+        }
+        Ok(Ipld::List(list))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut values = BTreeMap::new();
+        while let Some((key, value)) = map.next_entry()? {
+            values.insert(key, value);
+        }
+        Ok(Ipld::StringMap(values))
+    }
+}
+
+impl Ipld {
+    fn unexpected(&self) -> Unexpected<'_> {
+        match self {
+            Self::Null => Unexpected::Unit,
+            Self::Bool(b) => Unexpected::Bool(*b),
+            Self::Integer(i) => match i64::try_from(*i) {
+                Ok(i) => Unexpected::Signed(i),
+                Err(_) => Unexpected::Other("big integer"),
+            },
+            Self::Float(f) => Unexpected::Float(*f),
+            Self::String(s) => Unexpected::Str(s),
+            Self::Bytes(b) => Unexpected::Bytes(b),
+            Self::List(_) => Unexpected::Seq,
+            Self::StringMap(_) => Unexpected::Map,
+            #[cfg(feature = "unleashed")]
+            Self::IntegerMap(_) => Unexpected::Map,
+            Self::Link(_) => Unexpected::Other("link"),
+            #[cfg(feature = "unleashed")]
+            Self::Tag(_, _) => Unexpected::Other("tag"),
+        }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, SerdeError> for Ipld {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Ipld {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Self::Null => visitor.visit_unit(),
+            Self::Bool(b) => visitor.visit_bool(b),
+            Self::Integer(i) => {
+                if let Ok(u) = u64::try_from(i) {
+                    visitor.visit_u64(u)
+                } else if let Ok(i) = i64::try_from(i) {
+                    visitor.visit_i64(i)
+                } else {
+                    visitor.visit_i128(i)
+                }
+            }
+            Self::Float(f) => visitor.visit_f64(f),
+            Self::String(s) => visitor.visit_string(s),
+            Self::Bytes(b) => visitor.visit_byte_buf(b),
+            Self::List(l) => visitor.visit_seq(SeqDeserializer::new(l)),
+            Self::StringMap(m) => visitor.visit_map(MapDeserializer::new(
+                m.into_iter().map(|(k, v)| (Ipld::String(k), v)),
+            )),
+            #[cfg(feature = "unleashed")]
+            Self::IntegerMap(m) => visitor.visit_map(MapDeserializer::new(
+                m.into_iter().map(|(k, v)| (Ipld::Integer(k.into()), v)),
+            )),
+            Self::Link(cid) => visitor.visit_newtype_struct(CidDeserializer(cid)),
+            #[cfg(feature = "unleashed")]
+            Self::Tag(tag, ipld) => {
+                visitor.visit_seq(SeqDeserializer::new(vec![Ipld::Integer(tag.into()), *ipld]))
+            }
+        }
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Self::Null => visitor.visit_none(),
+            ipld => visitor.visit_some(ipld),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if name == CID_SERDE_PRIVATE_IDENTIFIER {
+            match self {
+                Self::Link(cid) => visitor.visit_newtype_struct(CidDeserializer(cid)),
+                ipld => Err(de::Error::invalid_type(ipld.unexpected(), &visitor)),
+            }
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self {
+            Self::String(variant) => visitor.visit_enum(EnumDeserializer {
+                variant,
+                value: None,
+            }),
+            Self::StringMap(map) => {
+                let mut iter = map.into_iter();
+                match (iter.next(), iter.next()) {
+                    (Some((variant, value)), None) => visitor.visit_enum(EnumDeserializer {
+                        variant,
+                        value: Some(value),
+                    }),
+                    _ => Err(de::Error::invalid_value(
+                        Unexpected::Map,
+                        &"map with a single key",
+                    )),
+                }
+            }
+            ipld => Err(de::Error::invalid_type(ipld.unexpected(), &"string or map")),
+        }
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// Deserializer handing out the binary representation of a cid.
+struct CidDeserializer(Cid);
+
+impl<'de> de::Deserializer<'de> for CidDeserializer {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_byte_buf(self.0.to_bytes())
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct SeqDeserializer {
+    iter: std::vec::IntoIter<Ipld>,
+}
+
+impl SeqDeserializer {
+    fn new(list: Vec<Ipld>) -> Self {
+        Self {
+            iter: list.into_iter(),
+        }
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for SeqDeserializer {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        match self.iter.next() {
+            Some(value) => seed.deserialize(value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct MapDeserializer<I> {
+    iter: I,
+    value: Option<Ipld>,
+}
+
+impl<I> MapDeserializer<I> {
+    fn new(iter: I) -> Self {
+        Self { iter, value: None }
+    }
+}
+
+impl<'de, I: Iterator<Item = (Ipld, Ipld)>> de::MapAccess<'de> for MapDeserializer<I> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value),
+            None => Err(de::Error::custom(
+                "next_value_seed called before next_key_seed",
+            )),
+        }
+    }
+}
+
+struct EnumDeserializer {
+    variant: String,
+    value: Option<Ipld>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer {
+    type Error = SerdeError;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let variant = seed.deserialize(Ipld::String(self.variant))?;
+        Ok((variant, VariantDeserializer(self.value)))
+    }
+}
+
+struct VariantDeserializer(Option<Ipld>);
+
+impl<'de> de::VariantAccess<'de> for VariantDeserializer {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        match self.0 {
+            None | Some(Ipld::Null) => Ok(()),
+            Some(ipld) => Err(de::Error::invalid_type(ipld.unexpected(), &"unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, Self::Error> {
+        match self.0 {
+            Some(ipld) => seed.deserialize(ipld),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Some(Ipld::List(list)) => visitor.visit_seq(SeqDeserializer::new(list)),
+            Some(ipld) => Err(de::Error::invalid_type(ipld.unexpected(), &"tuple variant")),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V: de::Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Some(ipld @ Ipld::StringMap(_)) => de::Deserializer::deserialize_any(ipld, visitor),
+            Some(ipld) => Err(de::Error::invalid_type(
+                ipld.unexpected(),
+                &"struct variant",
+            )),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Serde (de)serialization of [`Ipld`](crate::ipld::Ipld).
+//!
+//! This module makes `Ipld` a serde data format: any type implementing `Serialize` can be
+//! converted into an `Ipld` with [`to_ipld`] and any type implementing `Deserialize` can be
+//! extracted from an `Ipld` with [`from_ipld`].
+//!
+//! Serde has no notion of links. A [`Cid`] is therefore serialized as a newtype struct with the
+//! name [`CID_SERDE_PRIVATE_IDENTIFIER`] wrapping the binary representation of the cid. Data
+//! formats that understand ipld (like the one in this module) turn it into an
+//! [`Ipld::Link`](crate::ipld::Ipld::Link), all others will see a plain byte string. Use the
+//! [`Link`](crate::link::Link) type or annotate `Cid` fields with
+//! `#[serde(with = "libipld_core::serde::cid")]` to make use of it.
+use crate::error::SerdeError;
+use std::fmt;
+
+mod de;
+mod ser;
+
+pub use de::from_ipld;
+pub use ser::{to_ipld, Serializer};
+
+/// Name of the newtype struct that is used to signal a [`Cid`](crate::cid::Cid) to ipld aware
+/// data formats.
+pub const CID_SERDE_PRIVATE_IDENTIFIER: &str = "$__private__serde__identifier__for__cid";
+
+impl ::serde::ser::Error for SerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+impl ::serde::de::Error for SerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+/// Serialize and deserialize a [`Cid`](crate::cid::Cid) as an ipld link.
+///
+/// To be used with `#[serde(with = "libipld_core::serde::cid")]`.
+pub mod cid {
+    use super::CID_SERDE_PRIVATE_IDENTIFIER;
+    use crate::cid::Cid;
+    use core::convert::TryFrom;
+    use serde::{de, ser};
+    use std::fmt;
+
+    struct BytesRef<'a>(&'a [u8]);
+
+    impl<'a> ser::Serialize for BytesRef<'a> {
+        fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+
+    /// Serializes a cid.
+    pub fn serialize<S: ser::Serializer>(cid: &Cid, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer
+            .serialize_newtype_struct(CID_SERDE_PRIVATE_IDENTIFIER, &BytesRef(&cid.to_bytes()))
+    }
+
+    /// Deserializes a cid.
+    pub fn deserialize<'de, D: de::Deserializer<'de>>(deserializer: D) -> Result<Cid, D::Error> {
+        deserializer.deserialize_newtype_struct(CID_SERDE_PRIVATE_IDENTIFIER, CidVisitor)
+    }
+
+    struct CidVisitor;
+
+    impl<'de> de::Visitor<'de> for CidVisitor {
+        type Value = Cid;
+
+        fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt.write_str("a cid")
+        }
+
+        fn visit_newtype_struct<D: de::Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_bytes(self)
+        }
+
+        fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+            Cid::try_from(bytes).map_err(E::custom)
+        }
+
+        fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut bytes = Vec::new();
+            while let Some(byte) = seq.next_element::<u8>()? {
+                bytes.push(byte);
+            }
+            self.visit_bytes(&bytes)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cid::Cid;
+    use crate::ipld::Ipld;
+    use crate::link::Link;
+    use crate::multihash::{Code, MultihashDigest};
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct Person {
+        name: String,
+        age: u8,
+        nicknames: Vec<String>,
+        score: f64,
+        avatar: Option<Vec<u8>>,
+        #[serde(with = "crate::serde::cid")]
+        profile: Cid,
+        friends: Vec<Link<Person>>,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Empty,
+        Circle(u32),
+        Rect { w: u32, h: u32 },
+    }
+
+    fn cid(data: &[u8]) -> Cid {
+        Cid::new_v1(0x55, Code::Blake3_256.digest(data))
+    }
+
+    #[test]
+    fn test_struct_roundtrip() {
+        let person = Person {
+            name: "Hello World!".into(),
+            age: 52,
+            nicknames: vec!["Hello".into(), "World!".into()],
+            score: 1.5,
+            avatar: None,
+            profile: cid(b"profile"),
+            friends: vec![Link::new(cid(b"friend"))],
+        };
+        let ipld = to_ipld(&person).unwrap();
+
+        let mut map = BTreeMap::new();
+        map.insert("name".to_string(), Ipld::String("Hello World!".into()));
+        map.insert("age".to_string(), Ipld::Integer(52));
+        map.insert(
+            "nicknames".to_string(),
+            Ipld::List(vec![
+                Ipld::String("Hello".into()),
+                Ipld::String("World!".into()),
+            ]),
+        );
+        map.insert("score".to_string(), Ipld::Float(1.5));
+        map.insert("avatar".to_string(), Ipld::Null);
+        map.insert("profile".to_string(), Ipld::Link(cid(b"profile")));
+        map.insert(
+            "friends".to_string(),
+            Ipld::List(vec![Ipld::Link(cid(b"friend"))]),
+        );
+        assert_eq!(ipld, Ipld::StringMap(map));
+
+        let person2: Person = from_ipld(ipld).unwrap();
+        assert_eq!(person, person2);
+    }
+
+    #[test]
+    fn test_enum_roundtrip() {
+        for (shape, ipld) in [
+            (Shape::Empty, Ipld::String("Empty".into())),
+            (Shape::Circle(2), {
+                let mut map = BTreeMap::new();
+                map.insert("Circle".to_string(), Ipld::Integer(2));
+                Ipld::StringMap(map)
+            }),
+        ] {
+            assert_eq!(to_ipld(&shape).unwrap(), ipld);
+            assert_eq!(from_ipld::<Shape>(ipld).unwrap(), shape);
+        }
+        let rect = Shape::Rect { w: 1, h: 2 };
+        assert_eq!(from_ipld::<Shape>(to_ipld(&rect).unwrap()).unwrap(), rect);
+    }
+
+    #[test]
+    fn test_ipld_roundtrip() {
+        let mut map = BTreeMap::new();
+        map.insert("bytes".to_string(), Ipld::Bytes(vec![0, 1, 2]));
+        map.insert("link".to_string(), Ipld::Link(cid(b"link")));
+        map.insert("big".to_string(), Ipld::Integer(Ipld::INTEGER_MIN));
+        map.insert("neg".to_string(), Ipld::Integer(-1));
+        map.insert("null".to_string(), Ipld::Null);
+        let ipld = Ipld::StringMap(map);
+        assert_eq!(to_ipld(&ipld).unwrap(), ipld);
+        assert_eq!(from_ipld::<Ipld>(ipld.clone()).unwrap(), ipld);
+
+        let big = Ipld::Integer(Ipld::INTEGER_MAX + 1);
+        assert!(to_ipld(&big).is_err());
+        assert!(from_ipld::<Ipld>(big).is_err());
+    }
+
+    #[test]
+    fn test_type_errors() {
+        assert!(from_ipld::<u8>(Ipld::Integer(256)).is_err());
+        assert!(from_ipld::<String>(Ipld::Bool(true)).is_err());
+        assert!(from_ipld::<Link<Person>>(Ipld::Bytes(vec![0])).is_err());
+
+        let mut map = BTreeMap::new();
+        map.insert(1u32, true);
+        assert!(to_ipld(&map).is_err());
+    }
+}
@@ -0,0 +1,313 @@
+//! Walking ipld with paths.
+use crate::ipld::Ipld;
+use crate::path::Path;
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Controls how a walk continues after entering a node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+    /// Continue with the children of the node.
+    Continue,
+    /// Skip the children of the node.
+    Skip,
+    /// End the walk.
+    Stop,
+}
+
+/// Visits ipld nodes in pre-order.
+pub trait Visitor<'a> {
+    /// Called before the children of `ipld` are visited.
+    fn enter(&mut self, path: &Path, ipld: &'a Ipld) -> Control {
+        let _ = (path, ipld);
+        Control::Continue
+    }
+
+    /// Called after the children of `ipld` were visited or skipped.
+    fn leave(&mut self, path: &Path, ipld: &'a Ipld) {
+        let _ = (path, ipld);
+    }
+}
+
+/// Visits ipld nodes in pre-order, allowing them to be rewritten.
+pub trait VisitorMut {
+    /// Called before the children of `ipld` are visited.
+    ///
+    /// If `ipld` is replaced, the children of the replacement are visited.
+    fn enter(&mut self, path: &Path, ipld: &mut Ipld) -> Control {
+        let _ = (path, ipld);
+        Control::Continue
+    }
+
+    /// Called after the children of `ipld` were visited or skipped.
+    fn leave(&mut self, path: &Path, ipld: &mut Ipld) {
+        let _ = (path, ipld);
+    }
+}
+
+impl<F: FnMut(&Path, &mut Ipld) -> Control> VisitorMut for F {
+    fn enter(&mut self, path: &Path, ipld: &mut Ipld) -> Control {
+        self(path, ipld)
+    }
+}
+
+type Children<'a> = Box<dyn Iterator<Item = (Option<String>, &'a Ipld)> + 'a>;
+
+/// Returns the children of `ipld` with their path segments.
+///
+/// Tags are transparent, their value has no path segment.
+fn children(ipld: &Ipld) -> Option<Children<'_>> {
+    match ipld {
+        Ipld::List(list) => Some(Box::new(
+            list.iter()
+                .enumerate()
+                .map(|(i, ipld)| (Some(i.to_string()), ipld)),
+        )),
+        Ipld::StringMap(map) => Some(Box::new(
+            map.iter().map(|(key, ipld)| (Some(key.clone()), ipld)),
+        )),
+        #[cfg(feature = "unleashed")]
+        Ipld::IntegerMap(map) => Some(Box::new(
+            map.iter().map(|(key, ipld)| (Some(key.to_string()), ipld)),
+        )),
+        #[cfg(feature = "unleashed")]
+        Ipld::Tag(_, ipld) => Some(Box::new(core::iter::once((None, &**ipld)))),
+        _ => None,
+    }
+}
+
+/// Iterator over ipld nodes and their paths in pre-order.
+///
+/// Returned by [`Ipld::walk`].
+pub struct Walk<'a> {
+    root: Option<&'a Ipld>,
+    last: Option<&'a Ipld>,
+    stack: Vec<Children<'a>>,
+    segments: Vec<Option<String>>,
+}
+
+impl<'a> Walk<'a> {
+    pub(crate) fn new(ipld: &'a Ipld) -> Self {
+        Self {
+            root: Some(ipld),
+            last: None,
+            stack: Vec::new(),
+            segments: Vec::new(),
+        }
+    }
+
+    /// Skips the children of the node returned last.
+    pub fn skip_children(&mut self) {
+        self.last = None;
+    }
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = (Path, &'a Ipld);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(root) = self.root.take() {
+            self.last = Some(root);
+            return Some((Path::default(), root));
+        }
+        if let Some(children) = self.last.take().and_then(children) {
+            self.stack.push(children);
+        }
+        while let Some(children) = self.stack.last_mut() {
+            if let Some((segment, ipld)) = children.next() {
+                self.segments.truncate(self.stack.len() - 1);
+                self.segments.push(segment);
+                self.last = Some(ipld);
+                let path: Vec<String> = self.segments.iter().flatten().cloned().collect();
+                return Some((path.into(), ipld));
+            }
+            self.stack.pop();
+        }
+        None
+    }
+}
+
+pub(crate) fn visit<'a, V: Visitor<'a>>(path: &mut Path, ipld: &'a Ipld, v: &mut V) -> Control {
+    match v.enter(path, ipld) {
+        Control::Stop => return Control::Stop,
+        Control::Skip => {}
+        Control::Continue => {
+            if let Some(children) = children(ipld) {
+                for (segment, child) in children {
+                    let pushed = segment.map(|segment| path.push(segment)).is_some();
+                    let control = visit(path, child, v);
+                    if pushed {
+                        path.pop();
+                    }
+                    if control == Control::Stop {
+                        return Control::Stop;
+                    }
+                }
+            }
+        }
+    }
+    v.leave(path, ipld);
+    Control::Continue
+}
+
+pub(crate) fn visit_mut<V: VisitorMut + ?Sized>(
+    path: &mut Path,
+    ipld: &mut Ipld,
+    v: &mut V,
+) -> Control {
+    match v.enter(path, ipld) {
+        Control::Stop => return Control::Stop,
+        Control::Skip => {}
+        Control::Continue => {
+            let control = match ipld {
+                Ipld::List(list) => visit_children(
+                    path,
+                    list.iter_mut()
+                        .enumerate()
+                        .map(|(i, ipld)| (Some(i.to_string()), ipld)),
+                    v,
+                ),
+                Ipld::StringMap(map) => visit_children(
+                    path,
+                    map.iter_mut().map(|(key, ipld)| (Some(key.clone()), ipld)),
+                    v,
+                ),
+                #[cfg(feature = "unleashed")]
+                Ipld::IntegerMap(map) => visit_children(
+                    path,
+                    map.iter_mut()
+                        .map(|(key, ipld)| (Some(key.to_string()), ipld)),
+                    v,
+                ),
+                #[cfg(feature = "unleashed")]
+                Ipld::Tag(_, ipld) => {
+                    visit_children(path, core::iter::once((None, &mut **ipld)), v)
+                }
+                _ => Control::Continue,
+            };
+            if control == Control::Stop {
+                return Control::Stop;
+            }
+        }
+    }
+    v.leave(path, ipld);
+    Control::Continue
+}
+
+fn visit_children<'a, V: VisitorMut + ?Sized>(
+    path: &mut Path,
+    children: impl Iterator<Item = (Option<String>, &'a mut Ipld)>,
+    v: &mut V,
+) -> Control {
+    for (segment, child) in children {
+        let pushed = segment.map(|segment| path.push(segment)).is_some();
+        let control = visit_mut(path, child, v);
+        if pushed {
+            path.pop();
+        }
+        if control == Control::Stop {
+            return Control::Stop;
+        }
+    }
+    Control::Continue
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cid::Cid;
+    use crate::multihash::{Code, MultihashDigest};
+    use alloc::{collections::BTreeMap, vec};
+
+    fn ipld() -> Ipld {
+        let mut map = BTreeMap::new();
+        map.insert(
+            "a".to_string(),
+            Ipld::List(vec![Ipld::Integer(1), Ipld::Null]),
+        );
+        map.insert("b".to_string(), Ipld::Bool(true));
+        Ipld::StringMap(map)
+    }
+
+    #[test]
+    fn walk() {
+        let ipld = ipld();
+        let paths: Vec<_> = ipld.walk().map(|(path, _)| path.to_string()).collect();
+        assert_eq!(paths, ["", "a", "a/0", "a/1", "b"]);
+
+        let mut walk = ipld.walk();
+        let mut paths = Vec::new();
+        while let Some((path, ipld)) = walk.next() {
+            if let Ipld::List(_) = ipld {
+                walk.skip_children();
+            }
+            paths.push(path.to_string());
+        }
+        assert_eq!(paths, ["", "a", "b"]);
+    }
+
+    #[test]
+    fn visitor() {
+        struct Events(Vec<String>);
+
+        impl<'a> Visitor<'a> for Events {
+            fn enter(&mut self, path: &Path, ipld: &'a Ipld) -> Control {
+                self.0.push(format!("enter {}", path));
+                match ipld {
+                    Ipld::Bool(_) => Control::Stop,
+                    Ipld::Integer(_) => Control::Skip,
+                    _ => Control::Continue,
+                }
+            }
+
+            fn leave(&mut self, path: &Path, _: &'a Ipld) {
+                self.0.push(format!("leave {}", path));
+            }
+        }
+
+        let mut events = Events(Vec::new());
+        ipld().visit(&mut events);
+        assert_eq!(
+            events.0,
+            [
+                "enter ",
+                "enter a",
+                "enter a/0",
+                "leave a/0",
+                "enter a/1",
+                "leave a/1",
+                "leave a",
+                "enter b",
+            ]
+        );
+    }
+
+    #[test]
+    fn walk_mut() {
+        let old = Cid::new_v1(0x55, Code::Sha2_256.digest(b"old"));
+        let new = Cid::new_v1(0x55, Code::Sha2_256.digest(b"new"));
+        let mut ipld = Ipld::List(vec![
+            Ipld::Link(old),
+            Ipld::List(vec![Ipld::Link(old), Ipld::Link(new)]),
+        ]);
+        let mut replaced = Vec::new();
+        ipld.walk_mut(|path: &Path, ipld: &mut Ipld| {
+            if *ipld == Ipld::Link(old) {
+                *ipld = Ipld::Link(new);
+                replaced.push(path.to_string());
+            }
+            Control::Continue
+        });
+        assert_eq!(replaced, ["0", "1/0"]);
+        assert_eq!(
+            ipld,
+            Ipld::List(vec![
+                Ipld::Link(new),
+                Ipld::List(vec![Ipld::Link(new), Ipld::Link(new)]),
+            ])
+        );
+    }
+}
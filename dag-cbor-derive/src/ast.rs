@@ -41,6 +41,7 @@ pub struct StructField {
     pub rename: Option<String>,
     pub default: Option<Box<syn::Expr>>,
     pub binding: syn::Ident,
+    pub ty: syn::Type,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
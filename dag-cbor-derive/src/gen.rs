@@ -4,15 +4,15 @@ use quote::quote;
 
 pub fn gen_encode(ast: &SchemaType) -> TokenStream {
     let (ident, generics, body) = match ast {
-        SchemaType::Struct(s) => (&s.name, s.generics.as_ref().unwrap(), gen_encode_struct(&s)),
-        SchemaType::Union(u) => (&u.name, &u.generics, gen_encode_union(&u)),
+        SchemaType::Struct(s) => (&s.name, s.generics.as_ref().unwrap(), gen_encode_struct(s)),
+        SchemaType::Union(u) => (&u.name, &u.generics, gen_encode_union(u)),
     };
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     let trait_name = quote!(libipld::codec::Encode<libipld::cbor::DagCborCodec>);
 
     quote! {
         impl#impl_generics #trait_name for #ident #ty_generics #where_clause {
-            fn encode<W: std::io::Write>(
+            fn encode<W: libipld::io::Write>(
                 &self,
                 c: libipld::cbor::DagCborCodec,
                 w: &mut W,
@@ -26,42 +26,119 @@ pub fn gen_encode(ast: &SchemaType) -> TokenStream {
 }
 
 pub fn gen_decode(ast: &SchemaType) -> TokenStream {
-    let (ident, generics, body) = match ast {
-        SchemaType::Struct(s) => (&s.name, s.generics.as_ref().unwrap(), gen_decode_struct(&s)),
-        SchemaType::Union(u) => (&u.name, &u.generics, gen_decode_union(&u)),
+    let generics = match ast {
+        SchemaType::Struct(s) => s.generics.as_ref().unwrap(),
+        SchemaType::Union(u) => &u.generics,
+    };
+    if generics.lifetimes().next().is_some() {
+        return gen_decode_ref(ast);
+    }
+    let decode = quote!(Decode::decode(c, r));
+    let (ident, body) = match ast {
+        SchemaType::Struct(s) => (&s.name, gen_decode_struct(s, &decode)),
+        SchemaType::Union(u) => (&u.name, gen_decode_union(u, &decode, false)),
     };
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     let trait_name = quote!(libipld::codec::Decode<libipld::cbor::DagCborCodec>);
+    let ref_generics = de_generics(generics, false);
+    let (ref_impl_generics, _, _) = ref_generics.split_for_impl();
+    let ref_trait_name = quote!(libipld::codec::DecodeRef<'__de, libipld::cbor::DagCborCodec>);
 
     quote! {
         impl#impl_generics #trait_name for #ident #ty_generics #where_clause {
-            fn decode<R: std::io::Read + std::io::Seek>(
+            fn decode<R: libipld::io::Read + libipld::io::Seek>(
                 c: libipld::cbor::DagCborCodec,
                 r: &mut R,
             ) -> libipld::Result<Self> {
-                use libipld::cbor::decode::{read_len, read_u8, read_u64};
-                use libipld::cbor::error::{LengthOutOfRange, MissingKey, UnexpectedCode, UnexpectedKey};
+                use libipld::cbor::decode::{check_key_order, read_len, read_u8, read_u64};
+                use libipld::cbor::error::{
+                    IndefiniteLength, LengthOutOfRange, MissingKey, Undefined, UnexpectedCode,
+                    UnexpectedKey,
+                };
                 use libipld::codec::Decode;
                 use libipld::error::Result;
-                use std::io::SeekFrom;
+                use libipld::io::{Seek, SeekFrom};
+                let c = c.check_allocation(r)?;
                 #body
             }
         }
+
+        impl#ref_impl_generics #ref_trait_name for #ident #ty_generics #where_clause {
+            fn decode_ref(
+                c: libipld::cbor::DagCborCodec,
+                r: &mut libipld::io::Cursor<&'__de [u8]>,
+            ) -> libipld::Result<Self> {
+                libipld::codec::Decode::decode(c, r)
+            }
+        }
     }
 }
 
-fn rename(name: &syn::Member, rename: Option<&String>) -> TokenStream {
+/// Generates only a `DecodeRef` impl for types with lifetimes, which may borrow from the input.
+fn gen_decode_ref(ast: &SchemaType) -> TokenStream {
+    let decode = quote!(DecodeRef::decode_ref(c, r));
+    let (ident, generics, body) = match ast {
+        SchemaType::Struct(s) => (
+            &s.name,
+            s.generics.as_ref().unwrap(),
+            gen_decode_struct(s, &decode),
+        ),
+        SchemaType::Union(u) => (&u.name, &u.generics, gen_decode_union(u, &decode, true)),
+    };
+    let ref_generics = de_generics(generics, true);
+    let (impl_generics, _, _) = ref_generics.split_for_impl();
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+    let trait_name = quote!(libipld::codec::DecodeRef<'__de, libipld::cbor::DagCborCodec>);
+
+    quote! {
+        impl#impl_generics #trait_name for #ident #ty_generics #where_clause {
+            fn decode_ref(
+                c: libipld::cbor::DagCborCodec,
+                r: &mut libipld::io::Cursor<&'__de [u8]>,
+            ) -> libipld::Result<Self> {
+                use libipld::cbor::decode::{check_key_order, read_len, read_u8, read_u64};
+                use libipld::cbor::error::{
+                    IndefiniteLength, LengthOutOfRange, MissingKey, Undefined, UnexpectedCode,
+                    UnexpectedKey,
+                };
+                use libipld::codec::{Decode, DecodeRef};
+                use libipld::error::Result;
+                use libipld::io::{Seek, SeekFrom};
+                let c = c.check_allocation(r)?;
+                #body
+            }
+        }
+    }
+}
+
+/// Adds the `'__de` lifetime of the input to `generics`, outliving all lifetimes of the type if
+/// `borrow` is set.
+fn de_generics(generics: &syn::Generics, borrow: bool) -> syn::Generics {
+    let mut de: syn::LifetimeDef = syn::parse_quote!('__de);
+    if borrow {
+        de.bounds = generics.lifetimes().map(|l| l.lifetime.clone()).collect();
+    }
+    let mut generics = generics.clone();
+    generics.params.insert(0, syn::GenericParam::Lifetime(de));
+    generics
+}
+
+fn key(name: &syn::Member, rename: Option<&String>) -> String {
     if let Some(rename) = rename {
-        quote!(#rename)
+        rename.clone()
     } else {
-        let name = match name {
+        match name {
             syn::Member::Named(ident) => ident.to_string(),
             syn::Member::Unnamed(index) => index.index.to_string(),
-        };
-        quote!(#name)
+        }
     }
 }
 
+fn rename(name: &syn::Member, rename: Option<&String>) -> TokenStream {
+    let key = key(name, rename);
+    quote!(#key)
+}
+
 fn default(binding: &syn::Ident, default: Option<&syn::Expr>, tokens: TokenStream) -> TokenStream {
     if let Some(default) = default {
         quote! {
@@ -96,7 +173,6 @@ fn gen_encode_struct_body(s: &Struct) -> TokenStream {
             let dfields = s.fields.iter().filter_map(|field| {
                 if let Some(default) = field.default.as_ref() {
                     let binding = &field.binding;
-                    let default = &*default;
                     Some(quote! {
                         if #binding == &#default {
                             len -= 1;
@@ -106,23 +182,48 @@ fn gen_encode_struct_body(s: &Struct) -> TokenStream {
                     None
                 }
             });
-            let fields = s.fields.iter().map(|field| {
-                let key = rename(&field.name, field.rename.as_ref());
-                let binding = &field.binding;
-                default(
-                    binding,
-                    field.default.as_deref(),
-                    quote! {
-                        Encode::encode(#key, c, w)?;
-                        Encode::encode(#binding, c, w)?;
-                    },
-                )
-            });
+            let fields: Vec<_> = s
+                .fields
+                .iter()
+                .map(|field| {
+                    let key = key(&field.name, field.rename.as_ref());
+                    let binding = &field.binding;
+                    let tokens = default(
+                        binding,
+                        field.default.as_deref(),
+                        quote! {
+                            Encode::encode(#key, c, w)?;
+                            Encode::encode(#binding, c, w)?;
+                        },
+                    );
+                    (key, tokens)
+                })
+                .collect();
+            // the conformant encoding sorts keys length first
+            let mut sorted: Vec<_> = fields.iter().collect();
+            sorted.sort_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+            let legacy = fields.iter().map(|(_, tokens)| tokens);
+            let conformant = sorted.iter().map(|(_, tokens)| tokens);
+            let fields = if fields
+                .iter()
+                .map(|(k, _)| k)
+                .eq(sorted.iter().map(|(k, _)| k))
+            {
+                quote!(#(#legacy)*)
+            } else {
+                quote! {
+                    if c.version() == libipld::cbor::EncodingVersion::Legacy {
+                        #(#legacy)*
+                    } else {
+                        #(#conformant)*
+                    }
+                }
+            };
             quote! {
                 let mut len = #len;
                 #(#dfields)*
                 write_u64(w, 5, len)?;
-                #(#fields)*
+                #fields
             }
         }
         StructRepr::Tuple => {
@@ -207,7 +308,7 @@ fn gen_encode_union(u: &Union) -> TokenStream {
     }
 }
 
-fn gen_decode_struct(s: &Struct) -> TokenStream {
+fn gen_decode_struct(s: &Struct, decode: &TokenStream) -> TokenStream {
     let len = s.fields.len();
     let construct = &*s.construct;
     match s.repr {
@@ -232,18 +333,21 @@ fn gen_decode_struct(s: &Struct) -> TokenStream {
                 })
                 .collect();
             quote! {
+                let c = c.nested::<Self>()?;
                 let major = read_u8(r)?;
                 match major {
                     0xa0..=0xbb => {
-                        let len = read_len(r, major - 0xa0)?;
+                        let len = read_len(c, r, major - 0xa0)?;
                         if len > #len {
                             return Err(LengthOutOfRange::new::<Self>().into());
                         }
                         #(let mut #binding = None;)*
+                        let mut prev_key = None;
                         for _ in 0..len {
                             let mut key: String = Decode::decode(c, r)?;
+                            check_key_order::<Self>(c, &mut prev_key, key.as_bytes())?;
                             match key.as_str() {
-                                #(#key => { #binding = Some(Decode::decode(c, r)?); })*
+                                #(#key => { #binding = Some(#decode?); })*
                                 _ => {
                                     libipld::Ipld::decode(c, r)?;
                                     //return Err(UnexpectedKey::new::<Self>(key).into()),
@@ -256,6 +360,9 @@ fn gen_decode_struct(s: &Struct) -> TokenStream {
                         return Ok(#construct);
                     }
                     0xbf => {
+                        if c.is_strict() {
+                            return Err(IndefiniteLength::new::<Self>().into());
+                        }
                         #(let mut #binding = None;)*
                         loop {
                             let major = read_u8(r)?;
@@ -265,7 +372,7 @@ fn gen_decode_struct(s: &Struct) -> TokenStream {
                             r.seek(SeekFrom::Current(-1))?;
                             let key = String::decode(c, r)?;
                             match key.as_str() {
-                                #(#key => { #binding = Some(Decode::decode(c, r)?); })*
+                                #(#key => { #binding = Some(#decode?); })*
                                 _ => {
                                     libipld::Ipld::decode(c, r)?;
                                     //return Err(UnexpectedKey::new::<Self>(key).into()),
@@ -287,14 +394,15 @@ fn gen_decode_struct(s: &Struct) -> TokenStream {
             let fields = s.fields.iter().map(|field| {
                 let binding = &field.binding;
                 quote! {
-                    let #binding = Decode::decode(c, r)?;
+                    let #binding = #decode?;
                 }
             });
             quote! {
+                let c = c.nested::<Self>()?;
                 let major = read_u8(r)?;
                 match major {
                     0x80..=0x9b => {
-                        let len = read_len(r, major - 0x80)?;
+                        let len = read_len(c, r, major - 0x80)?;
                         if len != #len {
                             return Err(LengthOutOfRange::new::<Self>().into());
                         }
@@ -311,7 +419,7 @@ fn gen_decode_struct(s: &Struct) -> TokenStream {
             assert_eq!(s.fields.len(), 1);
             let binding = &s.fields[0].binding;
             quote! {
-                let #binding = Decode::decode(c, r)?;
+                let #binding = #decode?;
                 return Ok(#construct);
             }
         }
@@ -320,6 +428,9 @@ fn gen_decode_struct(s: &Struct) -> TokenStream {
             quote! {
                 let major = read_u8(r)?;
                 match major {
+                    0xf7 if c.is_strict() => {
+                        return Err(Undefined.into());
+                    }
                     0xf6..=0xf7 => {
                         return Ok(#construct);
                     }
@@ -332,12 +443,16 @@ fn gen_decode_struct(s: &Struct) -> TokenStream {
     }
 }
 
-fn gen_decode_union(u: &Union) -> TokenStream {
+/// Generates the body decoding a union.
+///
+/// Kinded unions only seek back over a member that failed to decode if `from_cursor` is set,
+/// otherwise the members matching the kind of the data item are tried on a copy of it.
+fn gen_decode_union(u: &Union, decode: &TokenStream, from_cursor: bool) -> TokenStream {
     match u.repr {
         UnionRepr::Keyed => {
             let variants = u.variants.iter().map(|s| {
                 let key = rename(&syn::Member::Named(s.name.clone()), s.rename.as_ref());
-                let parse = gen_decode_struct(s);
+                let parse = gen_decode_struct(s, decode);
                 quote! {
                     if key.as_str() == #key {
                         #parse
@@ -345,6 +460,7 @@ fn gen_decode_union(u: &Union) -> TokenStream {
                 }
             });
             quote! {
+                let c = c.nested::<Self>()?;
                 let major = read_u8(r)?;
                 if major != 0xa1 {
                     return Err(UnexpectedCode::new::<Self>(major).into());
@@ -354,18 +470,18 @@ fn gen_decode_union(u: &Union) -> TokenStream {
                 Err(UnexpectedKey::new::<Self>(key).into())
             }
         }
-        UnionRepr::Kinded => {
+        UnionRepr::Kinded if from_cursor => {
             let variants = u.variants.iter().map(|s| {
-                let parse = gen_decode_struct(s);
+                let parse = gen_decode_struct(s, decode);
                 quote! {
-                    let pos = r.seek(SeekFrom::Current(0))?;
+                    let pos = r.position();
                     let result: Result<Self> = (|| {
                         #parse
                     })();
                     match result {
                         Ok(res) => return Ok(res),
                         Err(err) => {
-                            r.seek(SeekFrom::Start(pos))?;
+                            r.set_position(pos);
                         }
                     };
                 }
@@ -375,6 +491,57 @@ fn gen_decode_union(u: &Union) -> TokenStream {
                 Err(UnexpectedCode::new::<Self>(read_u8(r)?).into())
             }
         }
+        UnionRepr::Kinded => {
+            // the kinds of members wrapping a type parameter aren't known without a bound
+            let generic = u.generics.type_params().next().is_some();
+            let matches = u.variants.iter().map(|s| {
+                let (kind, nullable) = if generic && s.repr == StructRepr::Value {
+                    (quote!(None), quote!(true))
+                } else {
+                    (struct_kind(s), struct_nullable(s))
+                };
+                quote!(kind_matches(kind, #kind, #nullable))
+            });
+            let only = u.variants.iter().enumerate().map(|(i, s)| {
+                let parse = gen_decode_struct(s, decode);
+                quote! {
+                    if candidates[#i] {
+                        return (|| {
+                            #parse
+                        })();
+                    }
+                }
+            });
+            let variants = u.variants.iter().enumerate().map(|(i, s)| {
+                let parse = gen_decode_struct(s, decode);
+                quote! {
+                    if candidates[#i] {
+                        let r = &mut libipld::io::Cursor::new(raw.as_ref());
+                        let result: Result<Self> = (|| {
+                            #parse
+                        })();
+                        if let Ok(res) = result {
+                            return Ok(res);
+                        }
+                    }
+                }
+            });
+            quote! {
+                use libipld::cbor::decode::{kind_matches, peek_kind};
+                use libipld::schema::IpldSchema;
+                let kind = peek_kind(r)?;
+                let candidates = [#(#matches),*];
+                if candidates.iter().filter(|candidate| **candidate).count() <= 1 {
+                    #(#only)*
+                    return Err(UnexpectedCode::new::<Self>(read_u8(r)?).into());
+                }
+                // the data item is copied, so that `r` doesn't need to seek back over it when a
+                // member fails to decode
+                let raw = libipld::raw_value::RawValue::<libipld::cbor::DagCborCodec>::decode(c, r)?;
+                #(#variants)*
+                Err(UnexpectedCode::new::<Self>(raw.as_ref()[0]).into())
+            }
+        }
         UnionRepr::String => {
             let arms = u.variants.iter().map(|v| {
                 let pat = &*v.pat;
@@ -406,10 +573,11 @@ fn gen_decode_union(u: &Union) -> TokenStream {
         }
         UnionRepr::IntTuple => {
             let variants = u.variants.iter().enumerate().map(|(i, s)| {
-                let parse = gen_decode_struct(s);
+                let parse = gen_decode_struct(s, decode);
                 quote!(#i => { #parse })
             });
             quote! {
+                let c = c.nested::<Self>()?;
                 let major = read_u8(r)?;
                 if major != 0x82 {
                     return Err(UnexpectedCode::new::<Self>(major).into());
@@ -423,3 +591,289 @@ fn gen_decode_union(u: &Union) -> TokenStream {
         }
     }
 }
+
+pub fn gen_schema(ast: &SchemaType) -> TokenStream {
+    let (ident, generics, def, kind, nullable) = match ast {
+        // the schema dsl has no union represented as an index and a value, so asking for the
+        // schema of an int-tuple union fails to compile
+        SchemaType::Union(u) if u.repr == UnionRepr::IntTuple => return TokenStream::new(),
+        SchemaType::Struct(s) => (
+            &s.name,
+            s.generics.as_ref().unwrap(),
+            gen_schema_struct(s),
+            struct_kind(s),
+            struct_nullable(s),
+        ),
+        SchemaType::Union(u) => (
+            &u.name,
+            &u.generics,
+            gen_schema_union(u),
+            union_kind(u),
+            union_nullable(u),
+        ),
+    };
+    let name = ident.to_string();
+    let mut generics = generics.clone();
+    let params: Vec<_> = generics.type_params().map(|p| p.ident.clone()).collect();
+    let where_clause = generics.make_where_clause();
+    for param in &params {
+        where_clause
+            .predicates
+            .push(syn::parse_quote!(#param: libipld::schema::IpldSchema));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    // members of generic unions are only checked when their schema is built
+    let check = match ast {
+        SchemaType::Union(u) if u.repr == UnionRepr::Kinded && u.generics.params.is_empty() => {
+            let kinds = u.variants.iter().map(member_kind);
+            quote! {
+                const _: () = {
+                    #(let _ = #kinds;)*
+                };
+            }
+        }
+        _ => TokenStream::new(),
+    };
+
+    quote! {
+        #check
+
+        impl#impl_generics libipld::schema::IpldSchema for #ident #ty_generics #where_clause {
+            fn schema_type(defs: &mut libipld::schema::SchemaDefs) -> String {
+                use libipld::schema::IpldSchema;
+                let name = [
+                    String::from(#name),
+                    #(libipld::schema::type_arg_name::<#params>(defs),)*
+                ]
+                .concat();
+                defs.define(&name, |defs| #def)
+            }
+
+            const SCHEMA_KIND: Option<&'static str> = #kind;
+
+            fn schema_nullable() -> bool {
+                use libipld::schema::IpldSchema;
+                #nullable
+            }
+        }
+    }
+}
+
+/// Returns the name of a field in the schema.
+fn schema_field_name(name: &syn::Member) -> String {
+    match name {
+        syn::Member::Named(ident) => ident.to_string().trim_start_matches("r#").to_string(),
+        syn::Member::Unnamed(index) => format!("_{}", index.index),
+    }
+}
+
+/// Returns the schema literal of an implicit value, if the default is a literal.
+fn schema_literal(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Lit(lit) => match &lit.lit {
+            syn::Lit::Bool(b) => Some(b.value.to_string()),
+            syn::Lit::Int(i) => Some(i.base10_digits().to_string()),
+            syn::Lit::Str(s) => Some(format!("{:?}", s.value())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Generates an expression returning the schema definition of a struct.
+fn gen_schema_struct(s: &Struct) -> TokenStream {
+    let fields = s.fields.iter().map(|field| {
+        let ty = &field.ty;
+        let field_name = schema_field_name(&field.name);
+        let key = key(&field.name, field.rename.as_ref());
+        let mut params = Vec::new();
+        if s.repr == StructRepr::Map && key != field_name {
+            params.push(format!("rename {:?}", key));
+        }
+        let (optional, nullable) = match (&s.repr, field.default.as_deref()) {
+            (StructRepr::Map, Some(syn::Expr::Path(path))) if path.path.is_ident("None") => {
+                (true, false)
+            }
+            (StructRepr::Map, Some(default)) => match schema_literal(default) {
+                Some(literal) => {
+                    params.push(format!("implicit {}", literal));
+                    (false, true)
+                }
+                None => (true, true),
+            },
+            _ => (false, true),
+        };
+        let optional = if optional { "optional " } else { "" };
+        let params = if params.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", params.join(" "))
+        };
+        quote! {
+            let ty = <#ty as IpldSchema>::schema_type(defs);
+            let nullable = #nullable && <#ty as IpldSchema>::schema_nullable();
+            let nullable = if nullable { "nullable " } else { "" };
+            def.push_str(&format!("  {} {}{}{}{}\n", #field_name, #optional, nullable, ty, #params));
+        }
+    });
+    match s.repr {
+        StructRepr::Map | StructRepr::Tuple => {
+            let repr = if s.repr == StructRepr::Tuple {
+                " representation tuple"
+            } else {
+                ""
+            };
+            quote! {{
+                let mut def = String::from("struct {\n");
+                #(#fields)*
+                def.push('}');
+                def.push_str(#repr);
+                def
+            }}
+        }
+        StructRepr::Value => {
+            let ty = &s.fields[0].ty;
+            quote! {{
+                let ty = <#ty as IpldSchema>::schema_type(defs);
+                if libipld::schema::is_named(&ty) {
+                    format!("= {}", ty)
+                } else {
+                    ty
+                }
+            }}
+        }
+        StructRepr::Null => quote!(String::from("unit representation null")),
+    }
+}
+
+fn struct_kind(s: &Struct) -> TokenStream {
+    match s.repr {
+        StructRepr::Map => quote!(Some("map")),
+        StructRepr::Tuple => quote!(Some("list")),
+        StructRepr::Value => {
+            let ty = &s.fields[0].ty;
+            quote!(<#ty as libipld::schema::IpldSchema>::SCHEMA_KIND)
+        }
+        StructRepr::Null => quote!(Some("null")),
+    }
+}
+
+/// Returns the kind of a kinded union member, which fails to compile if it has no single kind.
+fn member_kind(s: &Struct) -> TokenStream {
+    match s.repr {
+        StructRepr::Map => quote!("map"),
+        StructRepr::Tuple => quote!("list"),
+        StructRepr::Value => {
+            let ty = &s.fields[0].ty;
+            quote!(libipld::schema::MemberKind::<#ty>::KIND)
+        }
+        StructRepr::Null => quote!("null"),
+    }
+}
+
+fn struct_nullable(s: &Struct) -> TokenStream {
+    match s.repr {
+        StructRepr::Map | StructRepr::Tuple => quote!(false),
+        StructRepr::Value => {
+            let ty = &s.fields[0].ty;
+            quote!(<#ty as IpldSchema>::schema_nullable())
+        }
+        StructRepr::Null => quote!(true),
+    }
+}
+
+/// Generates an expression returning the name of the type of a union member.
+///
+/// Members that aren't represented by a named type define one named after the union and the
+/// variant.
+fn gen_schema_member(s: &Struct) -> TokenStream {
+    let variant = s.name.to_string();
+    let name = quote!(&format!("{}{}", name, #variant));
+    if s.repr == StructRepr::Value {
+        let ty = &s.fields[0].ty;
+        quote! {{
+            let ty = <#ty as IpldSchema>::schema_type(defs);
+            if libipld::schema::is_named(&ty) {
+                ty
+            } else {
+                defs.define(#name, |_| ty)
+            }
+        }}
+    } else {
+        let def = gen_schema_struct(s);
+        quote!(defs.define(#name, |defs| #def))
+    }
+}
+
+fn gen_schema_union(u: &Union) -> TokenStream {
+    let members = u.variants.iter().map(|s| {
+        let variant = s.name.to_string();
+        let key = key(&syn::Member::Named(s.name.clone()), s.rename.as_ref());
+        match u.repr {
+            UnionRepr::Keyed => {
+                let member = gen_schema_member(s);
+                quote! {
+                    let member = #member;
+                    def.push_str(&format!("  | {} {:?}\n", member, #key));
+                }
+            }
+            UnionRepr::Kinded => {
+                let member = gen_schema_member(s);
+                let kind = member_kind(s);
+                quote! {
+                    let member = #member;
+                    def.push_str(&format!("  | {} {}\n", member, #kind));
+                }
+            }
+            UnionRepr::String => {
+                let value = if s.rename.is_some() {
+                    format!(" ({:?})", key)
+                } else {
+                    String::new()
+                };
+                quote! {
+                    def.push_str(&format!("  | {}{}\n", #variant, #value));
+                }
+            }
+            UnionRepr::Int => {
+                let pat = &*s.pat;
+                quote! {
+                    def.push_str(&format!("  | {} (\"{}\")\n", #variant, #pat as u64));
+                }
+            }
+            UnionRepr::IntTuple => unreachable!("int-tuple unions have no schema"),
+        }
+    });
+    let (start, end) = match u.repr {
+        UnionRepr::Keyed => ("union {\n", "} representation keyed"),
+        UnionRepr::Kinded => ("union {\n", "} representation kinded"),
+        UnionRepr::String => ("enum {\n", "}"),
+        UnionRepr::Int => ("enum {\n", "} representation int"),
+        UnionRepr::IntTuple => unreachable!("int-tuple unions have no schema"),
+    };
+    quote! {{
+        let mut def = String::from(#start);
+        #(#members)*
+        def.push_str(#end);
+        def
+    }}
+}
+
+fn union_kind(u: &Union) -> TokenStream {
+    match u.repr {
+        UnionRepr::Keyed => quote!(Some("map")),
+        UnionRepr::Kinded => quote!(None),
+        UnionRepr::String => quote!(Some("string")),
+        UnionRepr::Int => quote!(Some("int")),
+        UnionRepr::IntTuple => unreachable!("int-tuple unions have no schema"),
+    }
+}
+
+fn union_nullable(u: &Union) -> TokenStream {
+    if u.repr == UnionRepr::Kinded {
+        let nullable = u.variants.iter().map(struct_nullable);
+        quote!(false #(|| #nullable)*)
+    } else {
+        quote!(false)
+    }
+}
@@ -13,9 +13,11 @@ fn dag_cbor_derive(s: Structure) -> TokenStream {
     let ast = parse::parse(&s);
     let encode = gen::gen_encode(&ast);
     let decode = gen::gen_decode(&ast);
+    let schema = gen::gen_schema(&ast);
     quote! {
         #encode
         #decode
+        #schema
     }
 }
 
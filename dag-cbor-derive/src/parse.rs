@@ -61,7 +61,7 @@ fn parse_union_repr(ast: &[syn::Attribute]) -> UnionRepr {
 }
 
 fn parse_struct(v: &VariantInfo, generics: Option<syn::Generics>) -> Struct {
-    let repr = parse_struct_repr(&v.ast().attrs);
+    let repr = parse_struct_repr(v.ast().attrs);
     let mut fields: Vec<_> = v
         .bindings()
         .iter()
@@ -108,7 +108,7 @@ fn parse_union(s: &Structure) -> Union {
             .iter()
             .map(|v| {
                 let mut s = parse_struct(v, None);
-                for attr in parse_attrs::<FieldAttr>(&v.ast().attrs) {
+                for attr in parse_attrs::<FieldAttr>(v.ast().attrs) {
                     if let FieldAttr::Rename(attr) = attr {
                         s.rename = Some(attr.value.value());
                     }
@@ -132,6 +132,7 @@ fn parse_field(i: usize, b: &BindingInfo) -> StructField {
         rename: None,
         default: None,
         binding: b.binding.clone(),
+        ty: b.ast().ty.clone(),
     };
     for attr in parse_attrs::<FieldAttr>(&b.ast().attrs) {
         match attr {
@@ -185,6 +186,7 @@ pub mod tests {
                     rename: Some("other".to_string()),
                     default: Some(syn::parse2(quote!(false)).unwrap()),
                     binding: format_ident!("__binding_0"),
+                    ty: syn::parse_quote!(bool),
                 }],
                 repr: StructRepr::Map,
                 pat: TokenStreamEq(quote! { Map { field: ref __binding_0, }}),
@@ -212,6 +214,7 @@ pub mod tests {
                     rename: None,
                     default: None,
                     binding: format_ident!("__binding_0"),
+                    ty: syn::parse_quote!(bool),
                 }],
                 repr: StructRepr::Tuple,
                 pat: TokenStreamEq(quote! { Tuple(ref __binding_0,) }),
@@ -277,6 +280,7 @@ pub mod tests {
                             rename: None,
                             default: None,
                             binding: format_ident!("__binding_0"),
+                            ty: syn::parse_quote!(bool),
                         }],
                         repr: StructRepr::Tuple,
                         pat: TokenStreamEq(quote! { Union::Tuple(ref __binding_0,) }),
@@ -291,6 +295,7 @@ pub mod tests {
                             rename: None,
                             default: None,
                             binding: format_ident!("__binding_0"),
+                            ty: syn::parse_quote!(bool),
                         }],
                         repr: StructRepr::Map,
                         pat: TokenStreamEq(quote! { Union::Struct { value: ref __binding_0, } }),
@@ -0,0 +1,246 @@
+use libipld::cbor::{DagCbor as DagCborTrait, DagCborCodec};
+use libipld::codec::Codec;
+use libipld::schema::{IpldSchema, Schema};
+use libipld::{DagCbor, Ipld, Link};
+use std::collections::BTreeMap;
+
+#[derive(Clone, DagCbor, Debug, PartialEq)]
+pub struct Node {
+    name: String,
+    #[ipld(rename = "p", default = None)]
+    parent: Option<Link<Node>>,
+    #[ipld(default = 1)]
+    weight: u32,
+    children: Vec<Node>,
+    tags: BTreeMap<String, Option<Tag>>,
+}
+
+#[derive(Clone, Copy, DagCbor, Debug, PartialEq)]
+#[ipld(repr = "string")]
+pub enum Tag {
+    #[ipld(rename = "red")]
+    Red,
+    Green,
+}
+
+#[derive(Clone, Copy, DagCbor, Debug, PartialEq)]
+#[ipld(repr = "int")]
+pub enum Code {
+    Ok = 0,
+    Err = 2,
+}
+
+#[derive(Clone, DagCbor, Debug, PartialEq)]
+#[ipld(repr = "tuple")]
+pub struct Point(i64, i64);
+
+#[derive(Clone, DagCbor, Debug, PartialEq)]
+#[ipld(repr = "value")]
+pub struct Points(Vec<Point>);
+
+#[derive(Clone, DagCbor, Debug, PartialEq)]
+#[ipld(repr = "keyed")]
+pub enum Shape {
+    Empty,
+    #[ipld(rename = "point")]
+    #[ipld(repr = "value")]
+    Point(Point),
+    #[ipld(repr = "value")]
+    Path(Vec<Point>),
+    Circle {
+        center: Point,
+        radius: f64,
+    },
+}
+
+#[derive(Clone, DagCbor, Debug, PartialEq)]
+#[ipld(repr = "kinded")]
+pub enum Value {
+    Null,
+    #[ipld(repr = "value")]
+    Int(i64),
+    #[ipld(repr = "value")]
+    Text(String),
+    Pair(bool, bool),
+}
+
+#[derive(Clone, DagCbor, Debug, PartialEq)]
+pub struct Wrapper<T: DagCborTrait> {
+    value: T,
+}
+
+#[derive(Clone, DagCbor, Debug, PartialEq)]
+#[ipld(repr = "keyed")]
+pub enum Either<T: DagCborTrait> {
+    Left { value: T },
+    Right,
+}
+
+#[derive(Clone, DagCbor, Debug, PartialEq)]
+pub struct Wrappers {
+    text: Wrapper<String>,
+    ints: Wrapper<Vec<i64>>,
+    either: Either<String>,
+    other: Either<i64>,
+}
+
+fn assert_valid<T: IpldSchema + libipld::codec::Encode<DagCborCodec>>(value: &T, name: &str) {
+    let schema = Schema::parse(&T::ipld_schema()).unwrap();
+    let ipld: Ipld = DagCborCodec
+        .decode(&DagCborCodec.encode(value).unwrap())
+        .unwrap();
+    schema.validate(&ipld, name).unwrap();
+}
+
+#[test]
+fn struct_schema() {
+    assert_eq!(
+        Node::ipld_schema(),
+        r#"type Node struct {
+  children [Node]
+  name String
+  parent optional &Node (rename "p")
+  tags {String:nullable Tag}
+  weight Int (implicit 1)
+}
+
+type Tag enum {
+  | Red ("red")
+  | Green
+}
+"#
+    );
+    let leaf = Node {
+        name: "leaf".into(),
+        parent: None,
+        weight: 1,
+        children: vec![],
+        tags: vec![("a".to_string(), Some(Tag::Red)), ("b".to_string(), None)]
+            .into_iter()
+            .collect(),
+    };
+    assert_valid(&leaf, "Node");
+}
+
+#[test]
+fn union_schema() {
+    assert_eq!(
+        Shape::ipld_schema(),
+        r#"type Shape union {
+  | ShapeEmpty "Empty"
+  | Point "point"
+  | ShapePath "Path"
+  | ShapeCircle "Circle"
+} representation keyed
+
+type ShapeEmpty unit representation null
+
+type Point struct {
+  _0 Int
+  _1 Int
+} representation tuple
+
+type ShapePath [Point]
+
+type ShapeCircle struct {
+  center Point
+  radius Float
+}
+"#
+    );
+    for shape in [
+        Shape::Empty,
+        Shape::Point(Point(1, 2)),
+        Shape::Path(vec![Point(1, 2)]),
+        Shape::Circle {
+            center: Point(0, 0),
+            radius: 1.0,
+        },
+    ] {
+        assert_valid(&shape, "Shape");
+    }
+    assert_eq!(
+        Points::ipld_schema().lines().next(),
+        Some("type Points [Point]")
+    );
+}
+
+#[test]
+fn kinded_schema() {
+    assert_eq!(
+        Value::ipld_schema(),
+        r#"type Value union {
+  | ValueNull null
+  | Int int
+  | String string
+  | ValuePair list
+} representation kinded
+
+type ValueNull unit representation null
+
+type ValuePair struct {
+  _0 Bool
+  _1 Bool
+} representation tuple
+"#
+    );
+    assert!(Value::schema_nullable());
+    assert_valid(&Value::Pair(true, false), "Value");
+    assert_valid(&Value::Text("a".into()), "Value");
+    assert_eq!(
+        Code::ipld_schema(),
+        "type Code enum {\n  | Ok (\"0\")\n  | Err (\"2\")\n} representation int\n"
+    );
+    assert_valid(&Code::Err, "Code");
+}
+
+#[test]
+fn generic_schema() {
+    assert_eq!(
+        Wrappers::ipld_schema(),
+        r#"type Wrappers struct {
+  either EitherString
+  ints WrapperListInt
+  other EitherInt
+  text WrapperString
+}
+
+type EitherString union {
+  | EitherStringLeft "Left"
+  | EitherStringRight "Right"
+} representation keyed
+
+type EitherStringLeft struct {
+  value String
+}
+
+type EitherStringRight unit representation null
+
+type WrapperListInt struct {
+  value [Int]
+}
+
+type EitherInt union {
+  | EitherIntLeft "Left"
+  | EitherIntRight "Right"
+} representation keyed
+
+type EitherIntLeft struct {
+  value Int
+}
+
+type EitherIntRight unit representation null
+
+type WrapperString struct {
+  value String
+}
+"#
+    );
+    let value = Wrappers {
+        text: Wrapper { value: "a".into() },
+        ints: Wrapper { value: vec![1] },
+        either: Either::Left { value: "b".into() },
+        other: Either::Right,
+    };
+    assert_valid(&value, "Wrappers");
+}
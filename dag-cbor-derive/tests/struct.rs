@@ -1,5 +1,5 @@
 use libipld::cbor::{DagCbor, DagCborCodec};
-use libipld::codec::{assert_roundtrip, Codec};
+use libipld::codec::{assert_roundtrip, Codec, Decode};
 use libipld::{ipld, DagCbor};
 
 #[derive(Clone, Copy, DagCbor, Debug, Eq, PartialEq)]
@@ -171,3 +171,177 @@ fn serde_cbor_compat() {
 
 #[derive(DagCbor)]
 pub struct Generic<T: DagCbor>(T);
+
+#[derive(Clone, Copy, DagCbor, Debug, Eq, PartialEq)]
+pub struct Canonical {
+    a: bool,
+    bb: bool,
+}
+
+#[test]
+fn struct_strict() {
+    let value = Canonical { a: true, bb: false };
+    let bytes = DagCborCodec.encode(&value).unwrap();
+    let value2: Canonical = DagCborCodec::strict().decode(&bytes).unwrap();
+    assert_eq!(value, value2);
+
+    // {"bb": false, "a": true}
+    let unsorted = [0xa2, 0x62, 0x62, 0x62, 0xf4, 0x61, 0x61, 0xf5];
+    let value2: Canonical = DagCborCodec.decode(&unsorted).unwrap();
+    assert_eq!(value, value2);
+    assert!(DagCborCodec::strict()
+        .decode::<Canonical>(&unsorted)
+        .is_err());
+}
+
+#[derive(Clone, Copy, DagCbor, Debug, Eq, PartialEq)]
+pub struct Unsorted {
+    b: bool,
+    aa: bool,
+}
+
+#[test]
+fn struct_conformant() {
+    use libipld::cbor::EncodingVersion;
+
+    let value = Unsorted { b: false, aa: true };
+    let legacy = DagCborCodec.encode(&value).unwrap();
+    assert_eq!(legacy, [0xa2, 0x62, 0x61, 0x61, 0xf5, 0x61, 0x62, 0xf4]);
+    let conformant = DagCborCodec
+        .with_version(EncodingVersion::Conformant)
+        .encode(&value)
+        .unwrap();
+    assert_eq!(conformant, [0xa2, 0x61, 0x62, 0xf4, 0x62, 0x61, 0x61, 0xf5]);
+    let value2: Unsorted = DagCborCodec::strict().decode(&conformant).unwrap();
+    assert_eq!(value, value2);
+}
+
+#[derive(Clone, DagCbor, Debug, Eq, PartialEq)]
+pub struct Tree {
+    children: Vec<Tree>,
+}
+
+#[test]
+fn struct_limits() {
+    use libipld::cbor::error::{CborError, Limit, LimitExceeded};
+    use libipld::cbor::DecodeLimits;
+    use libipld::error::Error;
+
+    let value = Tree {
+        children: vec![Tree { children: vec![] }],
+    };
+    let bytes = DagCborCodec.encode(&value).unwrap();
+    // each level nests a map and a list
+    let limits = DecodeLimits {
+        max_depth: 4,
+        ..DecodeLimits::default()
+    };
+    let value2: Tree = DagCborCodec.with_limits(limits).decode(&bytes).unwrap();
+    assert_eq!(value, value2);
+    let limits = DecodeLimits {
+        max_depth: 3,
+        ..DecodeLimits::default()
+    };
+    let err = DagCborCodec
+        .with_limits(limits)
+        .decode::<Tree>(&bytes)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Cbor(CborError::LimitExceeded(LimitExceeded {
+            limit: Limit::Depth,
+            ..
+        }))
+    ));
+
+    // the allocation limit holds when decoding without the codec
+    let limits = DecodeLimits {
+        max_allocation: 8,
+        ..DecodeLimits::default()
+    };
+    let c = DagCborCodec.with_limits(limits);
+    let err = Tree::decode(c, &mut std::io::Cursor::new(&bytes)).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Cbor(CborError::LimitExceeded(LimitExceeded {
+            limit: Limit::Allocation,
+            ..
+        }))
+    ));
+}
+
+#[derive(Clone, DagCbor, Debug, Eq, PartialEq)]
+pub struct Borrowed<'a> {
+    name: &'a str,
+    data: &'a [u8],
+    tags: Vec<std::borrow::Cow<'a, str>>,
+    map: Map,
+}
+
+#[test]
+fn struct_borrowed() {
+    let value = Borrowed {
+        name: "name",
+        data: &[1, 2, 3],
+        tags: vec!["a".into(), "b".into()],
+        map: Map { boolean: true },
+    };
+    let bytes = DagCborCodec.encode(&value).unwrap();
+    let value2: Borrowed = DagCborCodec.decode_ref(&bytes).unwrap();
+    assert_eq!(value, value2);
+    let name_offset = bytes.windows(4).position(|w| w == b"name").unwrap();
+    let value_offset = bytes[name_offset + 4..]
+        .windows(4)
+        .position(|w| w == b"name")
+        .unwrap();
+    assert_eq!(
+        value2.name.as_ptr(),
+        bytes[name_offset + 4 + value_offset..].as_ptr()
+    );
+}
+
+#[derive(Clone, DagCbor, Debug, Eq, PartialEq)]
+pub struct Linked {
+    name: String,
+    link: libipld::Cid,
+    tags: Vec<String>,
+}
+
+#[test]
+fn struct_async_decode() {
+    use futures::executor::block_on;
+    use futures::io::AsyncRead;
+    use libipld::codec::AsyncDecoder;
+    use libipld::multihash::{Code, MultihashDigest};
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    /// Reader returning one byte at a time.
+    struct OneByte<'a>(&'a [u8]);
+
+    impl AsyncRead for OneByte<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _: &mut Context,
+            buf: &mut [u8],
+        ) -> Poll<std::io::Result<usize>> {
+            let n = self.0.len().min(buf.len()).min(1);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    let value = Linked {
+        name: "name".into(),
+        link: libipld::Cid::new_v1(0x71, Code::Blake3_256.digest(b"block")),
+        tags: vec!["a".into(), "b".into()],
+    };
+    let mut bytes = DagCborCodec.encode(&value).unwrap();
+    bytes.extend(DagCborCodec.encode(&Map { boolean: true }).unwrap());
+    let mut decoder = AsyncDecoder::new(DagCborCodec, OneByte(&bytes));
+    let value2: Linked = block_on(decoder.decode()).unwrap();
+    assert_eq!(value2, value);
+    let map: Map = block_on(decoder.decode()).unwrap();
+    assert_eq!(map, Map { boolean: true });
+}
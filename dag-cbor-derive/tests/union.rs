@@ -1,5 +1,5 @@
 use libipld::cbor::DagCborCodec;
-use libipld::codec::assert_roundtrip;
+use libipld::codec::{assert_roundtrip, Codec};
 use libipld::{ipld, DagCbor};
 
 #[derive(Clone, Copy, DagCbor, Debug, Eq, PartialEq)]
@@ -64,6 +64,42 @@ fn union_kinded() {
     );
 }
 
+#[test]
+fn union_kinded_non_seekable() {
+    for value in [Kinded::A, Kinded::C { n: 1 }, Kinded::E { boolean: true }] {
+        let bytes = DagCborCodec.encode(&value).unwrap();
+        let value2: Kinded = DagCborCodec.decode_from_reader(&bytes[..]).unwrap();
+        assert_eq!(value, value2);
+    }
+    let values = vec![Kinded::D(true); 10_000];
+    let bytes = DagCborCodec.encode(&values).unwrap();
+    let values2: Vec<Kinded> = DagCborCodec.decode_from_reader(&bytes[..]).unwrap();
+    assert_eq!(values, values2);
+}
+
+#[derive(Clone, Copy, DagCbor, Debug, Eq, PartialEq)]
+#[ipld(repr = "kinded")]
+pub enum KindedInt {
+    #[ipld(repr = "value")]
+    Small(u8),
+    #[ipld(repr = "value")]
+    Large(u64),
+}
+
+#[test]
+fn union_kinded_same_kind() {
+    let bytes = DagCborCodec.encode(&vec![1u64, 300, 1 << 40]).unwrap();
+    let expected = [
+        KindedInt::Small(1),
+        KindedInt::Large(300),
+        KindedInt::Large(1 << 40),
+    ];
+    let values: Vec<KindedInt> = DagCborCodec.decode(&bytes).unwrap();
+    assert_eq!(values, expected);
+    let values: Vec<KindedInt> = DagCborCodec.decode_from_reader(&bytes[..]).unwrap();
+    assert_eq!(values, expected);
+}
+
 #[derive(Clone, Copy, DagCbor, Debug, Eq, PartialEq)]
 #[ipld(repr = "int-tuple")]
 pub enum IntTuple {
@@ -94,3 +130,21 @@ fn union_int_tuple() {
         &ipld!([4, { "boolean": true }]),
     );
 }
+
+#[derive(Clone, DagCbor, Debug, Eq, PartialEq)]
+#[ipld(repr = "kinded")]
+pub enum KindedRef<'a> {
+    #[ipld(repr = "value")]
+    Int(u64),
+    #[ipld(repr = "value")]
+    Str(&'a str),
+}
+
+#[test]
+fn union_kinded_borrowed() {
+    for value in [KindedRef::Int(1), KindedRef::Str("hello")] {
+        let bytes = DagCborCodec.encode(&value).unwrap();
+        let value2: KindedRef = DagCborCodec.decode_ref(&bytes).unwrap();
+        assert_eq!(value, value2);
+    }
+}
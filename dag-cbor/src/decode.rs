@@ -1,15 +1,31 @@
 //! CBOR decoder
-use crate::error::{InvalidCidPrefix, LengthOutOfRange, UnexpectedCode, UnknownTag};
+use crate::error::{
+    DuplicateMapKey, IndefiniteLength, InvalidCidPrefix, LengthOutOfRange, Limit, LimitExceeded,
+    NonCanonicalFloat, NonFiniteFloat, NonMinimalInteger, NumberOutOfRange, Undefined,
+    UnexpectedCode, UnknownTag, UnsortedMapKeys,
+};
 use crate::DagCborCodec as DagCbor;
+use alloc::{borrow::Cow, boxed::Box, collections::BTreeMap, string::String, sync::Arc, vec::Vec};
 use byteorder::{BigEndian, ByteOrder};
+use core::cmp::Ordering;
 use core::convert::TryFrom;
-use libipld_core::codec::{Decode, References};
+use core::mem::size_of;
+use libipld_core::codec::{Decode, DecodeRef, References};
 use libipld_core::error::Result;
+use libipld_core::io::{Cursor, ErrorKind, Read, Seek, SeekFrom};
 use libipld_core::ipld::Ipld;
-use libipld_core::{cid::Cid, raw_value::SkipOne};
-use std::collections::BTreeMap;
-use std::io::{Read, Seek, SeekFrom};
-use std::sync::Arc;
+use libipld_core::ipld_ref::IpldRef;
+use libipld_core::{
+    cid::{self, Cid, Version},
+    multihash::{MultihashGeneric, U64},
+    raw_value::{RawValue, SkipOne},
+};
+
+/// The multihash type `Cid` is built on, which exists without the default code table.
+type Multihash = MultihashGeneric<U64>;
+
+/// Maximum number of bytes preallocated based on a length read from the input.
+const MAX_PREALLOC: usize = 4096;
 
 /// Reads a u8 from a byte stream.
 pub fn read_u8<R: Read + Seek>(r: &mut R) -> Result<u8> {
@@ -53,105 +69,387 @@ pub fn read_f64<R: Read + Seek>(r: &mut R) -> Result<f64> {
     Ok(BigEndian::read_f64(&buf))
 }
 
+/// Reads a f64 from a byte stream, rejecting `NaN` and infinities if the codec is strict.
+fn read_finite_f64<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<f64> {
+    let value = read_f64(r)?;
+    if c.is_strict() && !value.is_finite() {
+        return Err(NonFiniteFloat(value).into());
+    }
+    Ok(value)
+}
+
+/// Narrows a f64 to a f32, failing if the value can't be represented exactly.
+fn narrow_f64(value: f64) -> Result<f32> {
+    let narrowed = value as f32;
+    if f64::from(narrowed) != value && !value.is_nan() {
+        return Err(NumberOutOfRange::new::<f32>().into());
+    }
+    Ok(narrowed)
+}
+
 /// Reads `len` number of bytes from a byte stream.
-pub fn read_bytes<R: Read + Seek>(r: &mut R, len: usize) -> Result<Vec<u8>> {
-    let mut buf = vec![0; len];
-    r.read_exact(&mut buf)?;
+///
+/// The buffer grows in chunks of bytes actually read, so a bogus `len` can't trigger a large
+/// allocation.
+pub fn read_bytes<R: Read + Seek>(c: DagCbor, r: &mut R, len: usize) -> Result<Vec<u8>> {
+    check_string_len::<Vec<u8>>(c, len)?;
+    let mut buf = Vec::new();
+    while buf.len() < len {
+        let start = buf.len();
+        buf.resize(len.min(start + MAX_PREALLOC), 0);
+        r.read_exact(&mut buf[start..])?;
+    }
     Ok(buf)
 }
 
 /// Reads `len` number of bytes from a byte stream and converts them to a string.
-pub fn read_str<R: Read + Seek>(r: &mut R, len: usize) -> Result<String> {
-    let bytes = read_bytes(r, len)?;
+pub fn read_str<R: Read + Seek>(c: DagCbor, r: &mut R, len: usize) -> Result<String> {
+    let bytes = read_bytes(c, r, len)?;
     Ok(String::from_utf8(bytes)?)
 }
 
+/// Fails if `len` exceeds the maximum string length of the codec.
+fn check_string_len<T>(c: DagCbor, len: usize) -> Result<()> {
+    if len > c.limits().max_string_len {
+        return Err(LimitExceeded::new::<T>(Limit::StringLen).into());
+    }
+    Ok(())
+}
+
+/// Fails if `len` exceeds the maximum collection length of the codec.
+fn check_collection_len<T>(c: DagCbor, len: usize) -> Result<()> {
+    if len > c.limits().max_collection_len {
+        return Err(LimitExceeded::new::<T>(Limit::CollectionLen).into());
+    }
+    Ok(())
+}
+
+/// Returns the number of items of type `T` to preallocate for a collection of length `len`.
+fn prealloc<T>(len: usize) -> usize {
+    len.min(MAX_PREALLOC / size_of::<T>().max(1))
+}
+
+/// Returns `value` if it is minimally encoded for the initial byte `major`, or if the codec
+/// isn't strict.
+fn minimal<T: Copy + Into<u64>>(c: DagCbor, major: u8, value: T) -> Result<T> {
+    if c.is_strict() {
+        let min: u64 = match major & 0x1f {
+            0x18 => 0x18,
+            0x19 => 0x100,
+            0x1a => 0x1_0000,
+            0x1b => 0x1_0000_0000,
+            _ => 0,
+        };
+        if value.into() < min {
+            return Err(NonMinimalInteger(value.into()).into());
+        }
+    }
+    Ok(value)
+}
+
+/// Reads a positive or negative integer that fits into `T`.
+///
+/// Unless the codec is strict, the legacy encoding of positive values is accepted too, see
+/// [`EncodingVersion::Legacy`](crate::EncodingVersion::Legacy).
+fn read_int<T: TryFrom<i128>, R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<T> {
+    let bits = 8 * size_of::<T>() as u32;
+    let major = read_u8(r)?;
+    let value = match major & 0x1f {
+        0x00..=0x17 => (major & 0x1f) as u64,
+        0x18 => minimal(c, major, read_u8(r)?)? as u64,
+        0x19 => minimal(c, major, read_u16(r)?)? as u64,
+        0x1a => minimal(c, major, read_u32(r)?)? as u64,
+        0x1b => minimal(c, major, read_u64(r)?)?,
+        _ => return Err(UnexpectedCode::new::<T>(major).into()),
+    };
+    let value = match major >> 5 {
+        0 => value as i128,
+        // the bitwise complement of a positive value in the width of `T`
+        1 if !c.is_strict() && (value as u128) >> (bits - 1) == 1 => {
+            (1i128 << bits) - 1 - value as i128
+        }
+        1 => -1 - value as i128,
+        _ => return Err(UnexpectedCode::new::<T>(major).into()),
+    };
+    T::try_from(value).map_err(|_| NumberOutOfRange::new::<T>().into())
+}
+
+/// Checks that `key` sorts after `prev` in canonical (length first) order if the codec is strict.
+///
+/// `prev` is updated to `key`. Comparing cbor encoded string keys yields the same order as
+/// comparing the strings.
+pub fn check_key_order<T>(c: DagCbor, prev: &mut Option<Vec<u8>>, key: &[u8]) -> Result<()> {
+    if !c.is_strict() {
+        return Ok(());
+    }
+    if let Some(prev) = prev.as_ref() {
+        match prev.len().cmp(&key.len()).then_with(|| prev[..].cmp(key)) {
+            Ordering::Less => {}
+            Ordering::Equal => return Err(DuplicateMapKey::new::<T>().into()),
+            Ordering::Greater => return Err(UnsortedMapKeys::new::<T>().into()),
+        }
+    }
+    *prev = Some(key.to_vec());
+    Ok(())
+}
+
+/// Returns the data model kind of the next data item without consuming it.
+///
+/// Returns `None` for codes that don't start a DAG-CBOR data item of a single kind.
+pub fn peek_kind<R: Read + Seek>(r: &mut R) -> Result<Option<&'static str>> {
+    let major = read_u8(r)?;
+    let kind = match major {
+        0x00..=0x1b | 0x20..=0x3b => Some("int"),
+        0x40..=0x5b => Some("bytes"),
+        0x60..=0x7b => Some("string"),
+        0x80..=0x9b | 0x9f => Some("list"),
+        0xa0..=0xbb | 0xbf => Some("map"),
+        0xd8 => {
+            let tag = read_u8(r)?;
+            r.seek(SeekFrom::Current(-1))?;
+            if tag == 42 {
+                Some("link")
+            } else {
+                None
+            }
+        }
+        0xf4 | 0xf5 => Some("bool"),
+        0xf6 | 0xf7 => Some("null"),
+        0xf9..=0xfb => Some("float"),
+        _ => None,
+    };
+    r.seek(SeekFrom::Current(-1))?;
+    Ok(kind)
+}
+
+/// Returns true if a data item of kind `kind`, as returned by [`peek_kind`], may be decoded as
+/// a type of kind `expected` that may be `nullable`.
+///
+/// Unknown kinds match anything.
+pub fn kind_matches(kind: Option<&str>, expected: Option<&str>, nullable: bool) -> bool {
+    match (kind, expected) {
+        (Some(kind), Some(expected)) => kind == expected || kind == "null" && nullable,
+        _ => true,
+    }
+}
+
 /// Reads a list of any type that implements `TryReadCbor` from a stream of cbor encoded bytes.
-pub fn read_list<R: Read + Seek, T: Decode<DagCbor>>(r: &mut R, len: usize) -> Result<Vec<T>> {
-    let mut list: Vec<T> = Vec::with_capacity(len);
+pub fn read_list<R: Read + Seek, T: Decode<DagCbor>>(
+    c: DagCbor,
+    r: &mut R,
+    len: usize,
+) -> Result<Vec<T>> {
+    read_list_with(c, r, len, T::decode)
+}
+
+/// Reads a list of `len` items decoded with `item` from a stream of cbor encoded bytes.
+fn read_list_with<R: Read + Seek, T>(
+    c: DagCbor,
+    r: &mut R,
+    len: usize,
+    mut item: impl FnMut(DagCbor, &mut R) -> Result<T>,
+) -> Result<Vec<T>> {
+    check_collection_len::<Vec<T>>(c, len)?;
+    let c = c.nested::<Vec<T>>()?;
+    let mut list: Vec<T> = Vec::with_capacity(prealloc::<T>(len));
     for _ in 0..len {
-        list.push(T::decode(DagCbor, r)?);
+        list.push(item(c, r)?);
     }
     Ok(list)
 }
 
 /// Reads a list of any type that implements `TryReadCbor` from a stream of cbor encoded bytes.
-pub fn read_list_il<R: Read + Seek, T: Decode<DagCbor>>(r: &mut R) -> Result<Vec<T>> {
+pub fn read_list_il<R: Read + Seek, T: Decode<DagCbor>>(c: DagCbor, r: &mut R) -> Result<Vec<T>> {
+    read_list_il_with(c, r, T::decode)
+}
+
+/// Reads a list of items decoded with `item` from a stream of cbor encoded bytes.
+fn read_list_il_with<R: Read + Seek, T>(
+    c: DagCbor,
+    r: &mut R,
+    mut item: impl FnMut(DagCbor, &mut R) -> Result<T>,
+) -> Result<Vec<T>> {
+    if c.is_strict() {
+        return Err(IndefiniteLength::new::<Vec<T>>().into());
+    }
+    let c = c.nested::<Vec<T>>()?;
     let mut list: Vec<T> = Vec::new();
     loop {
         let major = read_u8(r)?;
         if major == 0xff {
             break;
         }
+        check_collection_len::<Vec<T>>(c, list.len() + 1)?;
         r.seek(SeekFrom::Current(-1))?;
-        let value = T::decode(DagCbor, r)?;
-        list.push(value);
+        list.push(item(c, r)?);
     }
     Ok(list)
 }
 
 /// Reads a map of any type that implements `TryReadCbor` from a stream of cbor encoded bytes.
 pub fn read_map<R: Read + Seek, K: Decode<DagCbor> + Ord, T: Decode<DagCbor>>(
+    c: DagCbor,
     r: &mut R,
     len: usize,
 ) -> Result<BTreeMap<K, T>> {
+    if !c.is_strict() {
+        return read_map_with(c, r, len, K::decode, T::decode);
+    }
+    check_collection_len::<BTreeMap<K, T>>(c, len)?;
+    let c = c.nested::<BTreeMap<K, T>>()?;
     let mut map: BTreeMap<K, T> = BTreeMap::new();
+    let mut prev = None;
     for _ in 0..len {
-        let key = K::decode(DagCbor, r)?;
-        let value = T::decode(DagCbor, r)?;
-        map.insert(key, value);
+        // the key is decoded from its copy, so that `r` doesn't need to seek back over it
+        let raw = read_sorted_key::<K, T, R>(c, r, &mut prev)?;
+        let k = K::decode(c, &mut Cursor::new(raw.as_ref()))?;
+        let v = T::decode(c, r)?;
+        map.insert(k, v);
     }
     Ok(map)
 }
 
+/// Reads a map of `len` entries decoded with `key` and `value` from a stream of cbor encoded
+/// bytes.
+///
+/// If the codec is strict, `r` seeks back over every key after checking it, which is only done
+/// when decoding from a `Cursor`.
+fn read_map_with<R: Read + Seek, K: Ord, T>(
+    c: DagCbor,
+    r: &mut R,
+    len: usize,
+    mut key: impl FnMut(DagCbor, &mut R) -> Result<K>,
+    mut value: impl FnMut(DagCbor, &mut R) -> Result<T>,
+) -> Result<BTreeMap<K, T>> {
+    check_collection_len::<BTreeMap<K, T>>(c, len)?;
+    let c = c.nested::<BTreeMap<K, T>>()?;
+    let mut map: BTreeMap<K, T> = BTreeMap::new();
+    let mut prev = None;
+    for _ in 0..len {
+        if c.is_strict() {
+            let raw = read_sorted_key::<K, T, R>(c, r, &mut prev)?;
+            r.seek(SeekFrom::Current(-(raw.as_ref().len() as i64)))?;
+        }
+        let k = key(c, r)?;
+        let v = value(c, r)?;
+        map.insert(k, v);
+    }
+    Ok(map)
+}
+
+/// Reads the next map key, which must be a string sorting after `prev`, and returns its bytes.
+fn read_sorted_key<K, T, R: Read + Seek>(
+    c: DagCbor,
+    r: &mut R,
+    prev: &mut Option<Vec<u8>>,
+) -> Result<RawValue<DagCbor>> {
+    let raw = RawValue::<DagCbor>::decode(c, r)?;
+    // map keys must be strings
+    let code = raw.as_ref()[0];
+    if code >> 5 != 3 {
+        return Err(UnexpectedCode::new::<K>(code).into());
+    }
+    check_key_order::<BTreeMap<K, T>>(c, prev, raw.as_ref())?;
+    Ok(raw)
+}
+
 /// Reads a map of any type that implements `TryReadCbor` from a stream of cbor encoded bytes.
 pub fn read_map_il<R: Read + Seek, K: Decode<DagCbor> + Ord, T: Decode<DagCbor>>(
+    c: DagCbor,
     r: &mut R,
 ) -> Result<BTreeMap<K, T>> {
+    read_map_il_with(c, r, K::decode, T::decode)
+}
+
+/// Reads a map of entries decoded with `key` and `value` from a stream of cbor encoded bytes.
+fn read_map_il_with<R: Read + Seek, K: Ord, T>(
+    c: DagCbor,
+    r: &mut R,
+    mut key: impl FnMut(DagCbor, &mut R) -> Result<K>,
+    mut value: impl FnMut(DagCbor, &mut R) -> Result<T>,
+) -> Result<BTreeMap<K, T>> {
+    if c.is_strict() {
+        return Err(IndefiniteLength::new::<BTreeMap<K, T>>().into());
+    }
+    let c = c.nested::<BTreeMap<K, T>>()?;
     let mut map: BTreeMap<K, T> = BTreeMap::new();
+    let mut len = 0;
     loop {
         let major = read_u8(r)?;
         if major == 0xff {
             break;
         }
+        len += 1;
+        check_collection_len::<BTreeMap<K, T>>(c, len)?;
         r.seek(SeekFrom::Current(-1))?;
-        let key = K::decode(DagCbor, r)?;
-        let value = T::decode(DagCbor, r)?;
-        map.insert(key, value);
+        let k = key(c, r)?;
+        let v = value(c, r)?;
+        map.insert(k, v);
     }
     Ok(map)
 }
 
 /// Reads a cid from a stream of cbor encoded bytes.
-pub fn read_link<R: Read + Seek>(r: &mut R) -> Result<Cid> {
+pub fn read_link<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Cid> {
     let ty = read_u8(r)?;
     if ty != 0x58 {
         return Err(UnknownTag(ty).into());
     }
-    let len = read_u8(r)?;
+    let len = minimal(c, ty, read_u8(r)?)?;
     if len == 0 {
         return Err(LengthOutOfRange::new::<Cid>().into());
     }
-    let bytes = read_bytes(r, len as usize)?;
+    let bytes = read_bytes(c, r, len as usize)?;
     if bytes[0] != 0 {
         return Err(InvalidCidPrefix(bytes[0]).into());
     }
 
     // skip the first byte per
     // https://github.com/ipld/specs/blob/master/block-layer/codecs/dag-cbor.md#links
-    Ok(Cid::try_from(&bytes[1..])?)
+    read_cid(&bytes[1..])
+}
+
+/// Parses the binary representation of a cid.
+///
+/// Doesn't depend on the `std` feature of the `cid` crate.
+fn read_cid(mut bytes: &[u8]) -> Result<Cid> {
+    let version = read_varint(&mut bytes)?;
+    let codec = read_varint(&mut bytes)?;
+    // CIDv0 has the fixed `0x12 0x20` prefix
+    if [version, codec] == [0x12, 0x20] {
+        let digest = bytes.get(..32).ok_or(cid::Error::InputTooShort)?;
+        let mh = Multihash::wrap(version, digest).map_err(cid::Error::from)?;
+        Ok(Cid::new_v0(mh)?)
+    } else {
+        let version = Version::try_from(version)?;
+        let code = read_varint(&mut bytes)?;
+        let size = read_varint(&mut bytes)?;
+        let digest = usize::try_from(size)
+            .ok()
+            .and_then(|size| bytes.get(..size))
+            .ok_or(cid::Error::InputTooShort)?;
+        let mh = Multihash::wrap(code, digest).map_err(cid::Error::from)?;
+        Ok(Cid::new(version, codec, mh)?)
+    }
+}
+
+/// Reads an unsigned varint from the start of `bytes`.
+fn read_varint(bytes: &mut &[u8]) -> Result<u64> {
+    let (n, rest) = unsigned_varint::decode::u64(bytes).map_err(cid::Error::from)?;
+    *bytes = rest;
+    Ok(n)
 }
 
 /// Reads the len given a base.
-pub fn read_len<R: Read + Seek>(r: &mut R, major: u8) -> Result<usize> {
+pub fn read_len<R: Read + Seek>(c: DagCbor, r: &mut R, major: u8) -> Result<usize> {
     Ok(match major {
         0x00..=0x17 => major as usize,
-        0x18 => read_u8(r)? as usize,
-        0x19 => read_u16(r)? as usize,
-        0x1a => read_u32(r)? as usize,
+        0x18 => minimal(c, major, read_u8(r)?)? as usize,
+        0x19 => minimal(c, major, read_u16(r)?)? as usize,
+        0x1a => minimal(c, major, read_u32(r)?)? as usize,
         0x1b => {
-            let len = read_u64(r)?;
-            if len > usize::max_value() as u64 {
+            let len = minimal(c, major, read_u64(r)?)?;
+            if len > usize::MAX as u64 {
                 return Err(LengthOutOfRange::new::<usize>().into());
             }
             len as usize
@@ -160,6 +458,13 @@ pub fn read_len<R: Read + Seek>(r: &mut R, major: u8) -> Result<usize> {
     })
 }
 
+/// Skips the `len` bytes of a string, failing if `len` doesn't fit into a seek offset.
+fn skip_len<R: Seek>(r: &mut R, len: usize) -> Result<()> {
+    let offset = i64::try_from(len).map_err(|_| LengthOutOfRange::new::<i64>())?;
+    r.seek(SeekFrom::Current(offset))?;
+    Ok(())
+}
+
 impl Decode<DagCbor> for bool {
     fn decode<R: Read + Seek>(_: DagCbor, r: &mut R) -> Result<Self> {
         let major = read_u8(r)?;
@@ -175,11 +480,11 @@ impl Decode<DagCbor> for bool {
 }
 
 impl Decode<DagCbor> for u8 {
-    fn decode<R: Read + Seek>(_: DagCbor, r: &mut R) -> Result<Self> {
+    fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
         let major = read_u8(r)?;
         let result = match major {
             0x00..=0x17 => major,
-            0x18 => read_u8(r)?,
+            0x18 => minimal(c, major, read_u8(r)?)?,
             _ => {
                 return Err(UnexpectedCode::new::<Self>(major).into());
             }
@@ -189,12 +494,12 @@ impl Decode<DagCbor> for u8 {
 }
 
 impl Decode<DagCbor> for u16 {
-    fn decode<R: Read + Seek>(_: DagCbor, r: &mut R) -> Result<Self> {
+    fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
         let major = read_u8(r)?;
         let result = match major {
             0x00..=0x17 => major as u16,
-            0x18 => read_u8(r)? as u16,
-            0x19 => read_u16(r)?,
+            0x18 => minimal(c, major, read_u8(r)?)? as u16,
+            0x19 => minimal(c, major, read_u16(r)?)?,
             _ => {
                 return Err(UnexpectedCode::new::<Self>(major).into());
             }
@@ -204,13 +509,13 @@ impl Decode<DagCbor> for u16 {
 }
 
 impl Decode<DagCbor> for u32 {
-    fn decode<R: Read + Seek>(_: DagCbor, r: &mut R) -> Result<Self> {
+    fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
         let major = read_u8(r)?;
         let result = match major {
             0x00..=0x17 => major as u32,
-            0x18 => read_u8(r)? as u32,
-            0x19 => read_u16(r)? as u32,
-            0x1a => read_u32(r)?,
+            0x18 => minimal(c, major, read_u8(r)?)? as u32,
+            0x19 => minimal(c, major, read_u16(r)?)? as u32,
+            0x1a => minimal(c, major, read_u32(r)?)?,
             _ => {
                 return Err(UnexpectedCode::new::<Self>(major).into());
             }
@@ -220,14 +525,14 @@ impl Decode<DagCbor> for u32 {
 }
 
 impl Decode<DagCbor> for u64 {
-    fn decode<R: Read + Seek>(_: DagCbor, r: &mut R) -> Result<Self> {
+    fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
         let major = read_u8(r)?;
         let result = match major {
             0x00..=0x17 => major as u64,
-            0x18 => read_u8(r)? as u64,
-            0x19 => read_u16(r)? as u64,
-            0x1a => read_u32(r)? as u64,
-            0x1b => read_u64(r)?,
+            0x18 => minimal(c, major, read_u8(r)?)? as u64,
+            0x19 => minimal(c, major, read_u16(r)?)? as u64,
+            0x1a => minimal(c, major, read_u32(r)?)? as u64,
+            0x1b => minimal(c, major, read_u64(r)?)?,
             _ => {
                 return Err(UnexpectedCode::new::<Self>(major).into());
             }
@@ -237,72 +542,36 @@ impl Decode<DagCbor> for u64 {
 }
 
 impl Decode<DagCbor> for i8 {
-    fn decode<R: Read + Seek>(_: DagCbor, r: &mut R) -> Result<Self> {
-        let major = read_u8(r)?;
-        let result = match major {
-            0x20..=0x37 => -1 - (major - 0x20) as i8,
-            0x38 => -1 - read_u8(r)? as i8,
-            _ => {
-                return Err(UnexpectedCode::new::<Self>(major).into());
-            }
-        };
-        Ok(result)
+    fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
+        read_int(c, r)
     }
 }
 
 impl Decode<DagCbor> for i16 {
-    fn decode<R: Read + Seek>(_: DagCbor, r: &mut R) -> Result<Self> {
-        let major = read_u8(r)?;
-        let result = match major {
-            0x20..=0x37 => -1 - (major - 0x20) as i16,
-            0x38 => -1 - read_u8(r)? as i16,
-            0x39 => -1 - read_u16(r)? as i16,
-            _ => {
-                return Err(UnexpectedCode::new::<Self>(major).into());
-            }
-        };
-        Ok(result)
+    fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
+        read_int(c, r)
     }
 }
 
 impl Decode<DagCbor> for i32 {
-    fn decode<R: Read + Seek>(_: DagCbor, r: &mut R) -> Result<Self> {
-        let major = read_u8(r)?;
-        let result = match major {
-            0x20..=0x37 => -1 - (major - 0x20) as i32,
-            0x38 => -1 - read_u8(r)? as i32,
-            0x39 => -1 - read_u16(r)? as i32,
-            0x3a => -1 - read_u32(r)? as i32,
-            _ => {
-                return Err(UnexpectedCode::new::<Self>(major).into());
-            }
-        };
-        Ok(result)
+    fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
+        read_int(c, r)
     }
 }
 
 impl Decode<DagCbor> for i64 {
-    fn decode<R: Read + Seek>(_: DagCbor, r: &mut R) -> Result<Self> {
-        let major = read_u8(r)?;
-        let result = match major {
-            0x20..=0x37 => -1 - (major - 0x20) as i64,
-            0x38 => -1 - read_u8(r)? as i64,
-            0x39 => -1 - read_u16(r)? as i64,
-            0x3a => -1 - read_u32(r)? as i64,
-            0x3b => -1 - read_u64(r)? as i64,
-            _ => {
-                return Err(UnexpectedCode::new::<Self>(major).into());
-            }
-        };
-        Ok(result)
+    fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
+        read_int(c, r)
     }
 }
 
 impl Decode<DagCbor> for f32 {
-    fn decode<R: Read + Seek>(_: DagCbor, r: &mut R) -> Result<Self> {
+    fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
         let major = read_u8(r)?;
         let result = match major {
+            0xfa if c.is_strict() => return Err(NonCanonicalFloat(major).into()),
             0xfa => read_f32(r)?,
+            0xfb => narrow_f64(read_finite_f64(c, r)?)?,
             _ => {
                 return Err(UnexpectedCode::new::<Self>(major).into());
             }
@@ -312,11 +581,12 @@ impl Decode<DagCbor> for f32 {
 }
 
 impl Decode<DagCbor> for f64 {
-    fn decode<R: Read + Seek>(_: DagCbor, r: &mut R) -> Result<Self> {
+    fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
         let major = read_u8(r)?;
         let result = match major {
+            0xfa if c.is_strict() => return Err(NonCanonicalFloat(major).into()),
             0xfa => read_f32(r)? as f64,
-            0xfb => read_f64(r)?,
+            0xfb => read_finite_f64(c, r)?,
             _ => {
                 return Err(UnexpectedCode::new::<Self>(major).into());
             }
@@ -326,12 +596,13 @@ impl Decode<DagCbor> for f64 {
 }
 
 impl Decode<DagCbor> for String {
-    fn decode<R: Read + Seek>(_: DagCbor, r: &mut R) -> Result<Self> {
+    fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
+        let c = c.check_allocation(r)?;
         let major = read_u8(r)?;
         let result = match major {
             0x60..=0x7b => {
-                let len = read_len(r, major - 0x60)?;
-                read_str(r, len)?
+                let len = read_len(c, r, major - 0x60)?;
+                read_str(c, r, len)?
             }
             _ => {
                 return Err(UnexpectedCode::new::<Self>(major).into());
@@ -342,26 +613,23 @@ impl Decode<DagCbor> for String {
 }
 
 impl Decode<DagCbor> for Cid {
-    fn decode<R: Read + Seek>(_: DagCbor, r: &mut R) -> Result<Self> {
+    fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
         let major = read_u8(r)?;
-        if major == 0xd8 {
-            if let Ok(tag) = read_u8(r) {
-                if tag == 42 {
-                    return read_link(r);
-                }
-            }
+        if major == 0xd8 && read_u8(r)? == 42 {
+            return read_link(c, r);
         }
         Err(UnexpectedCode::new::<Self>(major).into())
     }
 }
 
 impl Decode<DagCbor> for Box<[u8]> {
-    fn decode<R: Read + Seek>(_: DagCbor, r: &mut R) -> Result<Self> {
+    fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
+        let c = c.check_allocation(r)?;
         let major = read_u8(r)?;
         let result = match major {
             0x40..=0x5b => {
-                let len = read_len(r, major - 0x40)?;
-                read_bytes(r, len)?.into_boxed_slice()
+                let len = read_len(c, r, major - 0x40)?;
+                read_bytes(c, r, len)?.into_boxed_slice()
             }
             _ => {
                 return Err(UnexpectedCode::new::<Self>(major).into());
@@ -376,6 +644,7 @@ impl<T: Decode<DagCbor>> Decode<DagCbor> for Option<T> {
         let major = read_u8(r)?;
         let result = match major {
             0xf6 => None,
+            0xf7 if c.is_strict() => return Err(Undefined.into()),
             0xf7 => None,
             _ => {
                 r.seek(SeekFrom::Current(-1))?;
@@ -387,14 +656,15 @@ impl<T: Decode<DagCbor>> Decode<DagCbor> for Option<T> {
 }
 
 impl<T: Decode<DagCbor>> Decode<DagCbor> for Vec<T> {
-    fn decode<R: Read + Seek>(_: DagCbor, r: &mut R) -> Result<Self> {
+    fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
+        let c = c.check_allocation(r)?;
         let major = read_u8(r)?;
         let result = match major {
             0x80..=0x9b => {
-                let len = read_len(r, major - 0x80)?;
-                read_list(r, len)?
+                let len = read_len(c, r, major - 0x80)?;
+                read_list(c, r, len)?
             }
-            0x9f => read_list_il(r)?,
+            0x9f => read_list_il(c, r)?,
             _ => {
                 return Err(UnexpectedCode::new::<Self>(major).into());
             }
@@ -404,14 +674,15 @@ impl<T: Decode<DagCbor>> Decode<DagCbor> for Vec<T> {
 }
 
 impl<K: Decode<DagCbor> + Ord, T: Decode<DagCbor>> Decode<DagCbor> for BTreeMap<K, T> {
-    fn decode<R: Read + Seek>(_: DagCbor, r: &mut R) -> Result<Self> {
+    fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
+        let c = c.check_allocation(r)?;
         let major = read_u8(r)?;
         let result = match major {
             0xa0..=0xbb => {
-                let len = read_len(r, major - 0xa0)?;
-                read_map(r, len)?
+                let len = read_len(c, r, major - 0xa0)?;
+                read_map(c, r, len)?
             }
-            0xbf => read_map_il(r)?,
+            0xbf => read_map_il(c, r)?,
             _ => {
                 return Err(UnexpectedCode::new::<Self>(major).into());
             }
@@ -420,86 +691,91 @@ impl<K: Decode<DagCbor> + Ord, T: Decode<DagCbor>> Decode<DagCbor> for BTreeMap<
     }
 }
 
+/// Peeks at the first key of a map to tell if it is an integer map.
+#[cfg(feature = "unleashed")]
+fn has_integer_keys<R: Read + Seek>(r: &mut R) -> Result<bool> {
+    let major = read_u8(r)?;
+    r.seek(SeekFrom::Current(-1))?;
+    Ok(major != 0xff && major >> 5 <= 1)
+}
+
 impl Decode<DagCbor> for Ipld {
-    fn decode<R: Read + Seek>(_: DagCbor, r: &mut R) -> Result<Self> {
+    fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
+        let c = c.check_allocation(r)?;
         let major = read_u8(r)?;
         let ipld = match major {
             // Major type 0: an unsigned integer
             0x00..=0x17 => Self::Integer(major as i128),
-            0x18 => Self::Integer(read_u8(r)? as i128),
-            0x19 => Self::Integer(read_u16(r)? as i128),
-            0x1a => Self::Integer(read_u32(r)? as i128),
-            0x1b => Self::Integer(read_u64(r)? as i128),
+            0x18 => Self::Integer(minimal(c, major, read_u8(r)?)? as i128),
+            0x19 => Self::Integer(minimal(c, major, read_u16(r)?)? as i128),
+            0x1a => Self::Integer(minimal(c, major, read_u32(r)?)? as i128),
+            0x1b => Self::Integer(minimal(c, major, read_u64(r)?)? as i128),
 
             // Major type 1: a negative integer
             0x20..=0x37 => Self::Integer(-1 - (major - 0x20) as i128),
-            0x38 => Self::Integer(-1 - read_u8(r)? as i128),
-            0x39 => Self::Integer(-1 - read_u16(r)? as i128),
-            0x3a => Self::Integer(-1 - read_u32(r)? as i128),
-            0x3b => Self::Integer(-1 - read_u64(r)? as i128),
+            0x38 => Self::Integer(-1 - minimal(c, major, read_u8(r)?)? as i128),
+            0x39 => Self::Integer(-1 - minimal(c, major, read_u16(r)?)? as i128),
+            0x3a => Self::Integer(-1 - minimal(c, major, read_u32(r)?)? as i128),
+            0x3b => Self::Integer(-1 - minimal(c, major, read_u64(r)?)? as i128),
 
             // Major type 2: a byte string
             0x40..=0x5b => {
-                let len = read_len(r, major - 0x40)?;
-                let bytes = read_bytes(r, len as usize)?;
+                let len = read_len(c, r, major - 0x40)?;
+                let bytes = read_bytes(c, r, len as usize)?;
                 Self::Bytes(bytes)
             }
 
             // Major type 3: a text string
             0x60..=0x7b => {
-                let len = read_len(r, major - 0x60)?;
-                let string = read_str(r, len as usize)?;
+                let len = read_len(c, r, major - 0x60)?;
+                let string = read_str(c, r, len as usize)?;
                 Self::String(string)
             }
 
             // Major type 4: an array of data items
             0x80..=0x9b => {
-                let len = read_len(r, major - 0x80)?;
-                let list = read_list(r, len as usize)?;
+                let len = read_len(c, r, major - 0x80)?;
+                let list = read_list(c, r, len as usize)?;
                 Self::List(list)
             }
 
             // Major type 4: an array of data items (indefinite length)
             0x9f => {
-                let list = read_list_il(r)?;
+                let list = read_list_il(c, r)?;
                 Self::List(list)
             }
 
             // Major type 5: a map of pairs of data items
             0xa0..=0xbb => {
-                let len = read_len(r, major - 0xa0)?;
+                let len = read_len(c, r, major - 0xa0)?;
                 #[cfg(feature = "unleashed")]
-                if len > 0 {
-                    let pos = r.seek(SeekFrom::Current(0))?;
-                    if let Ok(map) = read_map(r, len as usize) {
-                        return Ok(Self::IntegerMap(map));
-                    }
-                    r.seek(SeekFrom::Start(pos))?;
+                if len > 0 && has_integer_keys(r)? {
+                    return Ok(Self::IntegerMap(read_map(c, r, len as usize)?));
                 }
-                Self::StringMap(read_map(r, len as usize)?)
+                Self::StringMap(read_map(c, r, len as usize)?)
             }
 
             // Major type 5: a map of pairs of data items (indefinite length)
             0xbf => {
-                let pos = r.seek(SeekFrom::Current(0))?;
                 #[cfg(feature = "unleashed")]
-                if let Ok(map) = read_map_il(r) {
-                    return Ok(Self::IntegerMap(map));
+                if has_integer_keys(r)? {
+                    return Ok(Self::IntegerMap(read_map_il(c, r)?));
                 }
-                r.seek(SeekFrom::Start(pos))?;
-                Self::StringMap(read_map_il(r)?)
+                Self::StringMap(read_map_il(c, r)?)
             }
 
             // Major type 6: optional semantic tagging of other major types
             0xd8 => {
                 let tag = read_u8(r)?;
                 if tag == 42 {
-                    Self::Link(read_link(r)?)
+                    Self::Link(read_link(c, r)?)
                 } else {
-                    #[cfg(not(feature = "unleashed"))]
-                    return Err(UnknownTag(tag).into());
                     #[cfg(feature = "unleashed")]
-                    Self::Tag(tag as _, Box::new(Self::decode(DagCbor, r)?))
+                    if !c.is_strict() {
+                        let value = Self::decode(c.nested::<Self>()?, r)?;
+                        return Ok(Self::Tag(tag as _, Box::new(value)));
+                    }
+                    return Err(UnknownTag(tag).into());
                 }
             }
 
@@ -507,9 +783,11 @@ impl Decode<DagCbor> for Ipld {
             0xf4 => Self::Bool(false),
             0xf5 => Self::Bool(true),
             0xf6 => Self::Null,
+            0xf7 if c.is_strict() => return Err(Undefined.into()),
             0xf7 => Self::Null,
+            0xfa if c.is_strict() => return Err(NonCanonicalFloat(major).into()),
             0xfa => Self::Float(read_f32(r)? as f64),
-            0xfb => Self::Float(read_f64(r)?),
+            0xfb => Self::Float(read_finite_f64(c, r)?),
             _ => return Err(UnexpectedCode::new::<Self>(major).into()),
         };
         Ok(ipld)
@@ -556,37 +834,42 @@ impl References<DagCbor> for Ipld {
 
             // Major type 2: a byte string
             0x40..=0x5b => {
-                let len = read_len(r, major - 0x40)?;
-                r.seek(SeekFrom::Current(len as _))?;
+                let len = read_len(c, r, major - 0x40)?;
+                skip_len(r, len)?;
             }
 
             // Major type 3: a text string
             0x60..=0x7b => {
-                let len = read_len(r, major - 0x60)?;
-                r.seek(SeekFrom::Current(len as _))?;
+                let len = read_len(c, r, major - 0x60)?;
+                skip_len(r, len)?;
             }
 
             // Major type 4: an array of data items
             0x80..=0x9b => {
-                let len = read_len(r, major - 0x80)?;
+                let len = read_len(c, r, major - 0x80)?;
+                let c = c.nested::<Ipld>()?;
                 for _ in 0..len {
                     <Self as References<DagCbor>>::references(c, r, set)?;
                 }
             }
 
             // Major type 4: an array of data items (indefinite length)
-            0x9f => loop {
-                let major = read_u8(r)?;
-                if major == 0xff {
-                    break;
+            0x9f => {
+                let c = c.nested::<Ipld>()?;
+                loop {
+                    let major = read_u8(r)?;
+                    if major == 0xff {
+                        break;
+                    }
+                    r.seek(SeekFrom::Current(-1))?;
+                    <Self as References<DagCbor>>::references(c, r, set)?;
                 }
-                r.seek(SeekFrom::Current(-1))?;
-                <Self as References<DagCbor>>::references(c, r, set)?;
-            },
+            }
 
             // Major type 5: a map of pairs of data items
             0xa0..=0xbb => {
-                let len = read_len(r, major - 0xa0)?;
+                let len = read_len(c, r, major - 0xa0)?;
+                let c = c.nested::<Ipld>()?;
                 for _ in 0..len {
                     <Self as References<DagCbor>>::references(c, r, set)?;
                     <Self as References<DagCbor>>::references(c, r, set)?;
@@ -594,23 +877,26 @@ impl References<DagCbor> for Ipld {
             }
 
             // Major type 5: a map of pairs of data items (indefinite length)
-            0xbf => loop {
-                let major = read_u8(r)?;
-                if major == 0xff {
-                    break;
+            0xbf => {
+                let c = c.nested::<Ipld>()?;
+                loop {
+                    let major = read_u8(r)?;
+                    if major == 0xff {
+                        break;
+                    }
+                    r.seek(SeekFrom::Current(-1))?;
+                    <Self as References<DagCbor>>::references(c, r, set)?;
+                    <Self as References<DagCbor>>::references(c, r, set)?;
                 }
-                r.seek(SeekFrom::Current(-1))?;
-                <Self as References<DagCbor>>::references(c, r, set)?;
-                <Self as References<DagCbor>>::references(c, r, set)?;
-            },
+            }
 
             // Major type 6: optional semantic tagging of other major types
             0xd8 => {
                 let tag = read_u8(r)?;
                 if tag == 42 {
-                    set.extend(std::iter::once(read_link(r)?));
+                    set.extend(core::iter::once(read_link(c, r)?));
                 } else {
-                    <Self as References<DagCbor>>::references(c, r, set)?;
+                    <Self as References<DagCbor>>::references(c.nested::<Ipld>()?, r, set)?;
                 }
             }
 
@@ -655,6 +941,7 @@ impl Decode<DagCbor> for () {
 
 impl<A: Decode<DagCbor>> Decode<DagCbor> for (A,) {
     fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
+        let c = c.check_allocation(r)?;
         let major = read_u8(r)?;
         let result = match major {
             0x81 => (A::decode(c, r)?,),
@@ -668,6 +955,7 @@ impl<A: Decode<DagCbor>> Decode<DagCbor> for (A,) {
 
 impl<A: Decode<DagCbor>, B: Decode<DagCbor>> Decode<DagCbor> for (A, B) {
     fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
+        let c = c.check_allocation(r)?;
         let major = read_u8(r)?;
         let result = match major {
             0x82 => (A::decode(c, r)?, B::decode(c, r)?),
@@ -681,6 +969,7 @@ impl<A: Decode<DagCbor>, B: Decode<DagCbor>> Decode<DagCbor> for (A, B) {
 
 impl<A: Decode<DagCbor>, B: Decode<DagCbor>, C: Decode<DagCbor>> Decode<DagCbor> for (A, B, C) {
     fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
+        let c = c.check_allocation(r)?;
         let major = read_u8(r)?;
         let result = match major {
             0x83 => (A::decode(c, r)?, B::decode(c, r)?, C::decode(c, r)?),
@@ -696,6 +985,7 @@ impl<A: Decode<DagCbor>, B: Decode<DagCbor>, C: Decode<DagCbor>, D: Decode<DagCb
     for (A, B, C, D)
 {
     fn decode<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<Self> {
+        let c = c.check_allocation(r)?;
         let major = read_u8(r)?;
         let result = match major {
             0x84 => (
@@ -712,138 +1002,422 @@ impl<A: Decode<DagCbor>, B: Decode<DagCbor>, C: Decode<DagCbor>, D: Decode<DagCb
     }
 }
 
-impl SkipOne for DagCbor {
-    fn skip<R: Read + Seek>(&self, r: &mut R) -> Result<()> {
+/// Borrows `len` bytes from a cursor over a byte slice.
+pub fn read_bytes_ref<'a>(c: DagCbor, r: &mut Cursor<&'a [u8]>, len: usize) -> Result<&'a [u8]> {
+    check_string_len::<&[u8]>(c, len)?;
+    let bytes: &'a [u8] = r.get_ref();
+    let start = usize::try_from(r.position()).map_err(|_| LengthOutOfRange::new::<&[u8]>())?;
+    let end = start
+        .checked_add(len)
+        .filter(|end| *end <= bytes.len())
+        .ok_or_else(|| libipld_core::io::Error::from(ErrorKind::UnexpectedEof))?;
+    r.set_position(end as u64);
+    Ok(&bytes[start..end])
+}
+
+/// Borrows a string of `len` bytes from a cursor over a byte slice.
+pub fn read_str_ref<'a>(c: DagCbor, r: &mut Cursor<&'a [u8]>, len: usize) -> Result<&'a str> {
+    let bytes = read_bytes_ref(c, r, len)?;
+    Ok(core::str::from_utf8(bytes)?)
+}
+
+macro_rules! impl_decode_ref {
+    ($($ty:ty),*) => {
+        $(
+            impl<'a> DecodeRef<'a, DagCbor> for $ty {
+                fn decode_ref(c: DagCbor, r: &mut Cursor<&'a [u8]>) -> Result<Self> {
+                    Self::decode(c, r)
+                }
+            }
+        )*
+    };
+}
+
+impl_decode_ref!(
+    bool,
+    u8,
+    u16,
+    u32,
+    u64,
+    i8,
+    i16,
+    i32,
+    i64,
+    f32,
+    f64,
+    String,
+    Cid,
+    Box<[u8]>,
+    Ipld,
+    ()
+);
+
+impl<'de: 'a, 'a> DecodeRef<'de, DagCbor> for &'a str {
+    fn decode_ref(c: DagCbor, r: &mut Cursor<&'de [u8]>) -> Result<Self> {
         let major = read_u8(r)?;
         match major {
-            // Major type 0: an unsigned integer
-            0x00..=0x17 => {}
-            0x18 => {
-                r.seek(SeekFrom::Current(1))?;
-            }
-            0x19 => {
-                r.seek(SeekFrom::Current(2))?;
+            0x60..=0x7b => {
+                let len = read_len(c, r, major - 0x60)?;
+                read_str_ref(c, r, len)
             }
-            0x1a => {
-                r.seek(SeekFrom::Current(4))?;
+            _ => Err(UnexpectedCode::new::<Self>(major).into()),
+        }
+    }
+}
+
+impl<'de: 'a, 'a> DecodeRef<'de, DagCbor> for &'a [u8] {
+    fn decode_ref(c: DagCbor, r: &mut Cursor<&'de [u8]>) -> Result<Self> {
+        let major = read_u8(r)?;
+        match major {
+            0x40..=0x5b => {
+                let len = read_len(c, r, major - 0x40)?;
+                read_bytes_ref(c, r, len)
             }
-            0x1b => {
-                r.seek(SeekFrom::Current(8))?;
+            _ => Err(UnexpectedCode::new::<Self>(major).into()),
+        }
+    }
+}
+
+impl<'de: 'a, 'a> DecodeRef<'de, DagCbor> for Cow<'a, str> {
+    fn decode_ref(c: DagCbor, r: &mut Cursor<&'de [u8]>) -> Result<Self> {
+        Ok(Cow::Borrowed(<&str>::decode_ref(c, r)?))
+    }
+}
+
+impl<'de: 'a, 'a> DecodeRef<'de, DagCbor> for Cow<'a, [u8]> {
+    fn decode_ref(c: DagCbor, r: &mut Cursor<&'de [u8]>) -> Result<Self> {
+        Ok(Cow::Borrowed(<&[u8]>::decode_ref(c, r)?))
+    }
+}
+
+impl<'a, T: DecodeRef<'a, DagCbor>> DecodeRef<'a, DagCbor> for Option<T> {
+    fn decode_ref(c: DagCbor, r: &mut Cursor<&'a [u8]>) -> Result<Self> {
+        let major = read_u8(r)?;
+        let result = match major {
+            0xf6 => None,
+            0xf7 if c.is_strict() => return Err(Undefined.into()),
+            0xf7 => None,
+            _ => {
+                r.seek(SeekFrom::Current(-1))?;
+                Some(T::decode_ref(c, r)?)
             }
+        };
+        Ok(result)
+    }
+}
 
-            // Major type 1: a negative integer
-            0x20..=0x37 => {}
-            0x38 => {
-                r.seek(SeekFrom::Current(1))?;
+impl<'a, T: DecodeRef<'a, DagCbor>> DecodeRef<'a, DagCbor> for Vec<T> {
+    fn decode_ref(c: DagCbor, r: &mut Cursor<&'a [u8]>) -> Result<Self> {
+        let c = c.check_allocation(r)?;
+        let major = read_u8(r)?;
+        let result = match major {
+            0x80..=0x9b => {
+                let len = read_len(c, r, major - 0x80)?;
+                read_list_with(c, r, len, T::decode_ref)?
             }
-            0x39 => {
-                r.seek(SeekFrom::Current(2))?;
+            0x9f => read_list_il_with(c, r, T::decode_ref)?,
+            _ => {
+                return Err(UnexpectedCode::new::<Self>(major).into());
             }
-            0x3a => {
-                r.seek(SeekFrom::Current(4))?;
+        };
+        Ok(result)
+    }
+}
+
+impl<'a, K, T> DecodeRef<'a, DagCbor> for BTreeMap<K, T>
+where
+    K: DecodeRef<'a, DagCbor> + Ord,
+    T: DecodeRef<'a, DagCbor>,
+{
+    fn decode_ref(c: DagCbor, r: &mut Cursor<&'a [u8]>) -> Result<Self> {
+        let c = c.check_allocation(r)?;
+        let major = read_u8(r)?;
+        let result = match major {
+            0xa0..=0xbb => {
+                let len = read_len(c, r, major - 0xa0)?;
+                read_map_with(c, r, len, K::decode_ref, T::decode_ref)?
             }
-            0x3b => {
-                r.seek(SeekFrom::Current(8))?;
+            0xbf => read_map_il_with(c, r, K::decode_ref, T::decode_ref)?,
+            _ => {
+                return Err(UnexpectedCode::new::<Self>(major).into());
             }
+        };
+        Ok(result)
+    }
+}
 
+impl<'de: 'a, 'a> DecodeRef<'de, DagCbor> for IpldRef<'a> {
+    fn decode_ref(c: DagCbor, r: &mut Cursor<&'de [u8]>) -> Result<Self> {
+        let c = c.check_allocation(r)?;
+        let major = read_u8(r)?;
+        let ipld = match major {
             // Major type 2: a byte string
             0x40..=0x5b => {
-                let len = read_len(r, major - 0x40)?;
-                r.seek(SeekFrom::Current(len as _))?;
+                let len = read_len(c, r, major - 0x40)?;
+                Self::Bytes(read_bytes_ref(c, r, len)?)
             }
 
             // Major type 3: a text string
             0x60..=0x7b => {
-                let len = read_len(r, major - 0x60)?;
-                r.seek(SeekFrom::Current(len as _))?;
+                let len = read_len(c, r, major - 0x60)?;
+                Self::String(read_str_ref(c, r, len)?)
             }
 
             // Major type 4: an array of data items
             0x80..=0x9b => {
-                let len = read_len(r, major - 0x80)?;
-                for _ in 0..len {
-                    self.skip(r)?;
-                }
+                let len = read_len(c, r, major - 0x80)?;
+                Self::List(read_list_with(c, r, len, Self::decode_ref)?)
             }
 
             // Major type 4: an array of data items (indefinite length)
-            0x9f => loop {
-                let major = read_u8(r)?;
-                if major == 0xff {
-                    break;
-                }
-                r.seek(SeekFrom::Current(-1))?;
-                self.skip(r)?;
-            },
+            0x9f => Self::List(read_list_il_with(c, r, Self::decode_ref)?),
 
             // Major type 5: a map of pairs of data items
             0xa0..=0xbb => {
-                let len = read_len(r, major - 0xa0)?;
-                for _ in 0..len {
-                    self.skip(r)?;
-                    self.skip(r)?;
+                let len = read_len(c, r, major - 0xa0)?;
+                #[cfg(feature = "unleashed")]
+                if len > 0 && has_integer_keys(r)? {
+                    let map = read_map_with(c, r, len, i64::decode, Self::decode_ref)?;
+                    return Ok(Self::IntegerMap(map));
                 }
+                Self::StringMap(read_map_with(
+                    c,
+                    r,
+                    len,
+                    <&str>::decode_ref,
+                    Self::decode_ref,
+                )?)
             }
 
             // Major type 5: a map of pairs of data items (indefinite length)
-            0xbf => loop {
-                let major = read_u8(r)?;
-                if major == 0xff {
-                    break;
+            0xbf => {
+                #[cfg(feature = "unleashed")]
+                if has_integer_keys(r)? {
+                    let map = read_map_il_with(c, r, i64::decode, Self::decode_ref)?;
+                    return Ok(Self::IntegerMap(map));
                 }
-                r.seek(SeekFrom::Current(-1))?;
-                self.skip(r)?;
-                self.skip(r)?;
-            },
+                Self::StringMap(read_map_il_with(
+                    c,
+                    r,
+                    <&str>::decode_ref,
+                    Self::decode_ref,
+                )?)
+            }
 
             // Major type 6: optional semantic tagging of other major types
-            0xc0..=0xd7 => {
-                // let _tag = major - 0xc0;
-                self.skip(r)?;
-            }
             0xd8 => {
-                r.seek(SeekFrom::Current(1))?;
-                self.skip(r)?;
+                let tag = read_u8(r)?;
+                if tag == 42 {
+                    Self::Link(read_link(c, r)?)
+                } else {
+                    #[cfg(feature = "unleashed")]
+                    if !c.is_strict() {
+                        let value = Self::decode_ref(c.nested::<Self>()?, r)?;
+                        return Ok(Self::Tag(tag as _, Box::new(value)));
+                    }
+                    return Err(UnknownTag(tag).into());
+                }
             }
 
-            0xd9 => {
-                r.seek(SeekFrom::Current(2))?;
-                self.skip(r)?;
-            }
-            0xda => {
-                r.seek(SeekFrom::Current(4))?;
-                self.skip(r)?;
-            }
-            0xdb => {
-                r.seek(SeekFrom::Current(8))?;
-                self.skip(r)?;
+            // Everything else doesn't borrow
+            _ => {
+                r.seek(SeekFrom::Current(-1))?;
+                match Ipld::decode(c, r)? {
+                    Ipld::Null => Self::Null,
+                    Ipld::Bool(b) => Self::Bool(b),
+                    Ipld::Integer(i) => Self::Integer(i),
+                    Ipld::Float(f) => Self::Float(f),
+                    _ => unreachable!("strings, bytes, lists, maps and tags are handled above"),
+                }
             }
+        };
+        Ok(ipld)
+    }
+}
 
-            // Major type 7: floating-point numbers and other simple data types that need no content
-            0xf4..=0xf7 => {}
-            0xf8 => {
-                r.seek(SeekFrom::Current(1))?;
+/// Checks that the next data item doesn't exceed the limits of the codec without allocating.
+///
+/// The stream is rewound to the start of the data item afterwards.
+pub fn check_limits<R: Read + Seek>(c: DagCbor, r: &mut R) -> Result<()> {
+    let pos = r.stream_position()?;
+    scan(c, r, &mut 0)?;
+    r.seek(SeekFrom::Start(pos))?;
+    Ok(())
+}
+
+/// Adds `bytes` to the `allocated` estimate and fails if it exceeds the allocation limit.
+fn allocate(c: DagCbor, allocated: &mut usize, bytes: usize) -> Result<()> {
+    *allocated = allocated.saturating_add(bytes);
+    if *allocated > c.limits().max_allocation {
+        return Err(LimitExceeded::new::<Ipld>(Limit::Allocation).into());
+    }
+    Ok(())
+}
+
+/// Skips the next data item, checking the limits of the codec and adding the estimated
+/// allocation to decode it to `allocated`.
+fn scan<R: Read + Seek>(c: DagCbor, r: &mut R, allocated: &mut usize) -> Result<()> {
+    let major = read_u8(r)?;
+    match major {
+        // Major type 0: an unsigned integer
+        0x00..=0x17 => {}
+        0x18 => {
+            r.seek(SeekFrom::Current(1))?;
+        }
+        0x19 => {
+            r.seek(SeekFrom::Current(2))?;
+        }
+        0x1a => {
+            r.seek(SeekFrom::Current(4))?;
+        }
+        0x1b => {
+            r.seek(SeekFrom::Current(8))?;
+        }
+
+        // Major type 1: a negative integer
+        0x20..=0x37 => {}
+        0x38 => {
+            r.seek(SeekFrom::Current(1))?;
+        }
+        0x39 => {
+            r.seek(SeekFrom::Current(2))?;
+        }
+        0x3a => {
+            r.seek(SeekFrom::Current(4))?;
+        }
+        0x3b => {
+            r.seek(SeekFrom::Current(8))?;
+        }
+
+        // Major type 2: a byte string
+        0x40..=0x5b => {
+            let len = read_len(c, r, major - 0x40)?;
+            check_string_len::<Ipld>(c, len)?;
+            allocate(c, allocated, len)?;
+            skip_len(r, len)?;
+        }
+
+        // Major type 3: a text string
+        0x60..=0x7b => {
+            let len = read_len(c, r, major - 0x60)?;
+            check_string_len::<Ipld>(c, len)?;
+            allocate(c, allocated, len)?;
+            skip_len(r, len)?;
+        }
+
+        // Major type 4: an array of data items
+        0x80..=0x9b => {
+            let len = read_len(c, r, major - 0x80)?;
+            check_collection_len::<Ipld>(c, len)?;
+            allocate(c, allocated, len.saturating_mul(size_of::<Ipld>()))?;
+            let c = c.nested::<Ipld>()?;
+            for _ in 0..len {
+                scan(c, r, allocated)?;
             }
-            0xf9 => {
-                r.seek(SeekFrom::Current(2))?;
+        }
+
+        // Major type 4: an array of data items (indefinite length)
+        0x9f => {
+            let c = c.nested::<Ipld>()?;
+            let mut len = 0;
+            loop {
+                let major = read_u8(r)?;
+                if major == 0xff {
+                    break;
+                }
+                len += 1;
+                check_collection_len::<Ipld>(c, len)?;
+                allocate(c, allocated, size_of::<Ipld>())?;
+                r.seek(SeekFrom::Current(-1))?;
+                scan(c, r, allocated)?;
             }
-            0xfa => {
-                r.seek(SeekFrom::Current(4))?;
+        }
+
+        // Major type 5: a map of pairs of data items
+        0xa0..=0xbb => {
+            let len = read_len(c, r, major - 0xa0)?;
+            check_collection_len::<Ipld>(c, len)?;
+            allocate(c, allocated, len.saturating_mul(2 * size_of::<Ipld>()))?;
+            let c = c.nested::<Ipld>()?;
+            for _ in 0..len {
+                scan(c, r, allocated)?;
+                scan(c, r, allocated)?;
             }
-            0xfb => {
-                r.seek(SeekFrom::Current(8))?;
+        }
+
+        // Major type 5: a map of pairs of data items (indefinite length)
+        0xbf => {
+            let c = c.nested::<Ipld>()?;
+            let mut len = 0;
+            loop {
+                let major = read_u8(r)?;
+                if major == 0xff {
+                    break;
+                }
+                len += 1;
+                check_collection_len::<Ipld>(c, len)?;
+                allocate(c, allocated, 2 * size_of::<Ipld>())?;
+                r.seek(SeekFrom::Current(-1))?;
+                scan(c, r, allocated)?;
+                scan(c, r, allocated)?;
             }
-            major => return Err(UnexpectedCode::new::<Ipld>(major).into()),
-        };
-        Ok(())
+        }
+
+        // Major type 6: optional semantic tagging of other major types
+        0xc0..=0xd7 => {
+            // let _tag = major - 0xc0;
+            scan(c.nested::<Ipld>()?, r, allocated)?;
+        }
+        0xd8 => {
+            r.seek(SeekFrom::Current(1))?;
+            scan(c.nested::<Ipld>()?, r, allocated)?;
+        }
+
+        0xd9 => {
+            r.seek(SeekFrom::Current(2))?;
+            scan(c.nested::<Ipld>()?, r, allocated)?;
+        }
+        0xda => {
+            r.seek(SeekFrom::Current(4))?;
+            scan(c.nested::<Ipld>()?, r, allocated)?;
+        }
+        0xdb => {
+            r.seek(SeekFrom::Current(8))?;
+            scan(c.nested::<Ipld>()?, r, allocated)?;
+        }
+
+        // Major type 7: floating-point numbers and other simple data types that need no content
+        0xf4..=0xf7 => {}
+        0xf8 => {
+            r.seek(SeekFrom::Current(1))?;
+        }
+        0xf9 => {
+            r.seek(SeekFrom::Current(2))?;
+        }
+        0xfa => {
+            r.seek(SeekFrom::Current(4))?;
+        }
+        0xfb => {
+            r.seek(SeekFrom::Current(8))?;
+        }
+        major => return Err(UnexpectedCode::new::<Ipld>(major).into()),
+    };
+    Ok(())
+}
+
+impl SkipOne for DagCbor {
+    fn skip<R: Read + Seek>(&self, r: &mut R) -> Result<()> {
+        scan(*self, r, &mut 0)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::DagCborCodec;
+    use crate::error::CborError;
+    use crate::{DagCborCodec, DecodeLimits};
     use libipld_core::codec::Codec;
+    use libipld_core::error::Error;
     use libipld_macro::ipld;
 
     #[test]
@@ -866,6 +1440,268 @@ mod tests {
         assert_eq!(ipld, ipld2);
     }
 
+    #[test]
+    fn strict() {
+        fn strict_err<T: Decode<DagCbor> + std::fmt::Debug>(bytes: &[u8]) -> Error {
+            DagCbor.decode::<T>(bytes).unwrap();
+            DagCbor::strict().decode::<T>(bytes).unwrap_err()
+        }
+        let err = strict_err::<Ipld>(&[0x18, 0x01]);
+        assert!(matches!(err, Error::Cbor(CborError::NonMinimalInteger(_))));
+        let err = strict_err::<u32>(&[0x19, 0x00, 0xff]);
+        assert!(matches!(err, Error::Cbor(CborError::NonMinimalInteger(_))));
+        let err = strict_err::<String>(&[0x78, 0x01, 0x61]);
+        assert!(matches!(err, Error::Cbor(CborError::NonMinimalInteger(_))));
+        let err = strict_err::<Ipld>(&[0x9f, 0x01, 0xff]);
+        assert!(matches!(err, Error::Cbor(CborError::IndefiniteLength(_))));
+        let err = strict_err::<Vec<u8>>(&[0x9f, 0x01, 0xff]);
+        assert!(matches!(err, Error::Cbor(CborError::IndefiniteLength(_))));
+        let err = strict_err::<Ipld>(&[0xfa, 0x3f, 0xc0, 0x00, 0x00]);
+        assert!(matches!(err, Error::Cbor(CborError::NonCanonicalFloat(_))));
+        let err = strict_err::<f64>(&[0xfb, 0x7f, 0xf8, 0, 0, 0, 0, 0, 0]);
+        assert!(matches!(err, Error::Cbor(CborError::NonFiniteFloat(_))));
+        // 0.1 isn't exactly representable as a f32
+        let bytes = [0xfb, 0x3f, 0xb9, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a];
+        let err = DagCbor::strict().decode::<f32>(&bytes).unwrap_err();
+        assert!(matches!(err, Error::Cbor(CborError::NumberOutOfRange(_))));
+        let bytes = [0xfb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0];
+        assert_eq!(DagCbor::strict().decode::<f32>(&bytes).unwrap(), 1.5);
+        let err = strict_err::<Ipld>(&[0xf7]);
+        assert!(matches!(err, Error::Cbor(CborError::Undefined(_))));
+        // {"b": 1, "aa": 2} is sorted lexicographically but not length first
+        let err = strict_err::<Ipld>(&[0xa2, 0x62, 0x61, 0x61, 0x02, 0x61, 0x62, 0x01]);
+        assert!(matches!(err, Error::Cbor(CborError::UnsortedMapKeys(_))));
+        let err = strict_err::<BTreeMap<String, u8>>(&[0xa2, 0x61, 0x61, 0x01, 0x61, 0x61, 0x02]);
+        assert!(matches!(err, Error::Cbor(CborError::DuplicateMapKey(_))));
+
+        let ipld = ipld!({
+            "a": 1,
+            "bb": [true, null, -500, "hello"],
+            "ccc": { "x": 1.5 },
+        });
+        let bytes = [
+            0xa3, 0x61, 0x61, 0x01, 0x62, 0x62, 0x62, 0x84, 0xf5, 0xf6, 0x39, 0x01, 0xf3, 0x65,
+            0x68, 0x65, 0x6c, 0x6c, 0x6f, 0x63, 0x63, 0x63, 0x63, 0xa1, 0x61, 0x78, 0xfb, 0x3f,
+            0xf8, 0, 0, 0, 0, 0, 0,
+        ];
+        let ipld2: Ipld = DagCbor::strict().decode(&bytes).unwrap();
+        assert_eq!(ipld, ipld2);
+    }
+
+    #[test]
+    fn limits() {
+        fn limit_err<T: Decode<DagCbor> + std::fmt::Debug>(
+            limits: DecodeLimits,
+            bytes: &[u8],
+        ) -> Limit {
+            DagCbor.decode::<T>(bytes).unwrap();
+            let err = DagCbor.with_limits(limits).decode::<T>(bytes).unwrap_err();
+            match err {
+                Error::Cbor(CborError::LimitExceeded(err)) => err.limit,
+                err => panic!("unexpected error {}", err),
+            }
+        }
+        let limits = DecodeLimits {
+            max_depth: 2,
+            max_collection_len: 2,
+            max_string_len: 2,
+            ..DecodeLimits::default()
+        };
+        // [[[1]]]
+        assert_eq!(
+            limit_err::<Ipld>(limits, &[0x81, 0x81, 0x81, 0x01]),
+            Limit::Depth
+        );
+        assert_eq!(
+            limit_err::<Vec<Vec<Vec<u8>>>>(limits, &[0x81, 0x81, 0x81, 0x01]),
+            Limit::Depth
+        );
+        assert_eq!(
+            limit_err::<Ipld>(limits, &[0x83, 0x01, 0x02, 0x03]),
+            Limit::CollectionLen
+        );
+        assert_eq!(
+            limit_err::<Vec<u8>>(limits, &[0x9f, 0x01, 0x02, 0x03, 0xff]),
+            Limit::CollectionLen
+        );
+        assert_eq!(
+            limit_err::<String>(limits, &[0x63, 0x61, 0x61, 0x61]),
+            Limit::StringLen
+        );
+        let bytes = DagCbor.encode(&Ipld::Bytes(vec![0; 65])).unwrap();
+        let limits = DecodeLimits {
+            max_allocation: 64,
+            ..DecodeLimits::default()
+        };
+        assert_eq!(limit_err::<Ipld>(limits, &bytes), Limit::Allocation);
+        // the allocation limit holds when decoding without the codec
+        let c = DagCbor.with_limits(limits);
+        assert!(Ipld::decode(c, &mut Cursor::new(&bytes)).is_err());
+        assert!(Vec::<Ipld>::decode(c, &mut Cursor::new(&[0x81, 0x58, 0x41])).is_err());
+        let bytes = DagCbor.encode(&Ipld::Bytes(vec![0; 64])).unwrap();
+        DagCbor.with_limits(limits).decode::<Ipld>(&bytes).unwrap();
+
+        // the default limits reject input nested deep enough to overflow the stack, the maximum
+        // depth needs a larger stack than the test thread has in debug builds
+        std::thread::Builder::new()
+            .stack_size(8 * 1024 * 1024)
+            .spawn(|| {
+                let mut nested = vec![0x81; 100_000];
+                nested.push(0xf6);
+                let err = DagCbor.decode::<Ipld>(&nested).unwrap_err();
+                assert!(matches!(
+                    err,
+                    Error::Cbor(CborError::LimitExceeded(LimitExceeded {
+                        limit: Limit::Depth,
+                        ..
+                    }))
+                ));
+                let mut nested = vec![0x81; DecodeLimits::DEFAULT_MAX_DEPTH];
+                nested.push(0xf6);
+                DagCbor.decode::<Ipld>(&nested).unwrap();
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+
+        // data nested as deep as earlier versions stored is decoded by default
+        let mut ipld = Ipld::Null;
+        for _ in 0..100 {
+            ipld = Ipld::List(vec![ipld]);
+        }
+        let bytes = DagCbor.encode(&ipld).unwrap();
+        assert_eq!(DagCbor.decode::<Ipld>(&bytes).unwrap(), ipld);
+        DagCbor
+            .references::<Ipld, _>(&bytes, &mut Vec::new())
+            .unwrap();
+
+        // lengths are not trusted for allocations
+        assert!(DagCbor
+            .decode::<Ipld>(&[0x5b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff])
+            .is_err());
+        assert!(DagCbor
+            .decode::<Ipld>(&[0x9b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff])
+            .is_err());
+
+        // a length that doesn't fit a seek offset must not seek back, here over the header of
+        // the byte string for every item of the list
+        let bytes = [
+            0x9b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x5b, 0xff, 0xff, 0xff, 0xff,
+            0xff, 0xff, 0xff, 0xf7,
+        ];
+        assert!(DagCbor
+            .references::<Ipld, _>(&bytes, &mut Vec::new())
+            .is_err());
+        assert!(DagCbor
+            .decode::<libipld_core::raw_value::IgnoredAny>(&bytes)
+            .is_err());
+        assert!(DagCbor.decode::<RawValue<DagCbor>>(&bytes).is_err());
+        assert!(DagCbor
+            .with_limits(DecodeLimits {
+                max_allocation: 1 << 20,
+                ..DecodeLimits::default()
+            })
+            .decode::<Ipld>(&bytes)
+            .is_err());
+    }
+
+    #[test]
+    fn non_seekable_reader() {
+        let ipld = ipld!({
+            "a": [1, "two", { "three": 3.0 }],
+            "b": null,
+        });
+        let mut bytes = DagCbor.encode(&ipld).unwrap();
+        // a second value follows the first one
+        bytes.push(0xf5);
+        let mut r = &bytes[..];
+        let ipld2: Ipld = DagCbor.decode_from_reader(&mut r).unwrap();
+        assert_eq!(ipld, ipld2);
+        let ipld2: Ipld = DagCbor::strict().decode_from_reader(&mut r).unwrap();
+        assert_eq!(ipld2, Ipld::Bool(true));
+        assert!(r.is_empty());
+
+        let limits = DecodeLimits {
+            max_allocation: 64,
+            ..DecodeLimits::default()
+        };
+        let err = DagCbor
+            .with_limits(limits)
+            .decode_from_reader::<Ipld, _>(&bytes[..])
+            .unwrap_err();
+        assert!(matches!(err, Error::Cbor(CborError::LimitExceeded(_))));
+
+        // neither long keys nor checking allocations need seeking back further than a peek
+        let ipld = ipld!({
+            "a key longer than the lookbehind": 1,
+            "another key longer than the lookbehind": [2],
+        });
+        let mut bytes = DagCbor.encode(&ipld).unwrap();
+        bytes.push(0xf5);
+        let mut r = &bytes[..];
+        let ipld2: Ipld = DagCbor::strict().decode_from_reader(&mut r).unwrap();
+        assert_eq!(ipld, ipld2);
+        assert_eq!(r, [0xf5]);
+        let limits = DecodeLimits {
+            max_allocation: 1 << 10,
+            ..DecodeLimits::default()
+        };
+        let mut r = &bytes[..];
+        let ipld2: Ipld = DagCbor::strict()
+            .with_limits(limits)
+            .decode_from_reader(&mut r)
+            .unwrap();
+        assert_eq!(ipld, ipld2);
+        assert_eq!(r, [0xf5]);
+    }
+
+    #[test]
+    fn borrowed() {
+        let ipld = ipld!({
+            "a": [1, "two", { "three": 3.0 }],
+            "b": null,
+            "c": vec![1u8, 2, 3],
+        });
+        let bytes = DagCbor.encode(&ipld).unwrap();
+        let ipld2: IpldRef = DagCbor.decode_ref(&bytes).unwrap();
+        assert_eq!(IpldRef::from(&ipld), ipld2);
+        assert_eq!(Ipld::from(ipld2), ipld);
+
+        let bytes = DagCbor::strict().encode(&ipld).unwrap();
+        let map: BTreeMap<&str, Ipld> = DagCbor::strict().decode_ref(&bytes).unwrap();
+        assert_eq!(map.len(), 3);
+        let bytes = DagCbor.encode("hello").unwrap();
+        let s: Cow<str> = DagCbor.decode_ref(&bytes).unwrap();
+        assert!(matches!(s, Cow::Borrowed("hello")));
+        let err = DagCbor.decode_ref::<&str>(&bytes[..3]).unwrap_err();
+        assert!(matches!(err, Error::Io(_)));
+    }
+
+    #[test]
+    fn links() {
+        use libipld_core::multihash::{Code, MultihashDigest};
+
+        let v0 = Cid::new_v0(Code::Sha2_256.digest(b"v0")).unwrap();
+        let v1 = Cid::new_v1(0x71, Code::Blake3_256.digest(b"v1"));
+        for cid in [v0, v1] {
+            let cid_bytes = cid.to_bytes();
+            let mut expected = vec![0xd8, 0x2a, 0x58, cid_bytes.len() as u8 + 1, 0];
+            expected.extend_from_slice(&cid_bytes);
+            let bytes = DagCbor.encode(&cid).unwrap();
+            assert_eq!(bytes, expected);
+            let cid2: Cid = DagCbor::strict().decode(&bytes).unwrap();
+            assert_eq!(cid2, cid);
+        }
+
+        let mut bytes = DagCbor.encode(&v1).unwrap();
+        let len = bytes.len();
+        // truncate the digest but keep the lengths
+        bytes[3] -= 1;
+        bytes.truncate(len - 1);
+        let err = DagCbor.decode::<Cid>(&bytes).unwrap_err();
+        assert!(matches!(err, Error::Cid(_)));
+    }
+
     #[test]
     fn tuples() -> Result<()> {
         let data = ();
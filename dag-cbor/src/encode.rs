@@ -1,15 +1,24 @@
 //! CBOR encoder.
-use crate::error::NumberOutOfRange;
-use crate::DagCborCodec as DagCbor;
+use crate::error::{NonFiniteFloat, NumberOutOfRange};
+use crate::{DagCborCodec as DagCbor, EncodingVersion};
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    collections::BTreeMap,
+    string::String,
+    sync::Arc,
+    vec::Vec,
+};
 use byteorder::{BigEndian, ByteOrder};
-use libipld_core::cid::Cid;
+use core::ops::Deref;
+use libipld_core::cid::{Cid, Version};
 use libipld_core::codec::Encode;
 use libipld_core::error::Result;
+use libipld_core::io::Write;
 use libipld_core::ipld::Ipld;
-use std::collections::BTreeMap;
-use std::io::Write;
-use std::ops::Deref;
-use std::sync::Arc;
+use libipld_core::ipld_arc::IpldArc;
+use libipld_core::ipld_ref::IpldRef;
+use unsigned_varint::encode as varint;
 
 /// Writes a null byte to a cbor encoded byte stream.
 pub fn write_null<W: Write>(w: &mut W) -> Result<()> {
@@ -31,7 +40,7 @@ pub fn write_u8<W: Write>(w: &mut W, major: u8, value: u8) -> Result<()> {
 
 /// Writes a u16 to a cbor encoded byte stream.
 pub fn write_u16<W: Write>(w: &mut W, major: u8, value: u16) -> Result<()> {
-    if value <= u16::from(u8::max_value()) {
+    if value <= u16::from(u8::MAX) {
         write_u8(w, major, value as u8)?;
     } else {
         let mut buf = [major << 5 | 25, 0, 0];
@@ -43,7 +52,7 @@ pub fn write_u16<W: Write>(w: &mut W, major: u8, value: u16) -> Result<()> {
 
 /// Writes a u32 to a cbor encoded byte stream.
 pub fn write_u32<W: Write>(w: &mut W, major: u8, value: u32) -> Result<()> {
-    if value <= u32::from(u16::max_value()) {
+    if value <= u32::from(u16::MAX) {
         write_u16(w, major, value as u16)?;
     } else {
         let mut buf = [major << 5 | 26, 0, 0, 0, 0];
@@ -55,7 +64,7 @@ pub fn write_u32<W: Write>(w: &mut W, major: u8, value: u32) -> Result<()> {
 
 /// Writes a u64 to a cbor encoded byte stream.
 pub fn write_u64<W: Write>(w: &mut W, major: u8, value: u64) -> Result<()> {
-    if value <= u64::from(u32::max_value()) {
+    if value <= u64::from(u32::MAX) {
         write_u32(w, major, value as u32)?;
     } else {
         let mut buf = [major << 5 | 27, 0, 0, 0, 0, 0, 0, 0, 0];
@@ -103,32 +112,51 @@ impl Encode<DagCbor> for u64 {
 }
 
 impl Encode<DagCbor> for i8 {
-    fn encode<W: Write>(&self, _: DagCbor, w: &mut W) -> Result<()> {
-        write_u8(w, 1, -(*self + 1) as u8)
+    fn encode<W: Write>(&self, c: DagCbor, w: &mut W) -> Result<()> {
+        if *self >= 0 && c.version() == EncodingVersion::Conformant {
+            write_u8(w, 0, *self as u8)
+        } else {
+            write_u8(w, 1, !*self as u8)
+        }
     }
 }
 
 impl Encode<DagCbor> for i16 {
-    fn encode<W: Write>(&self, _: DagCbor, w: &mut W) -> Result<()> {
-        write_u16(w, 1, -(*self + 1) as u16)
+    fn encode<W: Write>(&self, c: DagCbor, w: &mut W) -> Result<()> {
+        if *self >= 0 && c.version() == EncodingVersion::Conformant {
+            write_u16(w, 0, *self as u16)
+        } else {
+            write_u16(w, 1, !*self as u16)
+        }
     }
 }
 
 impl Encode<DagCbor> for i32 {
-    fn encode<W: Write>(&self, _: DagCbor, w: &mut W) -> Result<()> {
-        write_u32(w, 1, -(*self + 1) as u32)
+    fn encode<W: Write>(&self, c: DagCbor, w: &mut W) -> Result<()> {
+        if *self >= 0 && c.version() == EncodingVersion::Conformant {
+            write_u32(w, 0, *self as u32)
+        } else {
+            write_u32(w, 1, !*self as u32)
+        }
     }
 }
 
 impl Encode<DagCbor> for i64 {
-    fn encode<W: Write>(&self, _: DagCbor, w: &mut W) -> Result<()> {
-        write_u64(w, 1, -(*self + 1) as u64)
+    fn encode<W: Write>(&self, c: DagCbor, w: &mut W) -> Result<()> {
+        if *self >= 0 && c.version() == EncodingVersion::Conformant {
+            write_u64(w, 0, *self as u64)
+        } else {
+            write_u64(w, 1, !*self as u64)
+        }
     }
 }
 
 impl Encode<DagCbor> for f32 {
     #[allow(clippy::float_cmp)]
-    fn encode<W: Write>(&self, _: DagCbor, w: &mut W) -> Result<()> {
+    fn encode<W: Write>(&self, c: DagCbor, w: &mut W) -> Result<()> {
+        if c.version() == EncodingVersion::Conformant {
+            return f64::from(*self).encode(c, w);
+        }
         if self.is_infinite() {
             if self.is_sign_positive() {
                 w.write_all(&[0xf9, 0x7c, 0x00])?;
@@ -149,9 +177,13 @@ impl Encode<DagCbor> for f32 {
 impl Encode<DagCbor> for f64 {
     #[allow(clippy::float_cmp)]
     fn encode<W: Write>(&self, c: DagCbor, w: &mut W) -> Result<()> {
-        if !self.is_finite() || f64::from(*self as f32) == *self {
+        if c.version() == EncodingVersion::Legacy
+            && (!self.is_finite() || f64::from(*self as f32) == *self)
+        {
             let value = *self as f32;
             value.encode(c, w)?;
+        } else if !self.is_finite() {
+            return Err(NonFiniteFloat(*self).into());
         } else {
             let mut buf = [0xfb, 0, 0, 0, 0, 0, 0, 0, 0];
             BigEndian::write_f64(&mut buf[1..], *self);
@@ -192,12 +224,12 @@ impl Encode<DagCbor> for String {
 impl Encode<DagCbor> for i128 {
     fn encode<W: Write>(&self, _: DagCbor, w: &mut W) -> Result<()> {
         if *self < 0 {
-            if -(*self + 1) > u64::max_value() as i128 {
+            if -(*self + 1) > u64::MAX as i128 {
                 return Err(NumberOutOfRange::new::<i128>().into());
             }
             write_u64(w, 1, -(*self + 1) as u64)?;
         } else {
-            if *self > u64::max_value() as i128 {
+            if *self > u64::MAX as i128 {
                 return Err(NumberOutOfRange::new::<i128>().into());
             }
             write_u64(w, 0, *self as u64)?;
@@ -209,13 +241,29 @@ impl Encode<DagCbor> for i128 {
 impl Encode<DagCbor> for Cid {
     fn encode<W: Write>(&self, _: DagCbor, w: &mut W) -> Result<()> {
         write_tag(w, 42)?;
+        let mut version_buf = varint::u64_buffer();
+        let mut codec_buf = varint::u64_buffer();
+        let (version, codec): (&[u8], &[u8]) = match self.version() {
+            Version::V0 => (&[], &[]),
+            Version::V1 => (
+                varint::u64(self.version().into(), &mut version_buf),
+                varint::u64(self.codec(), &mut codec_buf),
+            ),
+        };
+        let mut code_buf = varint::u64_buffer();
+        let code = varint::u64(self.hash().code(), &mut code_buf);
+        let mut size_buf = varint::u8_buffer();
+        let size = varint::u8(self.hash().size(), &mut size_buf);
+        let digest = self.hash().digest();
         // insert zero byte per https://github.com/ipld/specs/blob/master/block-layer/codecs/dag-cbor.md#links
-        // TODO: don't allocate
-        let buf = self.to_bytes();
-        let len = buf.len();
-        write_u64(w, 2, len as u64 + 1)?;
+        let len = 1 + version.len() + codec.len() + code.len() + size.len() + digest.len();
+        write_u64(w, 2, len as u64)?;
         w.write_all(&[0])?;
-        w.write_all(&buf[..len])?;
+        w.write_all(version)?;
+        w.write_all(codec)?;
+        w.write_all(code)?;
+        w.write_all(size)?;
+        w.write_all(digest)?;
         Ok(())
     }
 }
@@ -241,11 +289,26 @@ impl<T: Encode<DagCbor>> Encode<DagCbor> for Vec<T> {
     }
 }
 
-impl<K: Encode<DagCbor>, T: Encode<DagCbor> + 'static> Encode<DagCbor> for BTreeMap<K, T> {
+impl<K: Encode<DagCbor>, T: Encode<DagCbor>> Encode<DagCbor> for BTreeMap<K, T> {
     fn encode<W: Write>(&self, c: DagCbor, w: &mut W) -> Result<()> {
         write_u64(w, 5, self.len() as u64)?;
+        if c.version() == EncodingVersion::Legacy {
+            for (k, v) in self {
+                k.encode(c, w)?;
+                v.encode(c, w)?;
+            }
+            return Ok(());
+        }
+        // sort by the encoded keys, which for strings is length first
+        let mut entries = Vec::with_capacity(self.len());
         for (k, v) in self {
-            k.encode(c, w)?;
+            let mut key = Vec::new();
+            k.encode(c, &mut key)?;
+            entries.push((key, v));
+        }
+        entries.sort_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+        for (k, v) in entries {
+            w.write_all(&k)?;
             v.encode(c, w)?;
         }
         Ok(())
@@ -275,7 +338,65 @@ impl Encode<DagCbor> for Ipld {
     }
 }
 
-impl<T: Encode<DagCbor>> Encode<DagCbor> for Arc<T> {
+impl<'a> Encode<DagCbor> for IpldRef<'a> {
+    fn encode<W: Write>(&self, c: DagCbor, w: &mut W) -> Result<()> {
+        match self {
+            Self::Null => write_null(w),
+            Self::Bool(b) => b.encode(c, w),
+            Self::Integer(i) => i.encode(c, w),
+            Self::Float(f) => f.encode(c, w),
+            Self::Bytes(b) => b.encode(c, w),
+            Self::String(s) => s.encode(c, w),
+            Self::List(l) => l.encode(c, w),
+            Self::StringMap(m) => m.encode(c, w),
+            #[cfg(feature = "unleashed")]
+            Self::IntegerMap(m) => m.encode(c, w),
+            Self::Link(cid) => cid.encode(c, w),
+            #[cfg(feature = "unleashed")]
+            Self::Tag(tag, ipld) => {
+                write_tag(w, *tag)?;
+                ipld.encode(c, w)
+            }
+        }
+    }
+}
+
+impl Encode<DagCbor> for IpldArc {
+    fn encode<W: Write>(&self, c: DagCbor, w: &mut W) -> Result<()> {
+        match self {
+            Self::Null => write_null(w),
+            Self::Bool(b) => b.encode(c, w),
+            Self::Integer(i) => i.encode(c, w),
+            Self::Float(f) => f.encode(c, w),
+            Self::Bytes(b) => b.encode(c, w),
+            Self::String(s) => s.encode(c, w),
+            Self::List(l) => {
+                write_u64(w, 4, l.len() as u64)?;
+                for ipld in l.iter() {
+                    ipld.encode(c, w)?;
+                }
+                Ok(())
+            }
+            Self::StringMap(m) => m.encode(c, w),
+            #[cfg(feature = "unleashed")]
+            Self::IntegerMap(m) => m.encode(c, w),
+            Self::Link(cid) => cid.encode(c, w),
+            #[cfg(feature = "unleashed")]
+            Self::Tag(tag, ipld) => {
+                write_tag(w, *tag)?;
+                ipld.encode(c, w)
+            }
+        }
+    }
+}
+
+impl<'a, T: Encode<DagCbor> + ToOwned + ?Sized> Encode<DagCbor> for Cow<'a, T> {
+    fn encode<W: Write>(&self, c: DagCbor, w: &mut W) -> Result<()> {
+        self.deref().encode(c, w)
+    }
+}
+
+impl<T: Encode<DagCbor> + ?Sized> Encode<DagCbor> for Arc<T> {
     fn encode<W: Write>(&self, c: DagCbor, w: &mut W) -> Result<()> {
         self.deref().encode(c, w)
     }
@@ -327,3 +448,53 @@ impl<A: Encode<DagCbor>, B: Encode<DagCbor>, C: Encode<DagCbor>, D: Encode<DagCb
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DagCborCodec;
+    use libipld_core::codec::Codec;
+    use libipld_macro::ipld;
+
+    #[test]
+    fn conformant_floats() {
+        let legacy = DagCborCodec;
+        let conformant = DagCborCodec.with_version(EncodingVersion::Conformant);
+        assert_eq!(legacy.encode(&1.5f64).unwrap(), [0xfa, 0x3f, 0xc0, 0, 0]);
+        assert_eq!(
+            conformant.encode(&1.5f64).unwrap(),
+            [0xfb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0]
+        );
+        let bytes = conformant.encode(&1.5f32).unwrap();
+        assert_eq!(bytes, [0xfb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(legacy.decode::<f32>(&bytes).unwrap(), 1.5);
+        let bytes = conformant.encode(&0.1f64).unwrap();
+        assert!(legacy.decode::<f32>(&bytes).is_err());
+        assert_eq!(legacy.encode(&f64::NAN).unwrap(), [0xf9, 0x7e, 0x00]);
+        assert!(conformant.encode(&f64::NAN).is_err());
+        assert!(conformant.encode(&f64::INFINITY).is_err());
+        assert!(conformant.encode(&f32::NEG_INFINITY).is_err());
+    }
+
+    #[test]
+    fn conformant_map_keys() {
+        let ipld = ipld!({ "b": 1, "aa": 2 });
+        assert_eq!(
+            DagCborCodec.encode(&ipld).unwrap(),
+            [0xa2, 0x62, 0x61, 0x61, 0x02, 0x61, 0x62, 0x01]
+        );
+        let bytes = DagCborCodec::strict().encode(&ipld).unwrap();
+        assert_eq!(bytes, [0xa2, 0x61, 0x62, 0x01, 0x62, 0x61, 0x61, 0x02]);
+        let ipld2: Ipld = DagCborCodec::strict().decode(&bytes).unwrap();
+        assert_eq!(ipld, ipld2);
+    }
+
+    #[test]
+    fn ipld_arc() {
+        let ipld = ipld!({ "b": [1, "two", vec![3u8]], "aa": { "c": null } });
+        let arc = IpldArc::from(&ipld);
+        for c in [DagCborCodec, DagCborCodec::strict()] {
+            assert_eq!(c.encode(&arc).unwrap(), c.encode(&ipld).unwrap());
+        }
+    }
+}
@@ -1,20 +1,231 @@
 //! CBOR codec.
 #![deny(missing_docs)]
 #![deny(warnings)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 use core::convert::TryFrom;
-use libipld_core::codec::{Codec, Decode, Encode};
+use libipld_core::codec::{Codec, Decode, DecodeRef, Encode};
 pub use libipld_core::error::{Result, UnsupportedCodec};
+use libipld_core::io::{Cursor, Read, Seek};
+#[cfg(feature = "std")]
+use libipld_core::lookahead::LookaheadReader;
+#[cfg(feature = "std")]
+use libipld_core::raw_value::RawValue;
 
 pub mod decode;
 pub mod encode;
 pub mod error;
+#[cfg(feature = "serde-codec")]
+pub mod serde;
+
+/// Version of the DAG-CBOR encoding produced by the encoder.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EncodingVersion {
+    /// The encoding of libipld 0.12 and earlier.
+    ///
+    /// Floats are narrowed to 32-bit when lossless, `NaN` and infinities are encoded as 16-bit
+    /// floats and map keys are written in lexicographic order. Positive values of the signed
+    /// integer types `i8` to `i64` are encoded as negative integers holding the bitwise
+    /// complement of the value in the width of the type, e.g. `5i64` as `-2^64 + 5`. `Ipld`
+    /// integers are encoded correctly. Use it to reproduce existing CIDs.
+    ///
+    /// The default decoder accepts this integer encoding when decoding into the same type, the
+    /// strict decoder rejects it.
+    #[default]
+    Legacy,
+    /// The encoding required by the current DAG-CBOR spec.
+    ///
+    /// Floats are always encoded as 64-bit floats, `NaN` and infinities are rejected, map
+    /// keys are sorted length first and positive signed integers are encoded as unsigned
+    /// integers.
+    Conformant,
+}
+
+/// Limits applied when decoding untrusted input.
+///
+/// Lengths are checked before anything is allocated for them. The allocation limit is an
+/// estimate: strings and bytes count their length and every item of a list or map counts
+/// `size_of::<Ipld>()` bytes. It is checked once for the outermost value by scanning it before
+/// decoding, see [`DagCborCodec::check_allocation`]. All limits are checked by every
+/// `Decode<DagCborCodec>` impl. By default only the depth is limited, to
+/// [`DecodeLimits::DEFAULT_MAX_DEPTH`], so that malicious input can't overflow the stack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DecodeLimits {
+    /// Maximum number of bytes allocated when decoding a value.
+    pub max_allocation: usize,
+    /// Maximum nesting depth of lists, maps and tags.
+    pub max_depth: usize,
+    /// Maximum number of items of a list or entries of a map.
+    pub max_collection_len: usize,
+    /// Maximum length in bytes of a string or byte string.
+    pub max_string_len: usize,
+}
+
+impl DecodeLimits {
+    /// Default maximum nesting depth, the same as the limit of the serde deserializer and of
+    /// `serde_json`.
+    ///
+    /// It is high enough for the data stored by earlier versions, which didn't limit the depth.
+    /// Decoding data nested this deep needs about 3 MiB of stack in debug builds, more than the
+    /// 2 MiB of a spawned thread. Lower the limit or use a larger stack there.
+    pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+    /// The default limits.
+    pub const fn new() -> Self {
+        Self {
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+            ..Self::unlimited()
+        }
+    }
+
+    /// Limits that never trigger.
+    ///
+    /// Only use them for trusted input, deeply nested input overflows the stack.
+    pub const fn unlimited() -> Self {
+        Self {
+            max_allocation: usize::MAX,
+            max_depth: usize::MAX,
+            max_collection_len: usize::MAX,
+            max_string_len: usize::MAX,
+        }
+    }
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 /// CBOR codec.
+///
+/// By default the decoder is lenient and accepts some encodings the DAG-CBOR spec forbids, like
+/// indefinite length items or 32-bit floats. Use [`DagCborCodec::strict`] to reject them. The
+/// encoder uses [`EncodingVersion::Legacy`] by default. Use [`DagCborCodec::with_limits`] when
+/// decoding untrusted input.
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
-pub struct DagCborCodec;
+pub struct DagCborCodec {
+    strict: bool,
+    version: EncodingVersion,
+    limits: DecodeLimits,
+    depth: usize,
+    allocation_checked: bool,
+}
+
+/// The default `DagCborCodec`.
+#[allow(non_upper_case_globals)]
+pub const DagCborCodec: DagCborCodec = DagCborCodec {
+    strict: false,
+    version: EncodingVersion::Legacy,
+    limits: DecodeLimits::new(),
+    depth: 0,
+    allocation_checked: false,
+};
+
+impl DagCborCodec {
+    /// Creates a codec that only accepts and produces canonical DAG-CBOR.
+    ///
+    /// In strict mode indefinite length items, integers and lengths that aren't minimally
+    /// encoded, floats that aren't 64-bit, `NaN` and infinities, `undefined`, tags other than
+    /// 42 and unsorted or duplicate map keys are rejected. The encoder uses
+    /// [`EncodingVersion::Conformant`].
+    pub const fn strict() -> Self {
+        Self {
+            strict: true,
+            version: EncodingVersion::Conformant,
+            limits: DecodeLimits::new(),
+            depth: 0,
+            allocation_checked: false,
+        }
+    }
+
+    /// Returns `true` if the codec is strict.
+    pub const fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Returns a copy of the codec that encodes with `version`.
+    pub const fn with_version(self, version: EncodingVersion) -> Self {
+        Self { version, ..self }
+    }
+
+    /// Returns the encoding version.
+    pub const fn version(&self) -> EncodingVersion {
+        self.version
+    }
+
+    /// Returns a copy of the codec that decodes with `limits`.
+    pub const fn with_limits(self, limits: DecodeLimits) -> Self {
+        Self { limits, ..self }
+    }
+
+    /// Returns the decode limits.
+    pub const fn limits(&self) -> DecodeLimits {
+        self.limits
+    }
+
+    /// Returns a codec for decoding the items of a list, map or tag of type `T`.
+    ///
+    /// Fails with [`error::LimitExceeded`] if this exceeds the maximum depth.
+    pub fn nested<T>(self) -> Result<Self> {
+        if self.depth >= self.limits.max_depth {
+            return Err(error::LimitExceeded::new::<T>(error::Limit::Depth).into());
+        }
+        Ok(Self {
+            depth: self.depth + 1,
+            ..self
+        })
+    }
 
-impl Codec for DagCborCodec {}
+    /// Checks that decoding the data item at the current position of `r` stays within the
+    /// allocation limit, unless an enclosing data item was already checked.
+    ///
+    /// The data item is scanned with [`decode::check_limits`] and `r` is rewound to its start.
+    /// Returns a codec that skips the check for the items of the data item. Every
+    /// `Decode<DagCborCodec>` impl that allocates calls this first.
+    pub fn check_allocation<R: Read + Seek>(self, r: &mut R) -> Result<Self> {
+        if self.allocation_checked || self.limits.max_allocation == usize::MAX {
+            return Ok(self);
+        }
+        decode::check_limits(self, r)?;
+        Ok(Self {
+            allocation_checked: true,
+            ..self
+        })
+    }
+}
+
+impl Codec for DagCborCodec {
+    fn decode<T: Decode<Self>>(&self, bytes: &[u8]) -> Result<T> {
+        let mut r = Cursor::new(bytes);
+        T::decode(self.check_allocation(&mut r)?, &mut r)
+    }
+
+    fn decode_prefix<T: Decode<Self>>(&self, bytes: &[u8]) -> Result<(T, usize)> {
+        let mut r = Cursor::new(bytes);
+        let value = T::decode(self.check_allocation(&mut r)?, &mut r)?;
+        Ok((value, r.position() as usize))
+    }
+
+    fn decode_ref<'a, T: DecodeRef<'a, Self>>(&self, bytes: &'a [u8]) -> Result<T> {
+        let mut r = Cursor::new(bytes);
+        T::decode_ref(self.check_allocation(&mut r)?, &mut r)
+    }
+
+    #[cfg(feature = "std")]
+    fn decode_from_reader<T: Decode<Self>, R: Read>(&self, r: R) -> Result<T> {
+        let mut r = LookaheadReader::new(r);
+        if self.allocation_checked || self.limits.max_allocation == usize::MAX {
+            return T::decode(*self, &mut r);
+        }
+        // checking the allocations scans the value first, so it is read into memory to be
+        // decoded after the check
+        let raw = RawValue::<Self>::decode(*self, &mut r)?;
+        self.decode(raw.as_ref())
+    }
+}
 
 impl From<DagCborCodec> for u64 {
     fn from(_: DagCborCodec) -> Self {
@@ -26,7 +237,7 @@ impl TryFrom<u64> for DagCborCodec {
     type Error = UnsupportedCodec;
 
     fn try_from(_: u64) -> core::result::Result<Self, Self::Error> {
-        Ok(Self)
+        Ok(Self::default())
     }
 }
 
@@ -59,6 +270,35 @@ mod tests {
         assert_eq!(ipld, ipld2);
     }
 
+    #[test]
+    fn test_signed_integers() {
+        let conformant = DagCborCodec.with_version(EncodingVersion::Conformant);
+        for i in [0i64, 1, 24, -1, -25, i64::MAX, i64::MIN] {
+            let bytes = conformant.encode(&i).unwrap();
+            assert_eq!(DagCborCodec.encode(&(i as i128)).unwrap(), bytes);
+            assert_eq!(DagCborCodec.decode::<i64>(&bytes).unwrap(), i);
+            assert_eq!(DagCborCodec::strict().decode::<i64>(&bytes).unwrap(), i);
+            let legacy = DagCborCodec.encode(&i).unwrap();
+            assert_eq!(DagCborCodec.decode::<i64>(&legacy).unwrap(), i);
+        }
+        // the legacy encoding of positive values, which the strict decoder rejects
+        let bytes = DagCborCodec.encode(&5i64).unwrap();
+        assert_eq!(
+            bytes,
+            [0x3b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfa]
+        );
+        assert!(DagCborCodec::strict().decode::<i64>(&bytes).is_err());
+        for i in [0i8, 5, i8::MAX, -1, i8::MIN] {
+            let bytes = DagCborCodec.encode(&i).unwrap();
+            assert_eq!(DagCborCodec.decode::<i8>(&bytes).unwrap(), i);
+            let bytes = DagCborCodec.encode(&(i as i32)).unwrap();
+            assert_eq!(DagCborCodec.decode::<i32>(&bytes).unwrap(), i as i32);
+        }
+        let bytes = DagCborCodec.encode(&200u8).unwrap();
+        assert_eq!(DagCborCodec.decode::<i16>(&bytes).unwrap(), 200);
+        assert!(DagCborCodec.decode::<i8>(&bytes).is_err());
+    }
+
     #[test]
     fn test_references() {
         let cid = Cid::new_v1(0, Code::Blake3_256.digest(&b"0"[..]));
@@ -72,4 +312,100 @@ mod tests {
             .unwrap();
         assert!(set.contains(&cid));
     }
+
+    #[test]
+    fn test_async_decoder() {
+        use core::pin::Pin;
+        use core::task::{Context, Poll};
+        use futures::executor::block_on;
+        use futures::io::{AsyncRead, AsyncReadExt};
+        use futures::{Future, TryStreamExt};
+        use libipld_core::codec::{encode_async, AsyncDecoder};
+        use libipld_core::error::Error;
+
+        /// Reader returning at most `max` bytes at a time and being pending every other time.
+        struct Trickle<'a> {
+            bytes: &'a [u8],
+            max: usize,
+            pending: bool,
+        }
+
+        impl AsyncRead for Trickle<'_> {
+            fn poll_read(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context,
+                buf: &mut [u8],
+            ) -> Poll<std::io::Result<usize>> {
+                self.pending = !self.pending;
+                if self.pending {
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                let n = self.bytes.len().min(buf.len()).min(self.max);
+                buf[..n].copy_from_slice(&self.bytes[..n]);
+                self.bytes = &self.bytes[n..];
+                Poll::Ready(Ok(n))
+            }
+        }
+
+        let cid = Cid::new_v1(0, Code::Blake3_256.digest(&b"cid"[..]));
+        let ipld = ipld!({
+            "list": [1, "two", { "three": 3.0 }],
+            "bytes": vec![0u8; 100],
+        });
+        let link = ipld!([cid, null]);
+        let mut bytes = Vec::new();
+        block_on(async {
+            encode_async(DagCborCodec, &ipld, &mut bytes).await?;
+            encode_async(DagCborCodec, &link, &mut bytes).await
+        })
+        .unwrap();
+
+        let reader = Trickle {
+            bytes: &bytes,
+            max: 3,
+            pending: false,
+        };
+        let mut decoder = AsyncDecoder::new(DagCborCodec, reader);
+        let ipld2: Ipld = block_on(decoder.decode()).unwrap();
+        assert_eq!(ipld2, ipld);
+        let mut set = HashSet::new();
+        block_on(decoder.references::<Ipld, _>(&mut set)).unwrap();
+        assert_eq!(set.len(), 1);
+        assert!(set.contains(&cid));
+        assert!(decoder.buffer().is_empty());
+        let err = block_on(decoder.decode::<Ipld>()).unwrap_err();
+        assert!(matches!(err, Error::Io(_)));
+
+        let bytes = DagCborCodec.encode(&cid).unwrap();
+        let reader = Trickle {
+            bytes: &bytes,
+            max: 1,
+            pending: false,
+        };
+        let mut decoder = AsyncDecoder::new(DagCborCodec, reader);
+        let cid2: Cid = block_on(decoder.decode()).unwrap();
+        assert_eq!(cid2, cid);
+
+        // a value is decoded once it is complete, even if the reader stays pending afterwards
+        let bytes = DagCborCodec.encode(&ipld).unwrap();
+        let reader = Trickle {
+            bytes: &bytes,
+            max: 7,
+            pending: false,
+        }
+        .chain(futures::stream::pending::<std::io::Result<&[u8]>>().into_async_read());
+        let mut decoder = AsyncDecoder::new(DagCborCodec, reader);
+        let mut decode = Box::pin(decoder.decode::<Ipld>());
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let ipld2 = (0..10_000)
+            .find_map(|_| match decode.as_mut().poll(&mut cx) {
+                Poll::Ready(res) => Some(res),
+                Poll::Pending => None,
+            })
+            .expect("decoding waited for more bytes")
+            .unwrap();
+        assert_eq!(ipld2, ipld);
+    }
 }
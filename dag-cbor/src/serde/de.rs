@@ -0,0 +1,396 @@
+//! DAG-CBOR deserializer.
+use crate::DecodeLimits;
+use byteorder::{BigEndian, ByteOrder};
+use core::convert::TryFrom;
+use libipld_core::error::SerdeError;
+use libipld_core::serde::CID_SERDE_PRIVATE_IDENTIFIER;
+use serde::de::{self, Error as _, IntoDeserializer};
+use serde::forward_to_deserialize_any;
+use std::io::Read;
+
+/// Serde deserializer reading DAG-CBOR.
+pub struct Deserializer<R> {
+    reader: R,
+    peeked: Option<u8>,
+    remaining_depth: usize,
+}
+
+impl<R: Read> Deserializer<R> {
+    /// Creates a new deserializer reading from `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            peeked: None,
+            // the same limit as the default codec, so that neither overflows the stack
+            remaining_depth: DecodeLimits::DEFAULT_MAX_DEPTH,
+        }
+    }
+
+    /// Checks that the input was fully consumed.
+    pub fn end(&mut self) -> Result<(), SerdeError> {
+        if self.peeked.is_some() {
+            return Err(SerdeError::custom("trailing data"));
+        }
+        let mut buf = [0; 1];
+        match self.reader.read(&mut buf).map_err(SerdeError::custom)? {
+            0 => Ok(()),
+            _ => Err(SerdeError::custom("trailing data")),
+        }
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), SerdeError> {
+        self.reader.read_exact(buf).map_err(SerdeError::custom)
+    }
+
+    fn next(&mut self) -> Result<u8, SerdeError> {
+        if let Some(byte) = self.peeked.take() {
+            return Ok(byte);
+        }
+        let mut buf = [0; 1];
+        self.read_exact(&mut buf)?;
+        Ok(buf[0])
+    }
+
+    fn peek(&mut self) -> Result<u8, SerdeError> {
+        let byte = self.next()?;
+        self.peeked = Some(byte);
+        Ok(byte)
+    }
+
+    /// Reads the argument following the initial byte of a data item.
+    fn read_arg(&mut self, byte: u8) -> Result<u64, SerdeError> {
+        Ok(match byte & 0x1f {
+            info @ 0x00..=0x17 => info.into(),
+            0x18 => self.next()?.into(),
+            0x19 => {
+                let mut buf = [0; 2];
+                self.read_exact(&mut buf)?;
+                BigEndian::read_u16(&buf).into()
+            }
+            0x1a => {
+                let mut buf = [0; 4];
+                self.read_exact(&mut buf)?;
+                BigEndian::read_u32(&buf).into()
+            }
+            0x1b => {
+                let mut buf = [0; 8];
+                self.read_exact(&mut buf)?;
+                BigEndian::read_u64(&buf)
+            }
+            _ => {
+                return Err(SerdeError::custom(format_args!(
+                    "unexpected cbor code `0x{:x}`",
+                    byte
+                )))
+            }
+        })
+    }
+
+    /// Runs `f` one nesting level deeper.
+    fn recurse<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, SerdeError>,
+    ) -> Result<T, SerdeError> {
+        self.remaining_depth = self
+            .remaining_depth
+            .checked_sub(1)
+            .ok_or_else(|| SerdeError::custom("recursion limit exceeded"))?;
+        let result = f(self);
+        self.remaining_depth += 1;
+        result
+    }
+
+    fn read_len(&mut self, byte: u8) -> Result<usize, SerdeError> {
+        let len = self.read_arg(byte)?;
+        usize::try_from(len).map_err(SerdeError::custom)
+    }
+
+    /// Reads `len` bytes without trusting `len` for the allocation.
+    fn read_bytes(&mut self, len: usize) -> Result<Vec<u8>, SerdeError> {
+        let mut buf = Vec::new();
+        (&mut self.reader)
+            .take(len as u64)
+            .read_to_end(&mut buf)
+            .map_err(SerdeError::custom)?;
+        if buf.len() != len {
+            return Err(SerdeError::custom("unexpected end of file"));
+        }
+        Ok(buf)
+    }
+
+    fn read_str(&mut self, len: usize) -> Result<String, SerdeError> {
+        String::from_utf8(self.read_bytes(len)?).map_err(SerdeError::custom)
+    }
+
+    /// Reads the bytes of a cid after the tag 42 was consumed.
+    fn read_link(&mut self) -> Result<Vec<u8>, SerdeError> {
+        let byte = self.next()?;
+        if byte >> 5 != 2 {
+            return Err(SerdeError::custom("expected cid bytes"));
+        }
+        let len = self.read_len(byte)?;
+        let mut bytes = self.read_bytes(len)?;
+        // remove the zero byte per https://github.com/ipld/specs/blob/master/block-layer/codecs/dag-cbor.md#links
+        match bytes.first() {
+            Some(0) => {
+                bytes.remove(0);
+                Ok(bytes)
+            }
+            Some(prefix) => Err(SerdeError::custom(format_args!(
+                "invalid cid prefix: {}",
+                prefix
+            ))),
+            None => Err(SerdeError::custom("empty cid")),
+        }
+    }
+}
+
+impl<'de, R: Read> de::Deserializer<'de> for &mut Deserializer<R> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        let byte = self.next()?;
+        match byte {
+            // Major type 0: an unsigned integer
+            0x00..=0x1b => visitor.visit_u64(self.read_arg(byte)?),
+            // Major type 1: a negative integer
+            0x20..=0x3b => {
+                let value = -1 - i128::from(self.read_arg(byte)?);
+                match i64::try_from(value) {
+                    Ok(value) => visitor.visit_i64(value),
+                    Err(_) => visitor.visit_i128(value),
+                }
+            }
+            // Major type 2: a byte string
+            0x40..=0x5b => {
+                let len = self.read_len(byte)?;
+                visitor.visit_byte_buf(self.read_bytes(len)?)
+            }
+            // Major type 3: a text string
+            0x60..=0x7b => {
+                let len = self.read_len(byte)?;
+                visitor.visit_string(self.read_str(len)?)
+            }
+            // Major type 4: an array of data items
+            0x80..=0x9b => {
+                let len = self.read_len(byte)?;
+                self.recurse(|de| visitor.visit_seq(Access { de, len }))
+            }
+            // Major type 5: a map of pairs of data items
+            0xa0..=0xbb => {
+                let len = self.read_len(byte)?;
+                self.recurse(|de| visitor.visit_map(Access { de, len }))
+            }
+            // Major type 6: only tag 42 for links is supported
+            0xc0..=0xdb => match self.read_arg(byte)? {
+                42 => {
+                    let bytes = self.read_link()?;
+                    visitor.visit_newtype_struct(CidDeserializer(bytes))
+                }
+                tag => Err(SerdeError::custom(format_args!(
+                    "unknown cbor tag `{}`",
+                    tag
+                ))),
+            },
+            // Major type 7: floating-point numbers and other simple data types
+            0xf4 => visitor.visit_bool(false),
+            0xf5 => visitor.visit_bool(true),
+            0xf6 => visitor.visit_unit(),
+            0xfa => {
+                let mut buf = [0; 4];
+                self.read_exact(&mut buf)?;
+                visitor.visit_f64(BigEndian::read_f32(&buf).into())
+            }
+            0xfb => {
+                let mut buf = [0; 8];
+                self.read_exact(&mut buf)?;
+                visitor.visit_f64(BigEndian::read_f64(&buf))
+            }
+            _ => Err(SerdeError::custom(format_args!(
+                "unexpected cbor code `0x{:x}`",
+                byte
+            ))),
+        }
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        if self.peek()? == 0xf6 {
+            self.next()?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        if name == CID_SERDE_PRIVATE_IDENTIFIER {
+            // tag 42 is encoded as 0xd8 0x2a
+            if self.peek()? != 0xd8 {
+                return Err(SerdeError::custom("expected a link"));
+            }
+            self.deserialize_any(visitor)
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        let byte = self.peek()?;
+        match byte >> 5 {
+            3 => {
+                self.next()?;
+                let len = self.read_len(byte)?;
+                let variant = self.read_str(len)?;
+                visitor.visit_enum(variant.into_deserializer())
+            }
+            5 => {
+                self.next()?;
+                if self.read_len(byte)? != 1 {
+                    return Err(SerdeError::custom("expected a map with a single key"));
+                }
+                self.recurse(|de| visitor.visit_enum(de))
+            }
+            _ => Err(SerdeError::custom("expected a string or a map")),
+        }
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct Access<'a, R> {
+    de: &'a mut Deserializer<R>,
+    len: usize,
+}
+
+impl<'de, 'a, R: Read> de::SeqAccess<'de> for Access<'a, R> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, SerdeError> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        // don't trust the length for preallocation
+        Some(self.len.min(4096))
+    }
+}
+
+impl<'de, 'a, R: Read> de::MapAccess<'de> for Access<'a, R> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SerdeError> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+        if self.de.peek()? >> 5 != 3 {
+            return Err(SerdeError::custom("map keys must be strings"));
+        }
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, SerdeError> {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len.min(4096))
+    }
+}
+
+impl<'de, R: Read> de::EnumAccess<'de> for &mut Deserializer<R> {
+    type Error = SerdeError;
+    type Variant = Self;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), SerdeError> {
+        if self.peek()? >> 5 != 3 {
+            return Err(SerdeError::custom("map keys must be strings"));
+        }
+        let variant = seed.deserialize(&mut *self)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, R: Read> de::VariantAccess<'de> for &mut Deserializer<R> {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        match self.next()? {
+            0xf6 => Ok(()),
+            _ => Err(SerdeError::custom("expected null for unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, SerdeError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: de::Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+/// Deserializer handing out the binary representation of a cid.
+struct CidDeserializer(Vec<u8>);
+
+impl<'de> de::Deserializer<'de> for CidDeserializer {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_byte_buf(self.0)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
@@ -0,0 +1,182 @@
+//! Serde (de)serialization of DAG-CBOR.
+//!
+//! Any type implementing `Serialize` can be encoded to DAG-CBOR with [`to_vec`] or
+//! [`to_writer`] and any type implementing `Deserialize` can be decoded with [`from_slice`] or
+//! [`from_reader`]. The output follows the DAG-CBOR spec: floats are always encoded as 64-bit
+//! floats, `NaN` and infinities are rejected, map keys must be strings and are sorted length
+//! first, and no indefinite length items are emitted.
+//!
+//! Links are encoded with tag 42 when they are serialized through
+//! [`libipld_core::serde::cid`] or the [`Link`](libipld_core::link::Link) type.
+use libipld_core::error::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::{Read, Write};
+
+mod de;
+mod ser;
+
+pub use de::Deserializer;
+pub use ser::Serializer;
+
+/// Serializes a value to a writer.
+pub fn to_writer<W: Write, T: Serialize + ?Sized>(writer: W, value: &T) -> Result<()> {
+    value.serialize(&mut Serializer::new(writer))?;
+    Ok(())
+}
+
+/// Serializes a value to a byte vector.
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    to_writer(&mut bytes, value)?;
+    Ok(bytes)
+}
+
+/// Deserializes a value from a reader.
+pub fn from_reader<R: Read, T: DeserializeOwned>(reader: R) -> Result<T> {
+    let mut de = Deserializer::new(reader);
+    let value = T::deserialize(&mut de)?;
+    de.end()?;
+    Ok(value)
+}
+
+/// Deserializes a value from a byte slice.
+pub fn from_slice<T: DeserializeOwned>(slice: &[u8]) -> Result<T> {
+    from_reader(slice)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DagCborCodec;
+    use libipld_core::cid::Cid;
+    use libipld_core::codec::Codec;
+    use libipld_core::ipld::Ipld;
+    use libipld_core::link::Link;
+    use libipld_core::multihash::{Code, MultihashDigest};
+    use libipld_macro::ipld;
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct Person {
+        name: String,
+        age: u8,
+        score: f64,
+        avatar: Option<Vec<u8>>,
+        #[serde(with = "libipld_core::serde::cid")]
+        profile: Cid,
+        friends: Vec<Link<Person>>,
+        shape: Shape,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Empty,
+        Circle(u32),
+        Rect { w: u32, h: u32 },
+    }
+
+    fn cid(data: &[u8]) -> Cid {
+        Cid::new_v1(0x71, Code::Blake3_256.digest(data))
+    }
+
+    #[test]
+    fn test_struct_roundtrip() {
+        let person = Person {
+            name: "Hello World!".into(),
+            age: 52,
+            score: 1.5,
+            avatar: None,
+            profile: cid(b"profile"),
+            friends: vec![Link::new(cid(b"friend"))],
+            shape: Shape::Rect { w: 1, h: 2 },
+        };
+        let bytes = to_vec(&person).unwrap();
+        let person2: Person = from_slice(&bytes).unwrap();
+        assert_eq!(person, person2);
+
+        let ipld: Ipld = DagCborCodec.decode(&bytes).unwrap();
+        let expected = ipld!({
+            "name": "Hello World!",
+            "age": 52,
+            "score": 1.5,
+            "avatar": null,
+            "profile": cid(b"profile"),
+            "friends": [cid(b"friend")],
+            "shape": { "Rect": { "w": 1, "h": 2 } },
+        });
+        assert_eq!(ipld, expected);
+    }
+
+    #[test]
+    fn test_enum_roundtrip() {
+        for shape in [Shape::Empty, Shape::Circle(2), Shape::Rect { w: 1, h: 2 }] {
+            let bytes = to_vec(&shape).unwrap();
+            assert_eq!(from_slice::<Shape>(&bytes).unwrap(), shape);
+        }
+        assert_eq!(to_vec(&Shape::Empty).unwrap(), b"\x65Empty");
+    }
+
+    #[test]
+    fn test_ipld_interop() {
+        let ipld = ipld!({
+            "number": 1,
+            "negative": -5,
+            "list": [true, null, false],
+            "bytes": vec![0, 1, 2, 3],
+            "map": { "float": 0.5, "string": "hello" },
+            "link": cid(b"link"),
+        });
+        let bytes = to_vec(&ipld).unwrap();
+        let ipld2: Ipld = DagCborCodec.decode(&bytes).unwrap();
+        assert_eq!(ipld, ipld2);
+
+        let bytes = DagCborCodec.encode(&ipld).unwrap();
+        let ipld2: Ipld = from_slice(&bytes).unwrap();
+        assert_eq!(ipld, ipld2);
+    }
+
+    #[test]
+    fn test_canonical_encoding() {
+        let mut map = BTreeMap::new();
+        map.insert("aa", 1);
+        map.insert("b", 2);
+        assert_eq!(to_vec(&map).unwrap(), b"\xa2\x61b\x02\x62aa\x01");
+
+        assert_eq!(to_vec(&1.5f32).unwrap(), b"\xfb\x3f\xf8\0\0\0\0\0\0");
+        assert!(to_vec(&f64::NAN).is_err());
+        assert!(to_vec(&f64::INFINITY).is_err());
+
+        let mut map = BTreeMap::new();
+        map.insert(1u8, true);
+        assert!(to_vec(&map).is_err());
+
+        let cid = cid(b"cid");
+        let bytes = to_vec(&Link::<Ipld>::new(cid)).unwrap();
+        assert_eq!(bytes, DagCborCodec.encode(&cid).unwrap());
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        // trailing data
+        assert!(from_slice::<u8>(b"\x01\x02").is_err());
+        // out of range
+        assert!(from_slice::<u8>(b"\x19\x01\x00").is_err());
+        // indefinite length list
+        assert!(from_slice::<Vec<u8>>(b"\x9f\x01\xff").is_err());
+        // unknown tag
+        assert!(from_slice::<Ipld>(b"\xc1\x01").is_err());
+        // bytes are not a link
+        assert!(from_slice::<Link<Ipld>>(b"\x41\x00").is_err());
+        // length larger than input
+        assert!(from_slice::<Vec<u8>>(b"\x5b\xff\xff\xff\xff\xff\xff\xff\xff").is_err());
+        // nesting too deep
+        let mut nested = vec![0x81; 200_000];
+        nested.push(0xf6);
+        assert!(from_slice::<Ipld>(&nested).is_err());
+        let mut nested = vec![0x81; 100];
+        nested.push(0xf6);
+        assert!(from_slice::<Ipld>(&nested).is_ok());
+    }
+}
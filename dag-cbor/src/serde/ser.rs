@@ -0,0 +1,772 @@
+//! DAG-CBOR serializer.
+use byteorder::{BigEndian, ByteOrder};
+use core::convert::TryFrom;
+use libipld_core::cid::Cid;
+use libipld_core::error::SerdeError;
+use libipld_core::serde::CID_SERDE_PRIVATE_IDENTIFIER;
+use serde::ser::{self, Error as _, Serialize};
+use std::io::Write;
+
+/// Serde serializer writing DAG-CBOR.
+pub struct Serializer<W> {
+    writer: W,
+}
+
+impl<W: Write> Serializer<W> {
+    /// Creates a new serializer writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), SerdeError> {
+        self.writer.write_all(buf).map_err(SerdeError::custom)
+    }
+
+    fn write_header(&mut self, major: u8, value: u64) -> Result<(), SerdeError> {
+        let major = major << 5;
+        if value <= 0x17 {
+            self.write_all(&[major | value as u8])
+        } else if value <= u64::from(u8::MAX) {
+            self.write_all(&[major | 24, value as u8])
+        } else if value <= u64::from(u16::MAX) {
+            let mut buf = [major | 25, 0, 0];
+            BigEndian::write_u16(&mut buf[1..], value as u16);
+            self.write_all(&buf)
+        } else if value <= u64::from(u32::MAX) {
+            let mut buf = [major | 26, 0, 0, 0, 0];
+            BigEndian::write_u32(&mut buf[1..], value as u32);
+            self.write_all(&buf)
+        } else {
+            let mut buf = [major | 27, 0, 0, 0, 0, 0, 0, 0, 0];
+            BigEndian::write_u64(&mut buf[1..], value);
+            self.write_all(&buf)
+        }
+    }
+
+    fn write_link(&mut self, bytes: &[u8]) -> Result<(), SerdeError> {
+        Cid::try_from(bytes).map_err(SerdeError::custom)?;
+        self.write_header(6, 42)?;
+        // insert zero byte per https://github.com/ipld/specs/blob/master/block-layer/codecs/dag-cbor.md#links
+        self.write_header(2, bytes.len() as u64 + 1)?;
+        self.write_all(&[0])?;
+        self.write_all(bytes)
+    }
+
+    fn write_single_key_map(&mut self, key: &str) -> Result<(), SerdeError> {
+        self.write_header(5, 1)?;
+        ser::Serializer::serialize_str(self, key)
+    }
+}
+
+fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, SerdeError> {
+    let mut buf = Vec::new();
+    value.serialize(&mut Serializer::new(&mut buf))?;
+    Ok(buf)
+}
+
+impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    type SerializeSeq = SerializeSeq<'a, W>;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = SerializeMap<'a, W>;
+    type SerializeStruct = SerializeMap<'a, W>;
+    type SerializeStructVariant = SerializeMap<'a, W>;
+
+    fn serialize_bool(self, value: bool) -> Result<(), SerdeError> {
+        self.write_all(if value { &[0xf5] } else { &[0xf4] })
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<(), SerdeError> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<(), SerdeError> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<(), SerdeError> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<(), SerdeError> {
+        if value < 0 {
+            self.write_header(1, -(value + 1) as u64)
+        } else {
+            self.write_header(0, value as u64)
+        }
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<(), SerdeError> {
+        if value < 0 {
+            let value = u64::try_from(-(value + 1)).map_err(SerdeError::custom)?;
+            self.write_header(1, value)
+        } else {
+            let value = u64::try_from(value).map_err(SerdeError::custom)?;
+            self.write_header(0, value)
+        }
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<(), SerdeError> {
+        self.serialize_u64(value.into())
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<(), SerdeError> {
+        self.serialize_u64(value.into())
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<(), SerdeError> {
+        self.serialize_u64(value.into())
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<(), SerdeError> {
+        self.write_header(0, value)
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<(), SerdeError> {
+        let value = u64::try_from(value).map_err(SerdeError::custom)?;
+        self.write_header(0, value)
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<(), SerdeError> {
+        self.serialize_f64(value.into())
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<(), SerdeError> {
+        if !value.is_finite() {
+            return Err(SerdeError::custom(
+                "NaN and infinity are not supported by DAG-CBOR",
+            ));
+        }
+        let mut buf = [0xfb, 0, 0, 0, 0, 0, 0, 0, 0];
+        BigEndian::write_f64(&mut buf[1..], value);
+        self.write_all(&buf)
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), SerdeError> {
+        self.serialize_str(value.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<(), SerdeError> {
+        self.write_header(3, value.len() as u64)?;
+        self.write_all(value.as_bytes())
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), SerdeError> {
+        self.write_header(2, value.len() as u64)?;
+        self.write_all(value)
+    }
+
+    fn serialize_none(self) -> Result<(), SerdeError> {
+        self.write_all(&[0xf6])
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), SerdeError> {
+        self.serialize_none()
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), SerdeError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), SerdeError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        if name == CID_SERDE_PRIVATE_IDENTIFIER {
+            let bytes = value.serialize(CidSerializer)?;
+            self.write_link(&bytes)
+        } else {
+            value.serialize(self)
+        }
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.write_single_key_map(variant)?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, SerdeError> {
+        Ok(match len {
+            Some(len) => {
+                self.write_header(4, len as u64)?;
+                SerializeSeq::Known(self)
+            }
+            None => SerializeSeq::Unknown {
+                ser: self,
+                buf: Vec::new(),
+                len: 0,
+            },
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, SerdeError> {
+        self.write_header(4, len as u64)?;
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, SerdeError> {
+        self.serialize_tuple(len)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerdeError> {
+        self.write_single_key_map(variant)?;
+        self.serialize_tuple(len)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, SerdeError> {
+        Ok(SerializeMap {
+            ser: self,
+            entries: Vec::new(),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, SerdeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, SerdeError> {
+        self.write_single_key_map(variant)?;
+        self.serialize_map(Some(len))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+/// Serializes the elements of a sequence.
+///
+/// DAG-CBOR doesn't allow indefinite length items, so sequences of unknown length are buffered.
+#[doc(hidden)]
+pub enum SerializeSeq<'a, W> {
+    Known(&'a mut Serializer<W>),
+    Unknown {
+        ser: &'a mut Serializer<W>,
+        buf: Vec<u8>,
+        len: u64,
+    },
+}
+
+impl<'a, W: Write> ser::SerializeSeq for SerializeSeq<'a, W> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        match self {
+            Self::Known(ser) => value.serialize(&mut **ser),
+            Self::Unknown { buf, len, .. } => {
+                value.serialize(&mut Serializer::new(buf))?;
+                *len += 1;
+                Ok(())
+            }
+        }
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        if let Self::Unknown { ser, buf, len } = self {
+            ser.write_header(4, len)?;
+            ser.write_all(&buf)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> ser::SerializeTuple for &mut Serializer<W> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        Ok(())
+    }
+}
+
+impl<W: Write> ser::SerializeTupleStruct for &mut Serializer<W> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        Ok(())
+    }
+}
+
+impl<W: Write> ser::SerializeTupleVariant for &mut Serializer<W> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        Ok(())
+    }
+}
+
+/// Serializes the entries of a map.
+///
+/// The entries are buffered so that they can be written in canonical key order.
+#[doc(hidden)]
+pub struct SerializeMap<'a, W> {
+    ser: &'a mut Serializer<W>,
+    entries: Vec<(Vec<u8>, Vec<u8>)>,
+    next_key: Option<Vec<u8>>,
+}
+
+impl<'a, W: Write> ser::SerializeMap for SerializeMap<'a, W> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        self.next_key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let key = self
+            .next_key
+            .take()
+            .ok_or_else(|| SerdeError::custom("serialize_value called before serialize_key"))?;
+        self.entries.push((key, encode(value)?));
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<(), SerdeError> {
+        // Keys are encoded strings, so sorting the encoded bytes sorts them length first.
+        self.entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        if self.entries.windows(2).any(|w| w[0].0 == w[1].0) {
+            return Err(SerdeError::custom("duplicate map key"));
+        }
+        self.ser.write_header(5, self.entries.len() as u64)?;
+        for (key, value) in &self.entries {
+            self.ser.write_all(key)?;
+            self.ser.write_all(value)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> ser::SerializeStruct for SerializeMap<'a, W> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        ser::SerializeMap::serialize_entry(self, key, value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+impl<'a, W: Write> ser::SerializeStructVariant for SerializeMap<'a, W> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        ser::SerializeMap::serialize_entry(self, key, value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// Serializer for map keys, which must be strings.
+struct KeySerializer;
+
+fn key_must_be_a_string() -> SerdeError {
+    SerdeError::custom("map keys must be strings")
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = Vec<u8>;
+    type Error = SerdeError;
+
+    type SerializeSeq = ser::Impossible<Vec<u8>, SerdeError>;
+    type SerializeTuple = ser::Impossible<Vec<u8>, SerdeError>;
+    type SerializeTupleStruct = ser::Impossible<Vec<u8>, SerdeError>;
+    type SerializeTupleVariant = ser::Impossible<Vec<u8>, SerdeError>;
+    type SerializeMap = ser::Impossible<Vec<u8>, SerdeError>;
+    type SerializeStruct = ser::Impossible<Vec<u8>, SerdeError>;
+    type SerializeStructVariant = ser::Impossible<Vec<u8>, SerdeError>;
+
+    fn serialize_str(self, value: &str) -> Result<Vec<u8>, SerdeError> {
+        encode(value)
+    }
+
+    fn serialize_char(self, value: char) -> Result<Vec<u8>, SerdeError> {
+        encode(&value)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Vec<u8>, SerdeError> {
+        encode(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Vec<u8>, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _: bool) -> Result<Vec<u8>, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_i8(self, _: i8) -> Result<Vec<u8>, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_i16(self, _: i16) -> Result<Vec<u8>, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_i32(self, _: i32) -> Result<Vec<u8>, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_i64(self, _: i64) -> Result<Vec<u8>, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_u8(self, _: u8) -> Result<Vec<u8>, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_u16(self, _: u16) -> Result<Vec<u8>, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_u32(self, _: u32) -> Result<Vec<u8>, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_u64(self, _: u64) -> Result<Vec<u8>, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_f32(self, _: f32) -> Result<Vec<u8>, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<Vec<u8>, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_bytes(self, _: &[u8]) -> Result<Vec<u8>, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_none(self) -> Result<Vec<u8>, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _: &T) -> Result<Vec<u8>, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit(self) -> Result<Vec<u8>, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Vec<u8>, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Vec<u8>, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, SerdeError> {
+        Err(key_must_be_a_string())
+    }
+}
+
+/// Serializer extracting the binary representation of a cid.
+struct CidSerializer;
+
+fn expected_cid_bytes() -> SerdeError {
+    SerdeError::custom("expected cid bytes")
+}
+
+impl ser::Serializer for CidSerializer {
+    type Ok = Vec<u8>;
+    type Error = SerdeError;
+
+    type SerializeSeq = ser::Impossible<Vec<u8>, SerdeError>;
+    type SerializeTuple = ser::Impossible<Vec<u8>, SerdeError>;
+    type SerializeTupleStruct = ser::Impossible<Vec<u8>, SerdeError>;
+    type SerializeTupleVariant = ser::Impossible<Vec<u8>, SerdeError>;
+    type SerializeMap = ser::Impossible<Vec<u8>, SerdeError>;
+    type SerializeStruct = ser::Impossible<Vec<u8>, SerdeError>;
+    type SerializeStructVariant = ser::Impossible<Vec<u8>, SerdeError>;
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Vec<u8>, SerdeError> {
+        Ok(value.to_vec())
+    }
+
+    fn serialize_bool(self, _: bool) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_i8(self, _: i8) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_i16(self, _: i16) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_i32(self, _: i32) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_i64(self, _: i64) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_u8(self, _: u8) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_u16(self, _: u16) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_u32(self, _: u32) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_u64(self, _: u64) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_f32(self, _: f32) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_char(self, _: char) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_str(self, _: &str) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_none(self) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _: &T) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_unit(self) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+    ) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Vec<u8>, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, SerdeError> {
+        Err(expected_cid_bytes())
+    }
+}
@@ -39,11 +39,7 @@ impl Arbitrary for ValueArb {
             Value::Array(a) => Box::new(
                 a.clone()
                     .into_iter()
-                    .map(|x| {
-                        let slf = Self(x);
-                        let shrunk = slf.shrink().map(|x| x.0).collect::<Vec<_>>();
-                        shrunk
-                    })
+                    .map(|x| Self(x).shrink().map(|x| x.0).collect::<Vec<_>>())
                     .map(Value::Array)
                     .map(Self),
             ),
@@ -1,85 +1,303 @@
+use crate::DagJsonCodec;
 use core::convert::TryFrom;
 use libipld_core::cid::Cid;
+use libipld_core::error::{Error, JsonError, Result};
 use libipld_core::ipld::Ipld;
-use serde::de::Error as SerdeError;
-use serde::{de, ser, Deserialize, Serialize};
+use libipld_core::ipld_arc::IpldArc;
+use serde::de::DeserializeSeed;
+use serde::{de, ser, Serialize};
+use serde_json::error::Category;
 use serde_json::ser::Serializer;
-use serde_json::Error;
+use std::cell::Cell;
 use std::collections::BTreeMap;
 use std::fmt;
 use std::io::{Read, Write};
 
-const LINK_KEY: &str = "/";
+pub(crate) const LINK_KEY: &str = "/";
+pub(crate) const BYTES_KEY: &str = "bytes";
+/// Error message for a map with the single key `/` that is neither a link nor bytes.
+pub(crate) const RESERVED_KEY: &str =
+    "a map with the single key \"/\" is reserved for links and bytes";
 
-pub fn encode<W: Write>(ipld: &Ipld, writer: &mut W) -> Result<(), Error> {
+/// Largest integer that is exactly representable as a double, `2^53 - 1`.
+pub(crate) const MAX_SAFE_INTEGER: i128 = (1 << 53) - 1;
+
+pub fn encode<W: Write>(c: DagJsonCodec, ipld: &Ipld, writer: &mut W) -> Result<()> {
+    let mut ser = Serializer::new(writer);
+    serialize(c, ipld, &mut ser).map_err(|err| json_error(err, None))?;
+    Ok(())
+}
+
+pub fn encode_arc<W: Write>(c: DagJsonCodec, ipld: &IpldArc, writer: &mut W) -> Result<()> {
     let mut ser = Serializer::new(writer);
-    serialize(&ipld, &mut ser)?;
+    serialize_arc(c, ipld, &mut ser).map_err(|err| json_error(err, None))?;
     Ok(())
 }
 
-pub fn decode<R: Read>(r: &mut R) -> Result<Ipld, Error> {
+pub fn decode<R: Read>(c: DagJsonCodec, r: &mut R) -> Result<Ipld> {
     let mut de = serde_json::Deserializer::from_reader(r);
-    deserialize(&mut de)
+    let invalid = Cell::new(None);
+    IpldSeed(c, &invalid)
+        .deserialize(&mut de)
+        .map_err(|err| json_error(err, invalid.take()))
+}
+
+pub fn references<R: Read, E: Extend<Cid>>(c: DagJsonCodec, r: &mut R, set: &mut E) -> Result<()> {
+    let mut de = serde_json::Deserializer::from_reader(r);
+    let invalid = Cell::new(None);
+    ReferencesSeed {
+        codec: c,
+        invalid: &invalid,
+        set,
+    }
+    .deserialize(&mut de)
+    .map_err(|err| json_error(err, invalid.take()))
 }
 
-fn serialize<S: ser::Serializer>(ipld: &Ipld, ser: S) -> Result<S::Ok, S::Error> {
+/// Invalid DAG-JSON detected while decoding.
+///
+/// It is recorded next to the `serde_json` error, which only carries a message.
+pub(crate) enum Invalid {
+    Cid(String),
+    Bytes(String),
+    ReservedKey(String),
+}
+
+impl Invalid {
+    /// Records `self` and returns an error for `serde_json` to add the position to.
+    fn record<E: de::Error>(self, cell: &Cell<Option<Invalid>>) -> E {
+        let err = match &self {
+            Self::Cid(msg) | Self::Bytes(msg) | Self::ReservedKey(msg) => E::custom(msg),
+        };
+        cell.set(Some(self));
+        err
+    }
+}
+
+/// Converts a `serde_json` error into an ipld error.
+pub(crate) fn json_error(err: serde_json::Error, invalid: Option<Invalid>) -> Error {
+    let (line, column) = (err.line(), err.column());
+    match err.classify() {
+        Category::Io => Error::Io(err.into()),
+        Category::Syntax => JsonError::Syntax {
+            msg: err.to_string(),
+            line,
+            column,
+        }
+        .into(),
+        Category::Data => match invalid {
+            Some(Invalid::Cid(msg)) => JsonError::InvalidCid { msg, line, column },
+            Some(Invalid::Bytes(msg)) => JsonError::InvalidBytes { msg, line, column },
+            Some(Invalid::ReservedKey(msg)) => JsonError::ReservedKey { msg, line, column },
+            None => JsonError::Data {
+                msg: err.to_string(),
+                line,
+                column,
+            },
+        }
+        .into(),
+        Category::Eof => JsonError::Eof { line, column }.into(),
+    }
+}
+
+fn serialize<S: ser::Serializer>(c: DagJsonCodec, ipld: &Ipld, ser: S) -> Result<S::Ok, S::Error> {
     match &ipld {
         Ipld::Null => ser.serialize_none(),
         Ipld::Bool(bool) => ser.serialize_bool(*bool),
-        Ipld::Integer(i128) => ser.serialize_i128(*i128),
-        Ipld::Float(f64) => ser.serialize_f64(*f64),
-        Ipld::String(string) => ser.serialize_str(&string),
-        Ipld::Bytes(bytes) => ser.serialize_bytes(&bytes),
+        Ipld::Integer(i128) => serialize_integer(c, *i128, ser),
+        Ipld::Float(f64) => serialize_float(*f64, ser),
+        Ipld::String(string) => ser.serialize_str(string),
+        Ipld::Bytes(bytes) => serialize_bytes(bytes, ser),
         Ipld::List(list) => {
-            let wrapped = list.iter().map(|ipld| Wrapper(ipld));
+            let wrapped = list.iter().map(|ipld| Wrapper(c, ipld));
             ser.collect_seq(wrapped)
         }
+        Ipld::StringMap(map) if map.len() == 1 && map.contains_key(LINK_KEY) => {
+            Err(ser::Error::custom(RESERVED_KEY))
+        }
         Ipld::StringMap(map) => {
-            let wrapped = map.iter().map(|(key, ipld)| (key, Wrapper(ipld)));
+            let wrapped = map.iter().map(|(key, ipld)| (key, Wrapper(c, ipld)));
             ser.collect_map(wrapped)
         }
         #[cfg(feature = "unleashed")]
+        Ipld::IntegerMap(_) | Ipld::Tag(_, _) if c.is_canonical() => Err(unleashed_error()),
+        #[cfg(feature = "unleashed")]
         Ipld::IntegerMap(map) => {
-            let wrapped = map.iter().map(|(key, ipld)| (key, Wrapper(ipld)));
+            let wrapped = map.iter().map(|(key, ipld)| (key, Wrapper(c, ipld)));
             ser.collect_map(wrapped)
         }
         #[cfg(feature = "unleashed")]
         Ipld::Tag(tag, ipld) => {
             let mut map = BTreeMap::new();
-            map.insert("/", (tag, Wrapper(ipld)));
+            map.insert(LINK_KEY, (tag, Wrapper(c, ipld)));
             ser.collect_map(map)
         }
-        Ipld::Link(link) => {
-            let value = base64::encode(&link.to_bytes());
-            let mut map = BTreeMap::new();
-            map.insert("/", value);
+        Ipld::Link(link) => serialize_link(link, ser),
+    }
+}
 
+fn serialize_arc<S: ser::Serializer>(
+    c: DagJsonCodec,
+    ipld: &IpldArc,
+    ser: S,
+) -> Result<S::Ok, S::Error> {
+    match &ipld {
+        IpldArc::Null => ser.serialize_none(),
+        IpldArc::Bool(bool) => ser.serialize_bool(*bool),
+        IpldArc::Integer(i128) => serialize_integer(c, *i128, ser),
+        IpldArc::Float(f64) => serialize_float(*f64, ser),
+        IpldArc::String(string) => ser.serialize_str(string),
+        IpldArc::Bytes(bytes) => serialize_bytes(bytes, ser),
+        IpldArc::List(list) => {
+            let wrapped = list.iter().map(|ipld| ArcWrapper(c, ipld));
+            ser.collect_seq(wrapped)
+        }
+        IpldArc::StringMap(map) if map.len() == 1 && map.contains_key(LINK_KEY) => {
+            Err(ser::Error::custom(RESERVED_KEY))
+        }
+        IpldArc::StringMap(map) => {
+            let wrapped = map.iter().map(|(key, ipld)| (&**key, ArcWrapper(c, ipld)));
+            ser.collect_map(wrapped)
+        }
+        #[cfg(feature = "unleashed")]
+        IpldArc::IntegerMap(_) | IpldArc::Tag(_, _) if c.is_canonical() => Err(unleashed_error()),
+        #[cfg(feature = "unleashed")]
+        IpldArc::IntegerMap(map) => {
+            let wrapped = map.iter().map(|(key, ipld)| (key, ArcWrapper(c, ipld)));
+            ser.collect_map(wrapped)
+        }
+        #[cfg(feature = "unleashed")]
+        IpldArc::Tag(tag, ipld) => {
+            let mut map = BTreeMap::new();
+            map.insert(LINK_KEY, (tag, ArcWrapper(c, ipld)));
             ser.collect_map(map)
         }
+        IpldArc::Link(link) => serialize_link(link, ser),
+    }
+}
+
+fn serialize_integer<S: ser::Serializer>(
+    c: DagJsonCodec,
+    i128: i128,
+    ser: S,
+) -> Result<S::Ok, S::Error> {
+    if c.is_canonical() && !(-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(&i128) {
+        return Err(ser::Error::custom(format!(
+            "integer {} is outside of the safe range",
+            i128
+        )));
+    }
+    ser.serialize_i128(i128)
+}
+
+fn serialize_float<S: ser::Serializer>(f64: f64, ser: S) -> Result<S::Ok, S::Error> {
+    // serde_json silently encodes them as null
+    if !f64.is_finite() {
+        return Err(ser::Error::custom(format!("float {} is not finite", f64)));
     }
+    ser.serialize_f64(f64)
+}
+
+fn serialize_bytes<S: ser::Serializer>(bytes: &[u8], ser: S) -> Result<S::Ok, S::Error> {
+    let value = base64::encode_config(bytes, base64::STANDARD_NO_PAD);
+    let mut inner = BTreeMap::new();
+    inner.insert(BYTES_KEY, value);
+    let mut map = BTreeMap::new();
+    map.insert(LINK_KEY, inner);
+    ser.collect_map(map)
+}
+
+fn serialize_link<S: ser::Serializer>(link: &Cid, ser: S) -> Result<S::Ok, S::Error> {
+    let mut map = BTreeMap::new();
+    map.insert(LINK_KEY, link.to_string());
+    ser.collect_map(map)
 }
 
-fn deserialize<'de, D: de::Deserializer<'de>>(deserializer: D) -> Result<Ipld, D::Error> {
-    // Sadly such a PhantomData hack is needed
-    deserializer.deserialize_any(JsonVisitor)
+#[cfg(feature = "unleashed")]
+fn unleashed_error<E: ser::Error>() -> E {
+    ser::Error::custom("integer maps and tags are not part of the data model")
 }
 
 // Needed for `collect_seq` and `collect_map` in Seserializer
-struct Wrapper<'a>(&'a Ipld);
+struct Wrapper<'a>(DagJsonCodec, &'a Ipld);
 
 impl<'a> Serialize for Wrapper<'a> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: ser::Serializer,
     {
-        serialize(&self.0, serializer)
+        serialize(self.0, self.1, serializer)
+    }
+}
+
+struct ArcWrapper<'a>(DagJsonCodec, &'a IpldArc);
+
+impl<'a> Serialize for ArcWrapper<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serialize_arc(self.0, self.1, serializer)
+    }
+}
+
+/// Decodes ipld with the options of a codec.
+pub(crate) struct IpldSeed<'a>(pub DagJsonCodec, pub &'a Cell<Option<Invalid>>);
+
+impl<'de, 'a> DeserializeSeed<'de> for IpldSeed<'a> {
+    type Value = Ipld;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Ipld, D::Error> {
+        deserializer.deserialize_any(JsonVisitor(self.0, self.1))
     }
 }
 
 // serde deserializer visitor that is used by Deseraliazer to decode
 // json into IPLD.
-struct JsonVisitor;
-impl<'de> de::Visitor<'de> for JsonVisitor {
+struct JsonVisitor<'a>(DagJsonCodec, &'a Cell<Option<Invalid>>);
+
+/// Decodes the value of a map with the single key `/`, which is reserved for links and bytes.
+pub(crate) fn decode_slash<E: de::Error>(
+    c: DagJsonCodec,
+    invalid: &Cell<Option<Invalid>>,
+    value: Ipld,
+) -> Result<Ipld, E> {
+    match value {
+        Ipld::String(s) => match Cid::try_from(s.as_str()) {
+            Ok(cid) => Ok(Ipld::Link(cid)),
+            Err(err) if !c.legacy_links() => {
+                Err(Invalid::Cid(format!("{:?}: {}", s, err)).record(invalid))
+            }
+            Err(err) => base64::decode(&s)
+                .ok()
+                .and_then(|bytes| Cid::try_from(bytes).ok())
+                .map(Ipld::Link)
+                .ok_or_else(|| Invalid::Cid(format!("{:?}: {}", s, err)).record(invalid)),
+        },
+        Ipld::StringMap(map) if map.len() == 1 && map.contains_key(BYTES_KEY) => {
+            match &map[BYTES_KEY] {
+                Ipld::String(s) => {
+                    base64::decode_config(s.trim_end_matches('='), base64::STANDARD_NO_PAD)
+                        .map(Ipld::Bytes)
+                        .map_err(|err| Invalid::Bytes(format!("{:?}: {}", s, err)).record(invalid))
+                }
+                _ => Err(Invalid::Bytes("expected a base64 string".into()).record(invalid)),
+            }
+        }
+        #[cfg(feature = "unleashed")]
+        Ipld::List(list) if list.len() == 2 => match (&list[0], &list[1]) {
+            (Ipld::Integer(tag), ipld) if u64::try_from(*tag).is_ok() => {
+                Ok(Ipld::Tag(*tag as u64, Box::new(ipld.clone())))
+            }
+            _ => Err(Invalid::ReservedKey("expected a tag".into()).record(invalid)),
+        },
+        _ => {
+            Err(Invalid::ReservedKey("expected a cid string or a bytes map".into()).record(invalid))
+        }
+    }
+}
+
+impl<'de, 'a> de::Visitor<'de> for JsonVisitor<'a> {
     type Value = Ipld;
 
     fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -99,19 +317,6 @@ impl<'de> de::Visitor<'de> for JsonVisitor {
     {
         Ok(Ipld::String(value))
     }
-    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        self.visit_byte_buf(v.to_owned())
-    }
-
-    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        Ok(Ipld::Bytes(v))
-    }
 
     fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
     where
@@ -131,7 +336,7 @@ impl<'de> de::Visitor<'de> for JsonVisitor {
     where
         E: de::Error,
     {
-        Ok(Ipld::Integer(v))
+        Ipld::try_from(v).map_err(E::custom)
     }
 
     fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
@@ -159,41 +364,31 @@ impl<'de> de::Visitor<'de> for JsonVisitor {
     where
         V: de::SeqAccess<'de>,
     {
-        let mut vec: Vec<WrapperOwned> = Vec::new();
-
-        while let Some(elem) = visitor.next_element()? {
+        let mut vec = Vec::new();
+        while let Some(elem) = visitor.next_element_seed(IpldSeed(self.0, self.1))? {
             vec.push(elem);
         }
-
-        let unwrapped = vec.into_iter().map(|WrapperOwned(ipld)| ipld).collect();
-        Ok(Ipld::List(unwrapped))
+        Ok(Ipld::List(vec))
     }
 
     fn visit_map<V>(self, mut visitor: V) -> Result<Self::Value, V::Error>
     where
         V: de::MapAccess<'de>,
     {
-        let mut values: Vec<(String, WrapperOwned)> = Vec::new();
-
-        while let Some((key, value)) = visitor.next_entry()? {
-            values.push((key, value));
+        let mut map = BTreeMap::new();
+        while let Some(key) = visitor.next_key::<String>()? {
+            let value = visitor.next_value_seed(IpldSeed(self.0, self.1))?;
+            map.insert(key, value);
         }
 
-        // JSON Object represents IPLD Link if it is `{ "/": "...." }` therefor
-        // we valiadet if that is the case here.
-        if let Some((key, WrapperOwned(Ipld::String(value)))) = values.first() {
-            if key == LINK_KEY && values.len() == 1 {
-                let link = base64::decode(value).map_err(SerdeError::custom)?;
-                let cid = Cid::try_from(link).map_err(SerdeError::custom)?;
-                return Ok(Ipld::Link(cid));
+        // JSON Object represents IPLD Link or Bytes if it is `{ "/": ... }` therefor
+        // we validate if that is the case here.
+        if map.len() == 1 {
+            if let Some(value) = map.remove(LINK_KEY) {
+                return decode_slash(self.0, self.1, value);
             }
         }
-
-        let unwrapped = values
-            .into_iter()
-            .map(|(key, WrapperOwned(value))| (key, value))
-            .collect();
-        Ok(Ipld::StringMap(unwrapped))
+        Ok(Ipld::StringMap(map))
     }
 
     fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
@@ -204,22 +399,114 @@ impl<'de> de::Visitor<'de> for JsonVisitor {
     }
 }
 
-// Needed for `visit_seq` and `visit_map` in Deserializer
-/// We cannot directly implement `serde::Deserializer` for `Ipld` as it is a remote type.
-/// Instead wrap it into a newtype struct and implement `serde::Deserialize` for that one.
-/// All the deserializer does is calling the `deserialize()` function we defined which returns
-/// an unwrapped `Ipld` instance. Wrap that `Ipld` instance in `Wrapper` and return it.
-/// Users of this wrapper will then unwrap it again so that they can return the expected `Ipld`
-/// instance.
-struct WrapperOwned(Ipld);
+/// Scans json for links without decoding it.
+///
+/// Only the values of the reserved key `/` are decoded, to check them like [`JsonVisitor`] does.
+struct ReferencesSeed<'a, E> {
+    codec: DagJsonCodec,
+    invalid: &'a Cell<Option<Invalid>>,
+    set: &'a mut E,
+}
 
-impl<'de> Deserialize<'de> for WrapperOwned {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: de::Deserializer<'de>,
-    {
-        let deserialized = deserialize(deserializer);
-        // Better version of Ok(Wrapper(deserialized.unwrap()))
-        deserialized.map(Self)
+impl<'a, E: Extend<Cid>> ReferencesSeed<'a, E> {
+    fn reborrow(&mut self) -> ReferencesSeed<'_, E> {
+        ReferencesSeed {
+            codec: self.codec,
+            invalid: self.invalid,
+            set: self.set,
+        }
+    }
+}
+
+impl<'de, 'a, E: Extend<Cid>> DeserializeSeed<'de> for ReferencesSeed<'a, E> {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, 'a, E: Extend<Cid>> de::Visitor<'de> for ReferencesSeed<'a, E> {
+    type Value = ();
+
+    fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("any valid JSON value")
+    }
+
+    fn visit_bool<Er: de::Error>(self, _: bool) -> Result<(), Er> {
+        Ok(())
+    }
+
+    fn visit_i64<Er: de::Error>(self, _: i64) -> Result<(), Er> {
+        Ok(())
+    }
+
+    fn visit_u64<Er: de::Error>(self, _: u64) -> Result<(), Er> {
+        Ok(())
+    }
+
+    fn visit_i128<Er: de::Error>(self, v: i128) -> Result<(), Er> {
+        Ipld::try_from(v).map(drop).map_err(Er::custom)
+    }
+
+    fn visit_f64<Er: de::Error>(self, _: f64) -> Result<(), Er> {
+        Ok(())
+    }
+
+    fn visit_str<Er: de::Error>(self, _: &str) -> Result<(), Er> {
+        Ok(())
+    }
+
+    fn visit_unit<Er: de::Error>(self) -> Result<(), Er> {
+        Ok(())
+    }
+
+    fn visit_seq<V: de::SeqAccess<'de>>(mut self, mut seq: V) -> Result<(), V::Error> {
+        while seq.next_element_seed(self.reborrow())?.is_some() {}
+        Ok(())
+    }
+
+    fn visit_map<V: de::MapAccess<'de>>(mut self, mut map: V) -> Result<(), V::Error> {
+        match map.next_key_seed(IsLinkKey)? {
+            None => return Ok(()),
+            Some(true) => {
+                let value = map.next_value_seed(IpldSeed(self.codec, self.invalid))?;
+                if map.next_key_seed(IsLinkKey)?.is_none() {
+                    decode_slash::<V::Error>(self.codec, self.invalid, value)?.references(self.set);
+                    return Ok(());
+                }
+                value.references(self.set);
+            }
+            Some(false) => {}
+        }
+        // the key of this value was read above
+        map.next_value_seed(self.reborrow())?;
+        while map.next_key_seed(IsLinkKey)?.is_some() {
+            map.next_value_seed(self.reborrow())?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks if a map key is the reserved key `/`.
+struct IsLinkKey;
+
+impl<'de> DeserializeSeed<'de> for IsLinkKey {
+    type Value = bool;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<bool, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de> de::Visitor<'de> for IsLinkKey {
+    type Value = bool;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("a string")
+    }
+
+    fn visit_str<E: de::Error>(self, key: &str) -> Result<bool, E> {
+        Ok(key == LINK_KEY)
     }
 }
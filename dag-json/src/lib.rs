@@ -5,17 +5,70 @@
 use core::convert::TryFrom;
 use libipld_core::cid::Cid;
 use libipld_core::codec::{Codec, Decode, Encode, References};
-use libipld_core::error::{Result, UnsupportedCodec};
+use libipld_core::error::{Result, TypeError, TypeErrorType, UnsupportedCodec};
 use libipld_core::ipld::Ipld;
-// TODO vmx 2020-05-28: Don't expose the `serde_json` error directly, but wrap it in a custom one
-pub use serde_json::Error;
+use libipld_core::ipld_arc::IpldArc;
 use std::io::{Read, Seek, Write};
 
 mod codec;
+#[cfg(feature = "serde-codec")]
+pub mod serde;
 
 /// Json codec.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub struct DagJsonCodec;
+///
+/// Links are encoded as `{"/": "<cid>"}` and bytes as `{"/": {"bytes": "<base64>"}}` as
+/// required by the DAG-JSON spec. Use [`DagJsonCodec::with_legacy_links`] to decode data
+/// written by libipld 0.12 and earlier, which encoded links as base64 cid bytes. Use
+/// [`DagJsonCodec::canonical`] to produce the same bytes as other implementations.
+///
+/// `NaN` and infinite floats are always rejected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DagJsonCodec {
+    legacy_links: bool,
+    canonical: bool,
+}
+
+/// The default `DagJsonCodec`.
+#[allow(non_upper_case_globals)]
+pub const DagJsonCodec: DagJsonCodec = DagJsonCodec {
+    legacy_links: false,
+    canonical: false,
+};
+
+impl DagJsonCodec {
+    /// Creates a codec that only encodes canonical DAG-JSON.
+    ///
+    /// Map keys are sorted by their bytes, there is no whitespace and floats always contain a
+    /// decimal point or an exponent. Integers outside of `-(2^53 - 1)..=2^53 - 1`, which
+    /// JavaScript can't represent exactly, integer maps and tags are rejected.
+    pub const fn canonical() -> Self {
+        Self {
+            legacy_links: false,
+            canonical: true,
+        }
+    }
+
+    /// Returns `true` if the codec is canonical.
+    pub const fn is_canonical(&self) -> bool {
+        self.canonical
+    }
+
+    /// Returns a copy of the codec that also decodes links of the form
+    /// `{"/": "<base64 cid bytes>"}`.
+    ///
+    /// Links are always encoded as cid strings.
+    pub const fn with_legacy_links(self, legacy_links: bool) -> Self {
+        Self {
+            legacy_links,
+            ..self
+        }
+    }
+
+    /// Returns `true` if legacy links are decoded.
+    pub const fn legacy_links(&self) -> bool {
+        self.legacy_links
+    }
+}
 
 impl Codec for DagJsonCodec {}
 
@@ -29,19 +82,40 @@ impl TryFrom<u64> for DagJsonCodec {
     type Error = UnsupportedCodec;
 
     fn try_from(_: u64) -> core::result::Result<Self, Self::Error> {
-        Ok(Self)
+        Ok(Self::default())
     }
 }
 
 impl Encode<DagJsonCodec> for Ipld {
-    fn encode<W: Write>(&self, _: DagJsonCodec, w: &mut W) -> Result<()> {
-        Ok(codec::encode(self, w)?)
+    fn encode<W: Write>(&self, c: DagJsonCodec, w: &mut W) -> Result<()> {
+        codec::encode(c, self, w)
+    }
+}
+
+impl Encode<DagJsonCodec> for IpldArc {
+    fn encode<W: Write>(&self, c: DagJsonCodec, w: &mut W) -> Result<()> {
+        codec::encode_arc(c, self, w)
     }
 }
 
 impl Decode<DagJsonCodec> for Ipld {
-    fn decode<R: Read + Seek>(_: DagJsonCodec, r: &mut R) -> Result<Self> {
-        Ok(codec::decode(r)?)
+    fn decode<R: Read + Seek>(c: DagJsonCodec, r: &mut R) -> Result<Self> {
+        codec::decode(c, r)
+    }
+}
+
+impl Encode<DagJsonCodec> for Cid {
+    fn encode<W: Write>(&self, c: DagJsonCodec, w: &mut W) -> Result<()> {
+        codec::encode(c, &Ipld::Link(*self), w)
+    }
+}
+
+impl Decode<DagJsonCodec> for Cid {
+    fn decode<R: Read + Seek>(c: DagJsonCodec, r: &mut R) -> Result<Self> {
+        match codec::decode(c, r)? {
+            Ipld::Link(cid) => Ok(cid),
+            ipld => Err(TypeError::new(TypeErrorType::Link, ipld).into()),
+        }
     }
 }
 
@@ -51,8 +125,7 @@ impl References<DagJsonCodec> for Ipld {
         r: &mut R,
         set: &mut E,
     ) -> Result<()> {
-        Ipld::decode(c, r)?.references(set);
-        Ok(())
+        codec::references(c, r, set)
     }
 }
 
@@ -84,13 +157,215 @@ mod tests {
 
         assert_eq!(
             std::str::from_utf8(&contact_encoded).unwrap(),
-            format!(
-                r#"{{"details":{{"/":"{}"}},"name":"Hello World!"}}"#,
-                base64::encode(cid.to_bytes()),
-            )
+            format!(r#"{{"details":{{"/":"{}"}},"name":"Hello World!"}}"#, cid)
         );
 
         let contact_decoded: Ipld = DagJsonCodec.decode(&contact_encoded).unwrap();
         assert_eq!(contact_decoded, contact);
     }
+
+    #[test]
+    fn encode_links() {
+        let digest = Code::Sha2_256.digest(&b"block"[..]);
+        let v0 = Cid::new_v0(digest).unwrap();
+        let v1 = Cid::new_v1(0x71, digest);
+        let ipld = Ipld::List(vec![Ipld::Link(v0), Ipld::Link(v1)]);
+        let bytes = DagJsonCodec.encode(&ipld).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&bytes).unwrap(),
+            format!(r#"[{{"/":"{}"}},{{"/":"{}"}}]"#, v0, v1)
+        );
+        assert!(v0.to_string().starts_with("Qm"));
+        assert!(v1.to_string().starts_with('b'));
+        assert_eq!(DagJsonCodec.decode::<Ipld>(&bytes).unwrap(), ipld);
+    }
+
+    #[test]
+    fn encode_bytes() {
+        let ipld = Ipld::Bytes(b"hello".to_vec());
+        let bytes = DagJsonCodec.encode(&ipld).unwrap();
+        assert_eq!(bytes, br#"{"/":{"bytes":"aGVsbG8"}}"#);
+        assert_eq!(DagJsonCodec.decode::<Ipld>(&bytes).unwrap(), ipld);
+        let padded = br#"{"/":{"bytes":"aGVsbG8="}}"#;
+        assert_eq!(DagJsonCodec.decode::<Ipld>(padded).unwrap(), ipld);
+
+        // the reserved key doesn't prevent other maps
+        let json = br#"{"/":{"bytes":"aGVsbG8"},"other":1}"#;
+        assert!(matches!(
+            DagJsonCodec.decode::<Ipld>(json).unwrap(),
+            Ipld::StringMap(_)
+        ));
+        // but maps with only the reserved key must be links or bytes
+        let mut map = BTreeMap::new();
+        map.insert("/".to_string(), Ipld::String("not a link".into()));
+        assert!(DagJsonCodec.encode(&Ipld::StringMap(map)).is_err());
+    }
+
+    #[test]
+    fn encode_cid() {
+        use libipld_core::link::Link;
+
+        let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(&b"block"[..]));
+        let bytes = DagJsonCodec.encode(&cid).unwrap();
+        assert_eq!(bytes, format!(r#"{{"/":"{}"}}"#, cid).as_bytes());
+        assert_eq!(DagJsonCodec.decode::<Cid>(&bytes).unwrap(), cid);
+
+        let link = Link::<Ipld>::new(cid);
+        assert_eq!(DagJsonCodec.encode(&link).unwrap(), bytes);
+        assert_eq!(DagJsonCodec.decode::<Link<Ipld>>(&bytes).unwrap(), link);
+        assert!(DagJsonCodec.decode::<Cid>(b"null").is_err());
+    }
+
+    #[test]
+    fn decode_legacy_links() {
+        let cid = Cid::new_v1(0x55, Code::Blake3_256.digest(&b"block"[..]));
+        let json = format!(r#"{{"/":"{}"}}"#, base64::encode(cid.to_bytes()));
+        assert!(DagJsonCodec.decode::<Ipld>(json.as_bytes()).is_err());
+        let legacy = DagJsonCodec.with_legacy_links(true);
+        assert_eq!(
+            legacy.decode::<Ipld>(json.as_bytes()).unwrap(),
+            Ipld::Link(cid)
+        );
+        let json = format!(r#"{{"/":"{}"}}"#, cid);
+        assert_eq!(
+            legacy.decode::<Ipld>(json.as_bytes()).unwrap(),
+            Ipld::Link(cid)
+        );
+    }
+
+    #[test]
+    fn canonical() {
+        let c = DagJsonCodec::canonical();
+        let mut map = BTreeMap::new();
+        map.insert("b".to_string(), Ipld::Float(1.0));
+        map.insert("aa".to_string(), Ipld::Float(1e100));
+        map.insert(
+            "a".to_string(),
+            Ipld::List(vec![Ipld::Integer(-1), Ipld::Null]),
+        );
+        let ipld = Ipld::StringMap(map);
+        let bytes = c.encode(&ipld).unwrap();
+        assert_eq!(bytes, br#"{"a":[-1,null],"aa":1e100,"b":1.0}"#);
+        assert_eq!(c.decode::<Ipld>(&bytes).unwrap(), ipld);
+
+        let max = (1i128 << 53) - 1;
+        assert!(c.encode(&Ipld::Integer(max)).is_ok());
+        assert!(c.encode(&Ipld::Integer(-max)).is_ok());
+        assert!(c.encode(&Ipld::Integer(max + 1)).is_err());
+        assert!(c.encode(&Ipld::Integer(-max - 1)).is_err());
+        assert!(c.encode(&Ipld::Integer(i128::MIN)).is_err());
+        assert!(c.encode(&IpldArc::Integer(i128::MIN)).is_err());
+        assert!(DagJsonCodec.encode(&Ipld::Integer(max + 1)).is_ok());
+
+        for f in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(c.encode(&Ipld::Float(f)).is_err());
+            assert!(DagJsonCodec.encode(&Ipld::Float(f)).is_err());
+        }
+    }
+
+    #[test]
+    fn references() {
+        use std::collections::HashSet;
+
+        let cids: Vec<_> = (0..4u8)
+            .map(|i| Cid::new_v1(0x55, Code::Sha2_256.digest(&[i])))
+            .collect();
+        let json = format!(
+            r#"{{
+                "list": [1, -2.5, null, true, "/", {{"/": "{}"}}],
+                "bytes": {{"/": {{"bytes": "aGVsbG8"}}}},
+                "map": {{"/": {{"nested": {{"/": "{}"}}}}, "other": {{"/": "{}"}}}},
+                "link": {{"/": "{}"}}
+            }}"#,
+            cids[0], cids[1], cids[2], cids[3]
+        );
+        let mut refs = HashSet::new();
+        DagJsonCodec
+            .references::<Ipld, _>(json.as_bytes(), &mut refs)
+            .unwrap();
+        assert_eq!(refs, cids.iter().copied().collect());
+
+        let mut expected = HashSet::new();
+        DagJsonCodec
+            .decode::<Ipld>(json.as_bytes())
+            .unwrap()
+            .references(&mut expected);
+        assert_eq!(refs, expected);
+
+        for json in [
+            r#"[{"/": "bafy"}]"#,
+            r#"{"/": 1}"#,
+            r#"{"/": {"bytes": "!"}}"#,
+            "[1,",
+        ] {
+            let res = DagJsonCodec.references::<Ipld, _>(json.as_bytes(), &mut refs);
+            assert!(res.is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn decode_errors() {
+        use libipld_core::error::{Error, JsonError};
+
+        let decode = |json: &str| match DagJsonCodec.decode::<Ipld>(json.as_bytes()) {
+            Err(Error::Json(err)) => err,
+            res => panic!("unexpected {:?}", res),
+        };
+        assert!(matches!(decode("[1,\n2"), JsonError::Eof { line: 2, .. }));
+        assert!(matches!(
+            decode("[1,\n}"),
+            JsonError::Syntax { line: 2, .. }
+        ));
+        assert!(matches!(
+            decode("[\n{\"/\": \"bafy\"}]"),
+            JsonError::InvalidCid { line: 2, .. }
+        ));
+        assert!(matches!(
+            decode(r#"{"/": {"bytes": "!"}}"#),
+            JsonError::InvalidBytes { line: 1, .. }
+        ));
+        assert!(matches!(
+            decode(r#"{"/": {"bytes": "aGVsbG8", "other": 1}}"#),
+            JsonError::ReservedKey { .. }
+        ));
+        assert!(matches!(
+            decode(r#"{"/": 1}"#),
+            JsonError::ReservedKey { .. }
+        ));
+    }
+
+    #[test]
+    fn async_decode() {
+        use core::pin::Pin;
+        use core::task::{Context, Poll};
+        use futures::executor::block_on;
+        use futures::io::AsyncRead;
+        use libipld_core::codec::AsyncDecoder;
+
+        /// Reader returning one byte at a time.
+        struct OneByte<'a>(&'a [u8]);
+
+        impl AsyncRead for OneByte<'_> {
+            fn poll_read(
+                mut self: Pin<&mut Self>,
+                _: &mut Context,
+                buf: &mut [u8],
+            ) -> Poll<std::io::Result<usize>> {
+                let n = self.0.len().min(buf.len()).min(1);
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Poll::Ready(Ok(n))
+            }
+        }
+
+        let cid = Cid::new_v1(0x55, Code::Blake3_256.digest(&b"block"[..]));
+        let json = format!(r#"[1,2] {{"link": {{"/": "{}"}}}} 12"#, cid);
+        let mut decoder = AsyncDecoder::new(DagJsonCodec, OneByte(json.as_bytes()));
+        let list: Ipld = block_on(decoder.decode()).unwrap();
+        assert_eq!(list, Ipld::List(vec![Ipld::Integer(1), Ipld::Integer(2)]));
+        let map: Ipld = block_on(decoder.decode()).unwrap();
+        assert_eq!(map.get("link").unwrap(), &Ipld::Link(cid));
+        let int: Ipld = block_on(decoder.decode()).unwrap();
+        assert_eq!(int, Ipld::Integer(12));
+    }
 }
@@ -0,0 +1,381 @@
+//! DAG-JSON deserializer.
+//!
+//! `serde_json` does the parsing. Its deserializer is wrapped to turn the maps with the reserved
+//! key `/` into links and bytes.
+use crate::codec::{decode_slash, json_error, Invalid, IpldSeed, LINK_KEY};
+use crate::DagJsonCodec;
+use libipld_core::error::Result;
+use libipld_core::ipld::Ipld;
+use libipld_core::serde::CID_SERDE_PRIVATE_IDENTIFIER;
+use serde::de::value::BytesDeserializer;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, Deserializer as _, Error as _, IntoDeserializer,
+};
+use std::cell::Cell;
+use std::fmt;
+use std::io::Read;
+
+pub(crate) fn from_reader<R: Read, T: DeserializeOwned>(c: DagJsonCodec, reader: R) -> Result<T> {
+    let mut de = serde_json::Deserializer::from_reader(reader);
+    let invalid = Cell::new(None);
+    let ctx = Context {
+        codec: c,
+        invalid: &invalid,
+    };
+    T::deserialize(Deserializer { de: &mut de, ctx })
+        .and_then(|value| de.end().map(|()| value))
+        .map_err(|err| json_error(err, invalid.take()))
+}
+
+/// Options threaded through the wrappers.
+#[derive(Clone, Copy)]
+struct Context<'a> {
+    codec: DagJsonCodec,
+    invalid: &'a Cell<Option<Invalid>>,
+}
+
+impl<'a> Context<'a> {
+    fn ipld(self) -> IpldSeed<'a> {
+        IpldSeed(self.codec, self.invalid)
+    }
+}
+
+/// Wraps a deserializer.
+struct Deserializer<'a, D> {
+    de: D,
+    ctx: Context<'a>,
+}
+
+macro_rules! forward {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $method<V: de::Visitor<'de>>(
+                self,
+                $($arg: $ty,)*
+                visitor: V,
+            ) -> Result<V::Value, D::Error> {
+                self.de.$method($($arg,)* Visitor { visitor, ctx: self.ctx })
+            }
+        )*
+    };
+}
+
+impl<'de, 'a, D: de::Deserializer<'de>> de::Deserializer<'de> for Deserializer<'a, D> {
+    type Error = D::Error;
+
+    forward! {
+        deserialize_any();
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_i128();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_u128();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_option();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_seq();
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map();
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+        deserialize_ignored_any();
+    }
+
+    fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+        match self.ctx.ipld().deserialize(self.de)? {
+            Ipld::Bytes(bytes) => visitor.visit_byte_buf(bytes),
+            ipld => ipld.deserialize_any(visitor).map_err(D::Error::custom),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        if name == CID_SERDE_PRIVATE_IDENTIFIER {
+            match self.ctx.ipld().deserialize(self.de)? {
+                Ipld::Link(cid) => {
+                    visitor.visit_newtype_struct(BytesDeserializer::new(&cid.to_bytes()))
+                }
+                _ => Err(D::Error::custom("expected a link")),
+            }
+        } else {
+            self.de.deserialize_newtype_struct(
+                name,
+                Visitor {
+                    visitor,
+                    ctx: self.ctx,
+                },
+            )
+        }
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.de.is_human_readable()
+    }
+}
+
+/// Wraps a seed.
+struct Seed<'a, S> {
+    seed: S,
+    ctx: Context<'a>,
+}
+
+impl<'de, 'a, S: DeserializeSeed<'de>> DeserializeSeed<'de> for Seed<'a, S> {
+    type Value = S::Value;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, de: D) -> Result<S::Value, D::Error> {
+        self.seed.deserialize(Deserializer { de, ctx: self.ctx })
+    }
+}
+
+/// Wraps a visitor.
+struct Visitor<'a, V> {
+    visitor: V,
+    ctx: Context<'a>,
+}
+
+macro_rules! forward_visit {
+    ($($method:ident($ty:ty);)*) => {
+        $(
+            fn $method<E: de::Error>(self, value: $ty) -> Result<V::Value, E> {
+                self.visitor.$method(value)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a, V: de::Visitor<'de>> de::Visitor<'de> for Visitor<'a, V> {
+    type Value = V::Value;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.visitor.expecting(fmt)
+    }
+
+    forward_visit! {
+        visit_bool(bool);
+        visit_i8(i8);
+        visit_i16(i16);
+        visit_i32(i32);
+        visit_i64(i64);
+        visit_i128(i128);
+        visit_u8(u8);
+        visit_u16(u16);
+        visit_u32(u32);
+        visit_u64(u64);
+        visit_u128(u128);
+        visit_f32(f32);
+        visit_f64(f64);
+        visit_char(char);
+        visit_str(&str);
+        visit_borrowed_str(&'de str);
+        visit_string(String);
+        visit_bytes(&[u8]);
+        visit_borrowed_bytes(&'de [u8]);
+        visit_byte_buf(Vec<u8>);
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<V::Value, E> {
+        self.visitor.visit_none()
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<V::Value, E> {
+        self.visitor.visit_unit()
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, de: D) -> Result<V::Value, D::Error> {
+        self.visitor.visit_some(Deserializer { de, ctx: self.ctx })
+    }
+
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(self, de: D) -> Result<V::Value, D::Error> {
+        self.visitor
+            .visit_newtype_struct(Deserializer { de, ctx: self.ctx })
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, seq: A) -> Result<V::Value, A::Error> {
+        self.visitor.visit_seq(SeqAccess { seq, ctx: self.ctx })
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<V::Value, A::Error> {
+        let key = map.next_key::<String>()?;
+        if key.as_deref() != Some(LINK_KEY) {
+            return self.visitor.visit_map(MapAccess {
+                map,
+                slash: None,
+                key,
+                ctx: self.ctx,
+            });
+        }
+        let value = map.next_value_seed(self.ctx.ipld())?;
+        match map.next_key::<String>()? {
+            None => decode_slash(self.ctx.codec, self.ctx.invalid, value)?
+                .deserialize_any(self.visitor)
+                .map_err(A::Error::custom),
+            key => self.visitor.visit_map(MapAccess {
+                map,
+                slash: Some(value),
+                key,
+                ctx: self.ctx,
+            }),
+        }
+    }
+
+    fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> Result<V::Value, A::Error> {
+        self.visitor.visit_enum(EnumAccess {
+            data,
+            ctx: self.ctx,
+        })
+    }
+}
+
+/// Wraps the elements of a sequence.
+struct SeqAccess<'a, A> {
+    seq: A,
+    ctx: Context<'a>,
+}
+
+impl<'de, 'a, A: de::SeqAccess<'de>> de::SeqAccess<'de> for SeqAccess<'a, A> {
+    type Error = A::Error;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, A::Error> {
+        self.seq.next_element_seed(Seed {
+            seed,
+            ctx: self.ctx,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.seq.size_hint()
+    }
+}
+
+/// Wraps the entries of a map.
+///
+/// The first entries may already have been read to check for the reserved key `/`: `slash` is
+/// the value of the key `/` and `key` the key following it.
+struct MapAccess<'a, A> {
+    map: A,
+    slash: Option<Ipld>,
+    key: Option<String>,
+    ctx: Context<'a>,
+}
+
+impl<'de, 'a, A: de::MapAccess<'de>> de::MapAccess<'de> for MapAccess<'a, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, A::Error> {
+        if self.slash.is_some() {
+            seed.deserialize(LINK_KEY.into_deserializer()).map(Some)
+        } else if let Some(key) = self.key.take() {
+            seed.deserialize(key.into_deserializer()).map(Some)
+        } else {
+            self.map.next_key_seed(Seed {
+                seed,
+                ctx: self.ctx,
+            })
+        }
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value, A::Error> {
+        match self.slash.take() {
+            Some(value) => seed.deserialize(value).map_err(A::Error::custom),
+            None => self.map.next_value_seed(Seed {
+                seed,
+                ctx: self.ctx,
+            }),
+        }
+    }
+}
+
+/// Wraps the variant of an enum.
+struct EnumAccess<'a, A> {
+    data: A,
+    ctx: Context<'a>,
+}
+
+impl<'de, 'a, A: de::EnumAccess<'de>> de::EnumAccess<'de> for EnumAccess<'a, A> {
+    type Error = A::Error;
+    type Variant = VariantAccess<'a, A::Variant>;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<(S::Value, Self::Variant), A::Error> {
+        let ctx = self.ctx;
+        let (value, variant) = self.data.variant_seed(Seed { seed, ctx })?;
+        Ok((value, VariantAccess { variant, ctx }))
+    }
+}
+
+/// Wraps the value of an enum variant.
+struct VariantAccess<'a, A> {
+    variant: A,
+    ctx: Context<'a>,
+}
+
+impl<'de, 'a, A: de::VariantAccess<'de>> de::VariantAccess<'de> for VariantAccess<'a, A> {
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), A::Error> {
+        self.variant.unit_variant()
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<S::Value, A::Error> {
+        self.variant.newtype_variant_seed(Seed {
+            seed,
+            ctx: self.ctx,
+        })
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, A::Error> {
+        self.variant.tuple_variant(
+            len,
+            Visitor {
+                visitor,
+                ctx: self.ctx,
+            },
+        )
+    }
+
+    fn struct_variant<V: de::Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, A::Error> {
+        self.variant.struct_variant(
+            fields,
+            Visitor {
+                visitor,
+                ctx: self.ctx,
+            },
+        )
+    }
+}
@@ -0,0 +1,262 @@
+//! Serde (de)serialization of DAG-JSON.
+//!
+//! Any type implementing `Serialize` can be encoded to DAG-JSON with [`to_vec`] or
+//! [`to_writer`] and any type implementing `Deserialize` can be decoded with [`from_slice`] or
+//! [`from_reader`]. Wrap a value in [`Json`] to use it with [`DagJsonCodec`], for example to
+//! create a block from it.
+//!
+//! Links serialized through [`libipld_core::serde::cid`] or the
+//! [`Link`](libipld_core::link::Link) type are encoded as `{"/": "<cid>"}`, bytes serialized with
+//! `serialize_bytes` as `{"/": {"bytes": "<base64>"}}`. Map keys must be strings and are sorted
+//! by their bytes, so the output of the default codec matches the encoding of an equivalent
+//! [`Ipld`](libipld_core::ipld::Ipld).
+use crate::DagJsonCodec;
+use libipld_core::codec::{Decode, Encode};
+use libipld_core::error::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::{Read, Seek, Write};
+
+mod de;
+mod ser;
+
+pub use ser::Serializer;
+
+/// Serializes a value to a writer.
+pub fn to_writer<W: Write, T: Serialize + ?Sized>(writer: W, value: &T) -> Result<()> {
+    value.serialize(&mut Serializer::new(writer))?;
+    Ok(())
+}
+
+/// Serializes a value to a byte vector.
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    to_writer(&mut bytes, value)?;
+    Ok(bytes)
+}
+
+/// Deserializes a value from a reader.
+pub fn from_reader<R: Read, T: DeserializeOwned>(reader: R) -> Result<T> {
+    de::from_reader(DagJsonCodec, reader)
+}
+
+/// Deserializes a value from a byte slice.
+pub fn from_slice<T: DeserializeOwned>(slice: &[u8]) -> Result<T> {
+    from_reader(slice)
+}
+
+/// Encodes and decodes any serde type with [`DagJsonCodec`].
+///
+/// ```
+/// # use libipld_core::codec::Codec;
+/// # use libipld_json::{serde::Json, DagJsonCodec};
+/// let bytes = DagJsonCodec.encode(&Json(vec!["a", "b"])).unwrap();
+/// assert_eq!(bytes, br#"["a","b"]"#);
+/// let Json(list): Json<Vec<String>> = DagJsonCodec.decode(&bytes).unwrap();
+/// assert_eq!(list, ["a", "b"]);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Json<T>(pub T);
+
+impl<T: Serialize> Encode<DagJsonCodec> for Json<T> {
+    fn encode<W: Write>(&self, c: DagJsonCodec, w: &mut W) -> Result<()> {
+        self.0.serialize(&mut Serializer::with_codec(w, c))?;
+        Ok(())
+    }
+}
+
+impl<T: DeserializeOwned> Decode<DagJsonCodec> for Json<T> {
+    fn decode<R: Read + Seek>(c: DagJsonCodec, r: &mut R) -> Result<Self> {
+        de::from_reader(c, r).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libipld_core::cid::Cid;
+    use libipld_core::codec::Codec;
+    use libipld_core::error::{Error, JsonError};
+    use libipld_core::ipld::Ipld;
+    use libipld_core::link::Link;
+    use libipld_core::multihash::{Code, MultihashDigest};
+    use libipld_macro::ipld;
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct Person {
+        name: String,
+        age: u8,
+        score: f64,
+        #[serde(with = "bytes")]
+        avatar: Vec<u8>,
+        #[serde(with = "libipld_core::serde::cid")]
+        profile: Cid,
+        friends: Vec<Link<Person>>,
+        shape: Shape,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Empty,
+        Circle(u32),
+        Rect { w: u32, h: u32 },
+    }
+
+    /// Serializes a `Vec<u8>` as bytes instead of a list of integers.
+    mod bytes {
+        use serde::de::{self, Deserializer, Visitor};
+        use serde::Serializer;
+        use std::fmt;
+
+        pub fn serialize<S: Serializer>(bytes: &[u8], s: S) -> Result<S::Ok, S::Error> {
+            s.serialize_bytes(bytes)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+            d.deserialize_byte_buf(BytesVisitor)
+        }
+
+        struct BytesVisitor;
+
+        impl<'de> Visitor<'de> for BytesVisitor {
+            type Value = Vec<u8>;
+
+            fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt.write_str("bytes")
+            }
+
+            fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Vec<u8>, E> {
+                Ok(bytes.to_vec())
+            }
+        }
+    }
+
+    fn cid(data: &[u8]) -> Cid {
+        Cid::new_v1(0x0129, Code::Blake3_256.digest(data))
+    }
+
+    fn person() -> Person {
+        Person {
+            name: "Hello World!".into(),
+            age: 52,
+            score: 1.5,
+            avatar: b"hello".to_vec(),
+            profile: cid(b"profile"),
+            friends: vec![Link::new(cid(b"friend"))],
+            shape: Shape::Rect { w: 1, h: 2 },
+        }
+    }
+
+    #[test]
+    fn test_struct_roundtrip() {
+        let person = person();
+        let bytes = to_vec(&person).unwrap();
+        let person2: Person = from_slice(&bytes).unwrap();
+        assert_eq!(person, person2);
+
+        let ipld: Ipld = DagJsonCodec.decode(&bytes).unwrap();
+        let expected = ipld!({
+            "name": "Hello World!",
+            "age": 52,
+            "score": 1.5,
+            "avatar": b"hello".to_vec(),
+            "profile": cid(b"profile"),
+            "friends": [cid(b"friend")],
+            "shape": { "Rect": { "w": 1, "h": 2 } },
+        });
+        assert_eq!(ipld, expected);
+        assert_eq!(bytes, DagJsonCodec.encode(&expected).unwrap());
+    }
+
+    #[test]
+    fn test_codec() {
+        let person = person();
+        let bytes = DagJsonCodec.encode(&Json(&person)).unwrap();
+        assert_eq!(bytes, to_vec(&person).unwrap());
+        let Json(person2) = DagJsonCodec.decode::<Json<Person>>(&bytes).unwrap();
+        assert_eq!(person, person2);
+
+        let mut refs = std::collections::HashSet::new();
+        DagJsonCodec
+            .references::<Ipld, _>(&bytes, &mut refs)
+            .unwrap();
+        assert!(refs.contains(&cid(b"profile")));
+        assert!(refs.contains(&cid(b"friend")));
+    }
+
+    #[test]
+    fn test_enum_roundtrip() {
+        for shape in [Shape::Empty, Shape::Circle(2), Shape::Rect { w: 1, h: 2 }] {
+            let bytes = to_vec(&shape).unwrap();
+            assert_eq!(from_slice::<Shape>(&bytes).unwrap(), shape);
+        }
+        assert_eq!(to_vec(&Shape::Empty).unwrap(), br#""Empty""#);
+        assert_eq!(to_vec(&Shape::Circle(2)).unwrap(), br#"{"Circle":2}"#);
+    }
+
+    #[test]
+    fn test_ipld_interop() {
+        let ipld = ipld!({
+            "number": 1,
+            "negative": -5,
+            "list": [true, null, false],
+            "bytes": vec![0, 1, 2, 3],
+            "map": { "float": 0.5, "string": "hello", "/": "not a link" },
+            "link": cid(b"link"),
+        });
+        let bytes = to_vec(&ipld).unwrap();
+        assert_eq!(bytes, DagJsonCodec.encode(&ipld).unwrap());
+        let ipld2: Ipld = from_slice(&bytes).unwrap();
+        assert_eq!(ipld, ipld2);
+    }
+
+    #[test]
+    fn test_canonical_encoding() {
+        let mut map = BTreeMap::new();
+        map.insert("b", 2);
+        map.insert("aa", 1);
+        assert_eq!(to_vec(&map).unwrap(), br#"{"aa":1,"b":2}"#);
+        assert_eq!(to_vec(&1.0f64).unwrap(), b"1.0");
+        assert!(to_vec(&f64::NAN).is_err());
+        assert!(to_vec(&f64::INFINITY).is_err());
+
+        let mut map = BTreeMap::new();
+        map.insert(1u8, true);
+        assert!(to_vec(&map).is_err());
+
+        let max = (1u64 << 53) - 1;
+        let canonical = DagJsonCodec::canonical();
+        assert!(canonical.encode(&Json(max)).is_ok());
+        assert!(canonical.encode(&Json(max + 1)).is_err());
+        assert!(DagJsonCodec.encode(&Json(max + 1)).is_ok());
+        assert!(to_vec(&u128::MAX).is_err());
+        assert!(canonical.encode(&Json(i128::MIN)).is_err());
+        assert!(canonical.encode(&Json(i64::MIN)).is_err());
+
+        let mut map = BTreeMap::new();
+        map.insert("/", "not a link");
+        assert!(to_vec(&map).is_err());
+        map.insert("other", "a");
+        assert!(to_vec(&map).is_ok());
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        // trailing data
+        assert!(from_slice::<u8>(b"1 2").is_err());
+        // bytes are not a link
+        assert!(from_slice::<Link<Ipld>>(br#"{"/":{"bytes":"aGVsbG8"}}"#).is_err());
+        assert!(matches!(
+            from_slice::<Vec<Link<Ipld>>>(b"[\n{\"/\": \"bafy\"}]"),
+            Err(Error::Json(JsonError::InvalidCid { line: 2, .. }))
+        ));
+
+        let json = format!(r#"{{"/":"{}"}}"#, base64::encode(cid(b"link").to_bytes()));
+        assert!(from_slice::<Link<Ipld>>(json.as_bytes()).is_err());
+        let legacy = DagJsonCodec.with_legacy_links(true);
+        let Json(link) = legacy.decode::<Json<Link<Ipld>>>(json.as_bytes()).unwrap();
+        assert_eq!(*link, cid(b"link"));
+    }
+}
@@ -0,0 +1,452 @@
+//! DAG-JSON serializer.
+use crate::codec::{BYTES_KEY, LINK_KEY, MAX_SAFE_INTEGER, RESERVED_KEY};
+use crate::DagJsonCodec;
+use core::convert::TryFrom;
+use libipld_core::cid::Cid;
+use libipld_core::error::SerdeError;
+use libipld_core::ipld::Ipld;
+use libipld_core::serde::{to_ipld, CID_SERDE_PRIVATE_IDENTIFIER};
+use serde::ser::{self, Error as _, Serialize};
+use std::io::Write;
+
+/// Serde serializer writing DAG-JSON.
+pub struct Serializer<W> {
+    writer: W,
+    codec: DagJsonCodec,
+}
+
+impl<W: Write> Serializer<W> {
+    /// Creates a new serializer writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self::with_codec(writer, DagJsonCodec)
+    }
+
+    /// Creates a new serializer writing to `writer` with the options of `codec`.
+    pub fn with_codec(writer: W, codec: DagJsonCodec) -> Self {
+        Self { writer, codec }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), SerdeError> {
+        self.writer.write_all(buf).map_err(SerdeError::custom)
+    }
+
+    /// Writes a string or number with `serde_json`.
+    fn write_json<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        serde_json::to_writer(&mut self.writer, value).map_err(SerdeError::custom)
+    }
+
+    fn write_integer(&mut self, value: i128) -> Result<(), SerdeError> {
+        if !(Ipld::INTEGER_MIN..=Ipld::INTEGER_MAX).contains(&value) {
+            return Err(SerdeError::custom(format!(
+                "integer {} is out of range",
+                value
+            )));
+        }
+        if self.codec.is_canonical() && !(-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(&value) {
+            return Err(SerdeError::custom(format!(
+                "integer {} is outside of the safe range",
+                value
+            )));
+        }
+        self.write_json(&value)
+    }
+
+    fn write_link(&mut self, bytes: &[u8]) -> Result<(), SerdeError> {
+        let cid = Cid::try_from(bytes).map_err(SerdeError::custom)?;
+        self.write_all(b"{")?;
+        self.write_key(LINK_KEY)?;
+        self.write_json(&cid.to_string())?;
+        self.write_all(b"}")
+    }
+
+    fn write_key(&mut self, key: &str) -> Result<(), SerdeError> {
+        self.write_json(key)?;
+        self.write_all(b":")
+    }
+
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>, SerdeError> {
+        let mut buf = Vec::new();
+        value.serialize(&mut Serializer::with_codec(&mut buf, self.codec))?;
+        Ok(buf)
+    }
+}
+
+impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    type SerializeSeq = SerializeSeq<'a, W>;
+    type SerializeTuple = SerializeSeq<'a, W>;
+    type SerializeTupleStruct = SerializeSeq<'a, W>;
+    type SerializeTupleVariant = SerializeSeq<'a, W>;
+    type SerializeMap = SerializeMap<'a, W>;
+    type SerializeStruct = SerializeMap<'a, W>;
+    type SerializeStructVariant = SerializeMap<'a, W>;
+
+    fn serialize_bool(self, value: bool) -> Result<(), SerdeError> {
+        self.write_all(if value { b"true" } else { b"false" })
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<(), SerdeError> {
+        self.write_integer(value.into())
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<(), SerdeError> {
+        self.write_integer(value.into())
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<(), SerdeError> {
+        self.write_integer(value.into())
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<(), SerdeError> {
+        self.write_integer(value.into())
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<(), SerdeError> {
+        self.write_integer(value)
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<(), SerdeError> {
+        self.write_integer(value.into())
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<(), SerdeError> {
+        self.write_integer(value.into())
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<(), SerdeError> {
+        self.write_integer(value.into())
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<(), SerdeError> {
+        self.write_integer(value.into())
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<(), SerdeError> {
+        let value = i128::try_from(value).map_err(SerdeError::custom)?;
+        self.write_integer(value)
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<(), SerdeError> {
+        self.serialize_f64(value.into())
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<(), SerdeError> {
+        // serde_json silently encodes them as null
+        if !value.is_finite() {
+            return Err(SerdeError::custom(format!("float {} is not finite", value)));
+        }
+        self.write_json(&value)
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), SerdeError> {
+        self.serialize_str(value.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<(), SerdeError> {
+        self.write_json(value)
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), SerdeError> {
+        self.write_all(b"{")?;
+        self.write_key(LINK_KEY)?;
+        self.write_all(b"{")?;
+        self.write_key(BYTES_KEY)?;
+        self.write_json(&base64::encode_config(value, base64::STANDARD_NO_PAD))?;
+        self.write_all(b"}}")
+    }
+
+    fn serialize_none(self) -> Result<(), SerdeError> {
+        self.write_all(b"null")
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), SerdeError> {
+        self.serialize_none()
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), SerdeError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), SerdeError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        if name == CID_SERDE_PRIVATE_IDENTIFIER {
+            match to_ipld(value)? {
+                Ipld::Bytes(bytes) => self.write_link(&bytes),
+                _ => Err(SerdeError::custom("expected cid bytes")),
+            }
+        } else {
+            value.serialize(self)
+        }
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.write_all(b"{")?;
+        self.write_key(variant)?;
+        value.serialize(&mut *self)?;
+        self.write_all(b"}")
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, SerdeError> {
+        self.write_all(b"[")?;
+        Ok(SerializeSeq {
+            ser: self,
+            first: true,
+            end: b"]",
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerdeError> {
+        self.write_all(b"{")?;
+        self.write_key(variant)?;
+        self.write_all(b"[")?;
+        Ok(SerializeSeq {
+            ser: self,
+            first: true,
+            end: b"]}",
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, SerdeError> {
+        Ok(SerializeMap {
+            ser: self,
+            entries: Vec::new(),
+            next_key: None,
+            end: b"}",
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, SerdeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, SerdeError> {
+        self.write_all(b"{")?;
+        self.write_key(variant)?;
+        Ok(SerializeMap {
+            ser: self,
+            entries: Vec::new(),
+            next_key: None,
+            end: b"}}",
+        })
+    }
+}
+
+/// Serializes the elements of a sequence.
+#[doc(hidden)]
+pub struct SerializeSeq<'a, W> {
+    ser: &'a mut Serializer<W>,
+    first: bool,
+    end: &'static [u8],
+}
+
+impl<'a, W: Write> SerializeSeq<'a, W> {
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        if !self.first {
+            self.ser.write_all(b",")?;
+        }
+        self.first = false;
+        value.serialize(&mut *self.ser)
+    }
+
+    fn finish(self) -> Result<(), SerdeError> {
+        self.ser.write_all(self.end)
+    }
+}
+
+impl<'a, W: Write> ser::SerializeSeq for SerializeSeq<'a, W> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        self.finish()
+    }
+}
+
+impl<'a, W: Write> ser::SerializeTuple for SerializeSeq<'a, W> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        self.finish()
+    }
+}
+
+impl<'a, W: Write> ser::SerializeTupleStruct for SerializeSeq<'a, W> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        self.finish()
+    }
+}
+
+impl<'a, W: Write> ser::SerializeTupleVariant for SerializeSeq<'a, W> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        self.finish()
+    }
+}
+
+/// Serializes the entries of a map.
+///
+/// The entries are buffered so that they can be written sorted by their keys.
+#[doc(hidden)]
+pub struct SerializeMap<'a, W> {
+    ser: &'a mut Serializer<W>,
+    entries: Vec<(String, Vec<u8>)>,
+    next_key: Option<String>,
+    end: &'static [u8],
+}
+
+impl<'a, W: Write> ser::SerializeMap for SerializeMap<'a, W> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        match to_ipld(key)? {
+            Ipld::String(key) => {
+                self.next_key = Some(key);
+                Ok(())
+            }
+            _ => Err(SerdeError::custom("map keys must be strings")),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let key = self
+            .next_key
+            .take()
+            .ok_or_else(|| SerdeError::custom("serialize_value called before serialize_key"))?;
+        let value = self.ser.encode(value)?;
+        self.entries.push((key, value));
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<(), SerdeError> {
+        self.entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        if self.entries.windows(2).any(|w| w[0].0 == w[1].0) {
+            return Err(SerdeError::custom("duplicate map key"));
+        }
+        if self.entries.len() == 1 && self.entries[0].0 == LINK_KEY {
+            return Err(SerdeError::custom(RESERVED_KEY));
+        }
+        self.ser.write_all(b"{")?;
+        for (i, (key, value)) in self.entries.iter().enumerate() {
+            if i > 0 {
+                self.ser.write_all(b",")?;
+            }
+            self.ser.write_key(key)?;
+            self.ser.write_all(value)?;
+        }
+        self.ser.write_all(self.end)
+    }
+}
+
+impl<'a, W: Write> ser::SerializeStruct for SerializeMap<'a, W> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        ser::SerializeMap::serialize_entry(self, key, value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+impl<'a, W: Write> ser::SerializeStructVariant for SerializeMap<'a, W> {
+    type Ok = ();
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        ser::SerializeMap::serialize_entry(self, key, value)
+    }
+
+    fn end(self) -> Result<(), SerdeError> {
+        ser::SerializeMap::end(self)
+    }
+}
@@ -1,7 +1,8 @@
 use core::convert::{TryFrom, TryInto};
 use libipld_core::cid::Cid;
-use libipld_core::error::{Result, TypeError, TypeErrorType};
+use libipld_core::error::{Error, PbError, Result, TypeError, TypeErrorType};
 use libipld_core::ipld::Ipld;
+use libipld_core::ipld_arc::IpldArc;
 use std::collections::BTreeMap;
 
 mod dag_pb {
@@ -33,7 +34,8 @@ use prost::Message;
 impl PbNode {
     /// Deserializes a `PbNode` from bytes.
     pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
-        let proto: dag_pb::PbNode = dag_pb::PbNode::decode(bytes)?;
+        let proto: dag_pb::PbNode =
+            dag_pb::PbNode::decode(bytes).map_err(|err| PbError::Protobuf(err.to_string()))?;
         let data = proto.data.into_boxed_slice();
         let mut links = Vec::new();
         for link in proto.links {
@@ -69,6 +71,99 @@ impl PbNode {
     }
 }
 
+/// Extracts the cids of the links of an encoded node without decoding it.
+pub fn references<E: Extend<Cid>>(mut bytes: &[u8], set: &mut E) -> Result<()> {
+    while let Some((number, field)) = next_field(&mut bytes)? {
+        match (number, field) {
+            (1, Field::Bytes(_)) => {}
+            (2, Field::Bytes(mut link)) => {
+                let mut hash = None;
+                while let Some((number, field)) = next_field(&mut link)? {
+                    match (number, field) {
+                        (1, Field::Bytes(bytes)) => hash = Some(bytes),
+                        (2, Field::Bytes(_)) | (3, Field::Varint) => {}
+                        (1..=3, _) => return Err(unexpected_wire_type(number)),
+                        _ => {}
+                    }
+                }
+                let cid = Cid::try_from(hash.unwrap_or_default())?;
+                set.extend(core::iter::once(cid));
+            }
+            (1..=2, _) => return Err(unexpected_wire_type(number)),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The value of a protobuf field.
+enum Field<'a> {
+    Varint,
+    Fixed,
+    Bytes(&'a [u8]),
+}
+
+fn truncated() -> Error {
+    PbError::Protobuf("unexpected end of input".into()).into()
+}
+
+fn unexpected_wire_type(number: u64) -> Error {
+    PbError::Protobuf(format!("unexpected wire type of field {}", number)).into()
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (byte, rest) = bytes.split_first().ok_or_else(truncated)?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(PbError::Protobuf("varint is too long".into()).into())
+}
+
+fn read_bytes<'a>(bytes: &mut &'a [u8], len: u64) -> Result<&'a [u8]> {
+    let len = usize::try_from(len).map_err(|_| truncated())?;
+    if len > bytes.len() {
+        return Err(truncated());
+    }
+    let (value, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(value)
+}
+
+/// Reads the number and the value of the next field of a message.
+fn next_field<'a>(bytes: &mut &'a [u8]) -> Result<Option<(u64, Field<'a>)>> {
+    if bytes.is_empty() {
+        return Ok(None);
+    }
+    let key = read_varint(bytes)?;
+    let field = match key & 0x7 {
+        0 => {
+            read_varint(bytes)?;
+            Field::Varint
+        }
+        1 => {
+            read_bytes(bytes, 8)?;
+            Field::Fixed
+        }
+        2 => {
+            let len = read_varint(bytes)?;
+            Field::Bytes(read_bytes(bytes, len)?)
+        }
+        5 => {
+            read_bytes(bytes, 4)?;
+            Field::Fixed
+        }
+        wire_type => {
+            return Err(PbError::Protobuf(format!("invalid wire type {}", wire_type)).into())
+        }
+    };
+    Ok(Some((key >> 3, field)))
+}
+
 impl From<PbNode> for Ipld {
     fn from(node: PbNode) -> Self {
         let mut map = BTreeMap::<String, Ipld>::new();
@@ -136,3 +231,47 @@ impl TryFrom<&Ipld> for PbLink {
         Ok(PbLink { cid, name, size })
     }
 }
+
+impl TryFrom<&IpldArc> for PbNode {
+    type Error = TypeError;
+
+    fn try_from(ipld: &IpldArc) -> core::result::Result<PbNode, Self::Error> {
+        let links = if let IpldArc::List(links) = ipld.get("Links")? {
+            links
+                .iter()
+                .map(|link| link.try_into())
+                .collect::<Result<_, _>>()?
+        } else {
+            return Err(TypeError::new(TypeErrorType::List, ipld));
+        };
+        let data = if let IpldArc::Bytes(data) = ipld.get("Data")? {
+            Box::from(&data[..])
+        } else {
+            return Err(TypeError::new(TypeErrorType::Bytes, ipld));
+        };
+        Ok(PbNode { links, data })
+    }
+}
+
+impl TryFrom<&IpldArc> for PbLink {
+    type Error = TypeError;
+
+    fn try_from(ipld: &IpldArc) -> core::result::Result<PbLink, Self::Error> {
+        let cid = if let IpldArc::Link(cid) = ipld.get("Hash")? {
+            *cid
+        } else {
+            return Err(TypeError::new(TypeErrorType::Link, ipld));
+        };
+        let name = if let IpldArc::String(name) = ipld.get("Name")? {
+            name.to_string()
+        } else {
+            return Err(TypeError::new(TypeErrorType::String, ipld));
+        };
+        let size = if let IpldArc::Integer(size) = ipld.get("Tsize")? {
+            *size as u64
+        } else {
+            return Err(TypeError::new(TypeErrorType::Integer, ipld));
+        };
+        Ok(PbLink { cid, name, size })
+    }
+}
@@ -8,6 +8,7 @@ use libipld_core::cid::Cid;
 use libipld_core::codec::{Codec, Decode, Encode, References};
 use libipld_core::error::{Result, UnsupportedCodec};
 use libipld_core::ipld::Ipld;
+use libipld_core::ipld_arc::IpldArc;
 use std::io::{Read, Seek, Write};
 
 mod codec;
@@ -41,6 +42,15 @@ impl Encode<DagPbCodec> for Ipld {
     }
 }
 
+impl Encode<DagPbCodec> for IpldArc {
+    fn encode<W: Write>(&self, _: DagPbCodec, w: &mut W) -> Result<()> {
+        let pb_node: PbNode = self.try_into()?;
+        let bytes = pb_node.into_bytes();
+        w.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
 impl Decode<DagPbCodec> for Ipld {
     fn decode<R: Read + Seek>(_: DagPbCodec, r: &mut R) -> Result<Self> {
         let mut bytes = Vec::new();
@@ -51,12 +61,13 @@ impl Decode<DagPbCodec> for Ipld {
 
 impl References<DagPbCodec> for Ipld {
     fn references<R: Read + Seek, E: Extend<Cid>>(
-        c: DagPbCodec,
+        _: DagPbCodec,
         r: &mut R,
         set: &mut E,
     ) -> Result<()> {
-        Ipld::decode(c, r)?.references(set);
-        Ok(())
+        let mut bytes = Vec::new();
+        r.read_to_end(&mut bytes)?;
+        codec::references(&bytes, set)
     }
 }
 
@@ -86,4 +97,33 @@ mod tests {
         let data2 = DagPbCodec.decode(&bytes).unwrap();
         assert_eq!(data, data2);
     }
+
+    #[test]
+    fn test_references() {
+        let cids: Vec<_> = (0..3u8)
+            .map(|i| Cid::new_v1(0x55, Code::Sha2_256.digest(&[i])))
+            .collect();
+        let node = PbNode {
+            links: cids
+                .iter()
+                .map(|cid| PbLink {
+                    cid: *cid,
+                    name: "link".into(),
+                    size: 300,
+                })
+                .collect(),
+            data: b"data".to_vec().into_boxed_slice(),
+        };
+        let bytes = node.into_bytes();
+        let mut refs = Vec::new();
+        DagPbCodec.references::<Ipld, _>(&bytes, &mut refs).unwrap();
+        assert_eq!(refs, cids);
+
+        // truncated input
+        let res = DagPbCodec.references::<Ipld, _>(&bytes[..bytes.len() - 1], &mut refs);
+        assert!(res.is_err());
+        // link without a hash
+        let res = DagPbCodec.references::<Ipld, _>(&[0x12, 0x02, 0x18, 0x01], &mut refs);
+        assert!(res.is_err());
+    }
 }
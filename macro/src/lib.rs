@@ -38,6 +38,9 @@
 /// });
 /// ```
 ///
+/// Integers are limited to the data model range, so `i128` values can't be interpolated. Convert
+/// them with `Ipld::try_from` first.
+///
 /// Trailing commas are allowed inside both arrays and objects.
 ///
 /// ```edition2018
@@ -192,4 +192,22 @@ mod tests {
         let res = client.get(&cid, Some(&tmp)).unwrap();
         assert_eq!(res, 42);
     }
+
+    #[async_std::test]
+    async fn test_cache_ipld_arc() {
+        use crate::IpldArc;
+
+        let store = MemStore::<DefaultParams>::default();
+        let cache: IpldCache<_, DagCborCodec, IpldArc> =
+            IpldCache::new(store, DagCborCodec, Code::Blake3_256, 1);
+        let ipld = IpldArc::from(crate::ipld!({ "list": [1, 2, 3] }));
+        let cid = cache.insert(ipld.clone(), None).unwrap();
+        let a = cache.get(&cid, None).unwrap();
+        let b = cache.get(&cid, None).unwrap();
+        assert_eq!(a, ipld);
+        match (a.get("list").unwrap(), b.get("list").unwrap()) {
+            (IpldArc::List(a), IpldArc::List(b)) => assert!(std::sync::Arc::ptr_eq(a, b)),
+            _ => unreachable!(),
+        }
+    }
 }
@@ -5,6 +5,7 @@ use crate::cid::Cid;
 use crate::codec::{Codec, Decode, Encode, References};
 use crate::error::{Result, UnsupportedCodec};
 use crate::ipld::Ipld;
+use crate::ipld_arc::IpldArc;
 #[cfg(feature = "dag-json")]
 use crate::json::DagJsonCodec;
 #[cfg(feature = "dag-pb")]
@@ -76,7 +77,7 @@ impl From<DagCborCodec> for IpldCodec {
 #[cfg(feature = "dag-cbor")]
 impl From<IpldCodec> for DagCborCodec {
     fn from(_: IpldCodec) -> Self {
-        Self
+        Self::default()
     }
 }
 
@@ -90,7 +91,7 @@ impl From<DagJsonCodec> for IpldCodec {
 #[cfg(feature = "dag-json")]
 impl From<IpldCodec> for DagJsonCodec {
     fn from(_: IpldCodec) -> Self {
-        Self
+        Self::default()
     }
 }
 
@@ -125,6 +126,21 @@ impl Encode<IpldCodec> for Ipld {
     }
 }
 
+impl Encode<IpldCodec> for IpldArc {
+    fn encode<W: Write>(&self, c: IpldCodec, w: &mut W) -> Result<()> {
+        match c {
+            IpldCodec::Raw => self.encode(RawCodec, w)?,
+            #[cfg(feature = "dag-cbor")]
+            IpldCodec::DagCbor => self.encode(DagCborCodec, w)?,
+            #[cfg(feature = "dag-json")]
+            IpldCodec::DagJson => self.encode(DagJsonCodec, w)?,
+            #[cfg(feature = "dag-pb")]
+            IpldCodec::DagPb => self.encode(DagPbCodec, w)?,
+        };
+        Ok(())
+    }
+}
+
 impl Decode<IpldCodec> for Ipld {
     fn decode<R: Read + Seek>(c: IpldCodec, r: &mut R) -> Result<Self> {
         Ok(match c {
@@ -8,6 +8,7 @@ pub mod codec_impl;
 pub mod mem;
 pub mod path;
 pub mod prelude;
+pub mod schema;
 pub mod store;
 
 #[cfg(feature = "dag-cbor")]
@@ -26,6 +27,8 @@ pub use cid::Cid;
 pub use codec_impl::IpldCodec;
 pub use error::Result;
 pub use ipld::Ipld;
+pub use ipld_arc::IpldArc;
+pub use ipld_ref::IpldRef;
 pub use link::Link;
 pub use multihash::Multihash;
 pub use path::{DagPath, Path};
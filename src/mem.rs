@@ -194,10 +194,10 @@ impl<S: StoreParams> LocalStore<S> {
             }
             n -= 1;
             remove.push(*atime);
-            self.cid.remove(&id);
-            self.data.remove(&id);
-            self.refs.remove(&id);
-            self.atime.remove(&id);
+            self.cid.remove(id);
+            self.data.remove(id);
+            self.refs.remove(id);
+            self.atime.remove(id);
         }
         for atime in remove {
             self.sorted.remove(&atime);
@@ -451,6 +451,7 @@ where
 mod tests {
     use super::*;
     use crate::cbor::DagCborCodec;
+    use crate::error::Error;
     use crate::multihash::Code;
     use crate::store::DefaultParams;
     use crate::{alias, ipld};
@@ -555,6 +556,16 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_not_found() {
+        let mut store = SharedStore::<DefaultParams>::new(GlobalStore::default(), 5);
+        let a = create_block(&ipld!({ "a": 0 }));
+        match store.fetch(a.cid()) {
+            Err(Error::BlockNotFound(BlockNotFound(cid))) => assert_eq!(&cid, a.cid()),
+            res => panic!("expected block not found, got {:?}", res),
+        }
+    }
+
     #[test]
     fn test_sync() -> Result<()> {
         let network = GlobalStore::default();
@@ -1,4 +1,4 @@
 //! Prelude
 pub use crate::cache::Cache;
-pub use crate::codec::{Codec, Decode, Encode, References};
+pub use crate::codec::{Codec, Decode, DecodeRef, Encode, References};
 pub use crate::store::{Store, StoreParams};
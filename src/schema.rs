@@ -0,0 +1,391 @@
+//! IPLD schemas.
+//!
+//! Parses the [IPLD Schema DSL](https://ipld.io/docs/schemas/) and validates [`Ipld`] against
+//! the parsed types.
+//!
+//! ```
+//! use libipld::ipld;
+//! use libipld::schema::Schema;
+//!
+//! let schema = Schema::parse(r#"
+//!     type Person struct {
+//!         name String
+//!         age optional Int
+//!         friends [&Person] (rename "f")
+//!     }
+//! "#).unwrap();
+//! schema.validate(&ipld!({ "name": "Alice", "f": [] }), "Person").unwrap();
+//! let err = schema.validate(&ipld!({ "name": 42, "f": [] }), "Person").unwrap_err();
+//! assert_eq!(err.to_string(), "expected string but found int at `/name`");
+//! ```
+use crate::ipld::Ipld;
+use crate::path::Path;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+mod ast;
+pub mod codegen;
+mod parse;
+mod validate;
+
+pub use ast::*;
+pub use libipld_core::schema::{is_named, type_arg_name, IpldSchema, MemberKind, SchemaDefs};
+
+/// A parsed schema.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Schema {
+    types: Vec<(String, Type)>,
+    index: BTreeMap<String, usize>,
+}
+
+impl Schema {
+    /// Parses a schema from the IPLD Schema DSL.
+    ///
+    /// Fails if the text isn't valid or references types that aren't defined.
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        parse::parse(text)
+    }
+
+    /// Returns the defined types in declaration order.
+    pub fn types(&self) -> impl Iterator<Item = (&str, &Type)> {
+        self.types.iter().map(|(name, ty)| (name.as_str(), ty))
+    }
+
+    /// Returns the type with `name`.
+    ///
+    /// The prelude types `Bool`, `String`, `Bytes`, `Int`, `Float`, `Any` and `Link` are always
+    /// defined.
+    pub fn get(&self, name: &str) -> Option<&Type> {
+        if let Some(i) = self.index.get(name) {
+            return Some(&self.types[*i].1);
+        }
+        prelude(name)
+    }
+
+    /// Validates `ipld` against the type with `name`.
+    ///
+    /// Returns the first mismatch with the path to the offending value.
+    pub fn validate(&self, ipld: &Ipld, name: &str) -> Result<(), ValidationError> {
+        validate::Validator::new(self).validate_named(ipld, name)
+    }
+
+    fn insert(&mut self, name: String, ty: Type) -> bool {
+        if self.get(&name).is_some() {
+            return false;
+        }
+        self.index.insert(name.clone(), self.types.len());
+        self.types.push((name, ty));
+        true
+    }
+}
+
+/// Returns the prelude type with `name`.
+fn prelude(name: &str) -> Option<&'static Type> {
+    static BOOL: Type = Type::Bool;
+    static STRING: Type = Type::String;
+    static BYTES: Type = Type::Bytes;
+    static INT: Type = Type::Int;
+    static FLOAT: Type = Type::Float;
+    static ANY: Type = Type::Any;
+    static LINK: Type = Type::Link(None);
+    Some(match name {
+        "Bool" => &BOOL,
+        "String" => &STRING,
+        "Bytes" => &BYTES,
+        "Int" => &INT,
+        "Float" => &FLOAT,
+        "Any" => &ANY,
+        "Link" => &LINK,
+        _ => return None,
+    })
+}
+
+/// The schema text is invalid.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[error("{msg} at line {line} column {column}")]
+pub struct ParseError {
+    /// Message.
+    pub msg: String,
+    /// Line.
+    pub line: usize,
+    /// Column.
+    pub column: usize,
+}
+
+/// A value doesn't match its type.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[error("{msg} at `/{path}`")]
+pub struct ValidationError {
+    /// Path to the value.
+    pub path: Path,
+    /// Message.
+    pub msg: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cid::Cid;
+    use crate::ipld;
+
+    fn error(schema: &str) -> (String, usize, usize) {
+        let err = Schema::parse(schema).unwrap_err();
+        (err.msg, err.line, err.column)
+    }
+
+    fn invalid(schema: &Schema, ipld: Ipld, name: &str) -> String {
+        schema.validate(&ipld, name).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parse_types() {
+        let schema = Schema::parse(
+            r#"
+            # a comment
+            type Name string
+            type Names [nullable Name]
+            type Scores {String:Int} representation listpairs
+            type Parent &Node
+            type Alias = Name
+            type Node struct {
+                name Name
+                parent optional nullable Parent (rename "p")
+                kind Kind (implicit "leaf")
+            } representation map
+            type Kind enum {
+                | Leaf ("leaf")
+                | Branch ("branch")
+            }
+            type Empty unit representation emptymap
+            "#,
+        )
+        .unwrap();
+        let names: Vec<_> = schema.types().map(|(name, _)| name).collect();
+        assert_eq!(
+            names,
+            ["Name", "Names", "Scores", "Parent", "Alias", "Node", "Kind", "Empty"]
+        );
+        assert_eq!(schema.get("Name"), Some(&Type::String));
+        assert_eq!(schema.get("Link"), Some(&Type::Link(None)));
+        assert_eq!(schema.get("Parent"), Some(&Type::Link(Some("Node".into()))));
+        assert_eq!(schema.get("Alias"), Some(&Type::Copy("Name".into())));
+        match schema.get("Scores") {
+            Some(Type::Map(map)) => assert_eq!(map.repr, MapRepr::ListPairs),
+            ty => panic!("unexpected {:?}", ty),
+        }
+        match schema.get("Node") {
+            Some(Type::Struct(s)) => {
+                let parent = &s.fields[1];
+                assert!(parent.optional && parent.nullable);
+                assert_eq!(parent.key(), "p");
+                assert_eq!(s.fields[2].implicit, Some(Ipld::String("leaf".into())));
+            }
+            ty => panic!("unexpected {:?}", ty),
+        }
+        assert!(schema.get("Missing").is_none());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            error("type Foo struct {\n  bar Bar\n}"),
+            ("unknown type `Bar`".into(), 1, 6)
+        );
+        assert_eq!(
+            error("type Foo int\ntype Foo string"),
+            ("type `Foo` is defined twice".into(), 2, 6)
+        );
+        assert_eq!(
+            error("type Foo union {\n  | Int int\n}"),
+            ("unions require a representation".into(), 1, 10)
+        );
+        assert_eq!(
+            error("type Foo union {\n  | Int foo\n} representation kinded"),
+            ("expected kind but found `foo`".into(), 2, 9)
+        );
+        assert_eq!(
+            error("type Foo struct {} representation stringjoin"),
+            ("missing parameter `join`".into(), 1, 45)
+        );
+        assert_eq!(
+            error("type A = B\ntype B = A"),
+            ("type `A` is an alias of itself".into(), 1, 6)
+        );
+        assert_eq!(
+            error("type Foo {String:Int"),
+            ("unexpected end of schema".into(), 1, 21)
+        );
+        assert_eq!(
+            error("type Foo $"),
+            ("unexpected character `$`".into(), 1, 10)
+        );
+    }
+
+    #[test]
+    fn validate_structs() {
+        let schema = Schema::parse(
+            r#"
+            type Point struct {
+                x Int
+                y Int
+                label optional String
+            } representation tuple
+            type Pair struct {
+                a String
+                b String
+            } representation stringjoin { join ":" }
+            type Opts struct {
+                a String
+                b optional String
+            } representation stringpairs { innerDelim "=" entryDelim "," }
+            type Flags struct {
+                on Bool (implicit false)
+            }
+            "#,
+        )
+        .unwrap();
+        schema.validate(&ipld!([1, 2]), "Point").unwrap();
+        schema.validate(&ipld!([1, 2, "p"]), "Point").unwrap();
+        assert_eq!(
+            invalid(&schema, ipld!([1]), "Point"),
+            "expected 3 values but found 1 at `/`"
+        );
+        assert_eq!(
+            invalid(&schema, ipld!([1, "2"]), "Point"),
+            "expected int but found string at `/1`"
+        );
+        schema.validate(&ipld!("x:y"), "Pair").unwrap();
+        assert_eq!(
+            invalid(&schema, ipld!("x"), "Pair"),
+            "expected 2 values but found 1 at `/`"
+        );
+        schema.validate(&ipld!("a=1,b=2"), "Opts").unwrap();
+        schema.validate(&ipld!("a=1"), "Opts").unwrap();
+        assert_eq!(
+            invalid(&schema, ipld!("b=2"), "Opts"),
+            "missing key `a` at `/`"
+        );
+        schema.validate(&ipld!({}), "Flags").unwrap();
+        assert_eq!(
+            invalid(&schema, ipld!({ "on": true, "off": false }), "Flags"),
+            "unexpected key `off` at `/`"
+        );
+    }
+
+    #[test]
+    fn validate_unions() {
+        let schema = Schema::parse(
+            r#"
+            type Keyed union {
+                | Int "int"
+                | String "str"
+            } representation keyed
+            type Kinded union {
+                | Int int
+                | &Any link
+                | Keyed map
+            } representation kinded
+            type Envelope union {
+                | Int "int"
+            } representation envelope { discriminantKey "tag" contentKey "content" }
+            type Inline union {
+                | Foo "foo"
+            } representation inline { discriminantKey "tag" }
+            type Foo struct {
+                a Int
+            }
+            type Prefixed union {
+                | String "s:"
+            } representation stringprefix
+            type Tagged union {
+                | Bytes "0A"
+            } representation bytesprefix
+            "#,
+        )
+        .unwrap();
+        let cid = Cid::default();
+        schema.validate(&ipld!({ "int": 1 }), "Keyed").unwrap();
+        assert_eq!(
+            invalid(&schema, ipld!({ "str": 1 }), "Keyed"),
+            "expected string but found int at `/str`"
+        );
+        assert_eq!(
+            invalid(&schema, ipld!({ "float": 1.0 }), "Keyed"),
+            "unknown discriminant `float` at `/`"
+        );
+        schema.validate(&ipld!(1), "Kinded").unwrap();
+        schema.validate(&Ipld::Link(cid), "Kinded").unwrap();
+        schema.validate(&ipld!({ "str": "s" }), "Kinded").unwrap();
+        assert_eq!(
+            invalid(&schema, ipld!("s"), "Kinded"),
+            "expected int or link or map but found string at `/`"
+        );
+        schema
+            .validate(&ipld!({ "tag": "int", "content": 1 }), "Envelope")
+            .unwrap();
+        assert_eq!(
+            invalid(&schema, ipld!({ "tag": "int" }), "Envelope"),
+            "missing key `content` at `/`"
+        );
+        schema
+            .validate(&ipld!({ "tag": "foo", "a": 1 }), "Inline")
+            .unwrap();
+        assert_eq!(
+            invalid(&schema, ipld!({ "tag": "foo", "a": "1" }), "Inline"),
+            "expected int but found string at `/a`"
+        );
+        schema.validate(&ipld!("s:1"), "Prefixed").unwrap();
+        assert_eq!(
+            invalid(&schema, ipld!("i:1"), "Prefixed"),
+            "unknown prefix at `/`"
+        );
+        schema
+            .validate(&Ipld::Bytes(vec![0x0a, 1]), "Tagged")
+            .unwrap();
+    }
+
+    #[test]
+    fn validate_collections() {
+        let schema = Schema::parse(
+            r#"
+            type Color enum {
+                | Red
+                | Green
+            }
+            type Code enum {
+                | Ok ("0")
+                | Err ("1")
+            } representation int
+            type Colors {String:[nullable Color]}
+            type Pairs {String:Code} representation stringpairs { innerDelim ":" entryDelim ";" }
+            type Links [&Colors]
+            type Empty unit representation null
+            "#,
+        )
+        .unwrap();
+        schema
+            .validate(&ipld!({ "a": ["Red", null], "b": [] }), "Colors")
+            .unwrap();
+        assert_eq!(
+            invalid(&schema, ipld!({ "a": ["Red", "Blue"] }), "Colors"),
+            "unknown enum member \"Blue\" at `/a/1`"
+        );
+        assert_eq!(
+            invalid(&schema, ipld!({ "a": "Red" }), "Colors"),
+            "expected list but found string at `/a`"
+        );
+        schema.validate(&ipld!(1), "Code").unwrap();
+        assert_eq!(
+            invalid(&schema, ipld!("a:0;b:1"), "Pairs"),
+            "expected int but found string at `/a`"
+        );
+        schema
+            .validate(&Ipld::List(vec![Ipld::Link(Cid::default())]), "Links")
+            .unwrap();
+        schema.validate(&Ipld::Null, "Empty").unwrap();
+        assert_eq!(
+            invalid(&schema, Ipld::Null, "Missing"),
+            "unknown type `Missing` at `/`"
+        );
+    }
+}
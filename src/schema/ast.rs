@@ -0,0 +1,238 @@
+//! Schema types.
+use crate::ipld::Ipld;
+
+/// A type definition.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Type {
+    /// Boolean type.
+    Bool,
+    /// String type.
+    String,
+    /// Bytes type.
+    Bytes,
+    /// Integer type.
+    Int,
+    /// Float type.
+    Float,
+    /// Any data model value.
+    Any,
+    /// Link to a value of the named type, to any value if `None`.
+    Link(Option<String>),
+    /// Map type.
+    Map(MapType),
+    /// List type.
+    List(ListType),
+    /// Struct type.
+    Struct(StructType),
+    /// Union type.
+    Union(UnionType),
+    /// Enum type.
+    Enum(EnumType),
+    /// Type without data.
+    Unit(UnitRepr),
+    /// Alias for the named type.
+    Copy(String),
+}
+
+/// Type of a struct field or of the values of a map or list.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeExpr {
+    /// Named type.
+    Named(String),
+    /// Link to a value of the named type, to any value if `None`.
+    Link(Option<String>),
+    /// Anonymous map type.
+    Map(Box<MapType>),
+    /// Anonymous list type.
+    List(Box<ListType>),
+}
+
+/// Map type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapType {
+    /// Name of the key type.
+    pub key: String,
+    /// Value type.
+    pub value: TypeExpr,
+    /// Values may be null.
+    pub nullable: bool,
+    /// Representation.
+    pub repr: MapRepr,
+}
+
+/// Representation of a map.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MapRepr {
+    /// Represented as a map.
+    Map,
+    /// Represented as a string of delimited key value pairs.
+    StringPairs {
+        /// Delimiter between a key and its value.
+        inner_delim: String,
+        /// Delimiter between entries.
+        entry_delim: String,
+    },
+    /// Represented as a list of key value lists.
+    ListPairs,
+}
+
+/// List type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListType {
+    /// Value type.
+    pub value: TypeExpr,
+    /// Values may be null.
+    pub nullable: bool,
+}
+
+/// Struct type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StructType {
+    /// Fields in declaration order.
+    pub fields: Vec<StructField>,
+    /// Representation.
+    pub repr: StructRepr,
+}
+
+/// Field of a struct.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StructField {
+    /// Field name.
+    pub name: String,
+    /// Field type.
+    pub ty: TypeExpr,
+    /// The field may be absent.
+    pub optional: bool,
+    /// The field may be null.
+    pub nullable: bool,
+    /// Key of the field in the map representation.
+    pub rename: Option<String>,
+    /// Value of the field when it's absent in the map representation.
+    pub implicit: Option<Ipld>,
+}
+
+impl StructField {
+    /// Returns the key of the field in the map representation.
+    pub fn key(&self) -> &str {
+        self.rename.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// Representation of a struct.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StructRepr {
+    /// Represented as a map from field keys to values.
+    Map,
+    /// Represented as a list of values.
+    Tuple {
+        /// Order of the fields, declaration order if `None`.
+        field_order: Option<Vec<String>>,
+    },
+    /// Represented as a string of joined values.
+    StringJoin {
+        /// Delimiter between values.
+        join: String,
+    },
+    /// Represented as a string of delimited key value pairs.
+    StringPairs {
+        /// Delimiter between a key and its value.
+        inner_delim: String,
+        /// Delimiter between entries.
+        entry_delim: String,
+    },
+    /// Represented as a list of key value lists.
+    ListPairs,
+}
+
+/// Union type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnionType {
+    /// Members in declaration order.
+    pub members: Vec<UnionMember>,
+    /// Representation.
+    pub repr: UnionRepr,
+}
+
+/// Member of a union.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnionMember {
+    /// Type of the member, either a named type or a link.
+    pub ty: TypeExpr,
+    /// Discriminant of the member.
+    ///
+    /// It's a key for keyed, envelope and inline unions, a kind for kinded unions, a prefix for
+    /// stringprefix unions and a hex encoded prefix for bytesprefix unions.
+    pub discriminant: String,
+}
+
+/// Representation of a union.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UnionRepr {
+    /// Represented as a map with a single entry from the discriminant to the value.
+    Keyed,
+    /// Represented as the value, discriminated by its kind.
+    Kinded,
+    /// Represented as a map with the discriminant and the value under fixed keys.
+    Envelope {
+        /// Key of the discriminant.
+        discriminant_key: String,
+        /// Key of the value.
+        content_key: String,
+    },
+    /// Represented as the map of a struct with an additional discriminant entry.
+    Inline {
+        /// Key of the discriminant.
+        discriminant_key: String,
+    },
+    /// Represented as the string of the value prefixed by the discriminant.
+    StringPrefix,
+    /// Represented as the bytes of the value prefixed by the discriminant.
+    BytesPrefix,
+}
+
+/// Enum type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnumType {
+    /// Members in declaration order.
+    pub members: Vec<EnumMember>,
+    /// Representation.
+    pub repr: EnumRepr,
+}
+
+/// Member of an enum.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnumMember {
+    /// Member name.
+    pub name: String,
+    /// Representation of the member, the name if `None`.
+    pub value: Option<String>,
+}
+
+impl EnumMember {
+    /// Returns the representation of the member.
+    pub fn value(&self) -> &str {
+        self.value.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// Representation of an enum.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnumRepr {
+    /// Represented as a string.
+    String,
+    /// Represented as an integer.
+    Int,
+}
+
+/// Representation of a unit type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnitRepr {
+    /// Represented as null.
+    Null,
+    /// Represented as true.
+    True,
+    /// Represented as false.
+    False,
+    /// Represented as an empty map.
+    EmptyMap,
+}
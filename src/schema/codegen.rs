@@ -0,0 +1,442 @@
+//! Rust code generation from IPLD schemas.
+//!
+//! Every schema type becomes a Rust type deriving `DagCbor` with the matching `#[ipld(repr)]`
+//! attributes, so the generated code needs the `dag-cbor` and `derive` features. Types without
+//! data, like scalars, maps, lists and links, become type aliases. Representations the derive
+//! doesn't support are reported as [`CodegenError::Unsupported`].
+//!
+//! The generator is meant to be called from a build script:
+//!
+//! ```no_run
+//! // build.rs
+//! let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("schema.rs");
+//! libipld::schema::codegen::compile("schema.ipldsch", out).unwrap();
+//! println!("cargo:rerun-if-changed=schema.ipldsch");
+//! ```
+//!
+//! The generated file is then included with `include!(concat!(env!("OUT_DIR"), "/schema.rs"));`.
+use super::ast::*;
+use super::{ParseError, Schema};
+use crate::ipld::Ipld;
+use std::fmt::Write;
+use thiserror::Error;
+
+/// Code generation failed.
+#[derive(Debug, Error)]
+pub enum CodegenError {
+    /// The schema file couldn't be read or the output couldn't be written.
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    /// The schema is invalid.
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+    /// The type can't be expressed with the derive.
+    #[error("type `{name}` is unsupported: {msg}")]
+    Unsupported {
+        /// Name of the type.
+        name: String,
+        /// Reason.
+        msg: String,
+    },
+}
+
+/// Generates Rust types for all types of `schema` in declaration order.
+pub fn generate(schema: &Schema) -> Result<String, CodegenError> {
+    let mut out = String::from("// Generated from an IPLD schema, do not edit.\n");
+    for (name, ty) in schema.types() {
+        let gen = Generator { schema, name };
+        out.push('\n');
+        out.push_str(&gen.type_def(ty)?);
+    }
+    Ok(out)
+}
+
+/// Generates Rust types from the schema file at `input` and writes them to `output`.
+pub fn compile(
+    input: impl AsRef<std::path::Path>,
+    output: impl AsRef<std::path::Path>,
+) -> Result<(), CodegenError> {
+    let text = std::fs::read_to_string(input)?;
+    let code = generate(&Schema::parse(&text)?)?;
+    std::fs::write(output, code)?;
+    Ok(())
+}
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+    "where", "while", "yield",
+];
+
+/// Converts a field name to a snake case identifier.
+fn field_ident(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut ident = String::with_capacity(name.len());
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let prev = if i > 0 { Some(chars[i - 1]) } else { None };
+            let next = chars.get(i + 1);
+            let boundary = match prev {
+                Some(prev) if prev.is_lowercase() || prev.is_ascii_digit() => true,
+                Some(prev) if prev.is_uppercase() => next.is_some_and(|c| c.is_lowercase()),
+                _ => false,
+            };
+            if boundary {
+                ident.push('_');
+            }
+            ident.extend(c.to_lowercase());
+        } else if c.is_alphanumeric() {
+            ident.push(*c);
+        } else {
+            ident.push('_');
+        }
+    }
+    match ident.as_str() {
+        "crate" | "self" | "super" => ident.push('_'),
+        ident_str if KEYWORDS.contains(&ident_str) => ident.insert_str(0, "r#"),
+        _ => {}
+    }
+    ident
+}
+
+/// Returns the attribute renaming `ident` to `key` if they differ.
+fn rename(ident: &str, key: &str) -> String {
+    if ident == key {
+        String::new()
+    } else {
+        format!("#[ipld(rename = {:?})]\n    ", key)
+    }
+}
+
+/// Generates the definition of a single type.
+struct Generator<'a> {
+    schema: &'a Schema,
+    name: &'a str,
+}
+
+impl<'a> Generator<'a> {
+    fn unsupported<T>(&self, msg: impl Into<String>) -> Result<T, CodegenError> {
+        Err(CodegenError::Unsupported {
+            name: self.name.to_string(),
+            msg: msg.into(),
+        })
+    }
+
+    /// Returns the Rust type of the named type.
+    fn named(&self, name: &str) -> String {
+        match name {
+            "Bool" => "bool".into(),
+            "String" => "String".into(),
+            "Bytes" => "Box<[u8]>".into(),
+            "Int" => "i64".into(),
+            "Float" => "f64".into(),
+            "Any" => "libipld::Ipld".into(),
+            "Link" => "libipld::Cid".into(),
+            _ => name.into(),
+        }
+    }
+
+    fn link(&self, name: Option<&str>) -> String {
+        match name {
+            Some(name) => format!("libipld::Link<{}>", self.named(name)),
+            None => "libipld::Cid".into(),
+        }
+    }
+
+    fn map(&self, map: &MapType) -> Result<String, CodegenError> {
+        if map.repr != MapRepr::Map {
+            return self.unsupported("maps must use the map representation");
+        }
+        let value = self.value(&map.value, map.nullable)?;
+        Ok(format!(
+            "std::collections::BTreeMap<{}, {}>",
+            self.named(&map.key),
+            value
+        ))
+    }
+
+    fn list(&self, list: &ListType) -> Result<String, CodegenError> {
+        Ok(format!("Vec<{}>", self.value(&list.value, list.nullable)?))
+    }
+
+    fn expr(&self, expr: &TypeExpr) -> Result<String, CodegenError> {
+        match expr {
+            TypeExpr::Named(name) => Ok(self.named(name)),
+            TypeExpr::Link(name) => Ok(self.link(name.as_deref())),
+            TypeExpr::Map(map) => self.map(map),
+            TypeExpr::List(list) => self.list(list),
+        }
+    }
+
+    fn value(&self, expr: &TypeExpr, nullable: bool) -> Result<String, CodegenError> {
+        let ty = self.expr(expr)?;
+        Ok(if nullable {
+            format!("Option<{}>", ty)
+        } else {
+            ty
+        })
+    }
+
+    /// Follows aliases of the named type.
+    fn resolve(&self, mut name: &'a str) -> (&'a str, Option<&'a Type>) {
+        loop {
+            match self.schema.get(name) {
+                Some(Type::Copy(target)) => name = target,
+                ty => return (name, ty),
+            }
+        }
+    }
+
+    /// Returns the expression of an implicit field value.
+    fn implicit(&self, field: &StructField, value: &Ipld) -> Result<String, CodegenError> {
+        let resolved = match &field.ty {
+            TypeExpr::Named(name) => self.resolve(name),
+            _ => ("", None),
+        };
+        match (resolved, value) {
+            ((_, Some(Type::String)), Ipld::String(s)) => Ok(format!("String::from({:?})", s)),
+            ((_, Some(Type::Int)), Ipld::Integer(i)) => Ok(i.to_string()),
+            ((_, Some(Type::Bool)), Ipld::Bool(b)) => Ok(b.to_string()),
+            ((name, Some(Type::Enum(e))), _) => {
+                let member = e.members.iter().find(|member| match (e.repr, value) {
+                    (EnumRepr::String, Ipld::String(s)) => member.value() == s,
+                    (EnumRepr::Int, Ipld::Integer(i)) => member.value().parse() == Ok(*i),
+                    _ => false,
+                });
+                match member {
+                    Some(member) => Ok(format!("{}::{}", name, member.name)),
+                    None => self.unsupported(format!("invalid implicit value of `{}`", field.name)),
+                }
+            }
+            _ => self.unsupported(format!("implicit value of `{}`", field.name)),
+        }
+    }
+
+    fn type_def(&self, ty: &Type) -> Result<String, CodegenError> {
+        let name = self.name;
+        let alias = |ty: String| Ok(format!("pub type {} = {};\n", name, ty));
+        match ty {
+            Type::Bool => alias(self.named("Bool")),
+            Type::String => alias(self.named("String")),
+            Type::Bytes => alias(self.named("Bytes")),
+            Type::Int => alias(self.named("Int")),
+            Type::Float => alias(self.named("Float")),
+            Type::Any => alias(self.named("Any")),
+            Type::Link(target) => alias(self.link(target.as_deref())),
+            Type::Map(map) => alias(self.map(map)?),
+            Type::List(list) => alias(self.list(list)?),
+            Type::Copy(target) => alias(self.named(target)),
+            Type::Struct(s) => self.struct_def(s),
+            Type::Union(u) => self.union_def(u),
+            Type::Enum(e) => self.enum_def(e),
+            Type::Unit(UnitRepr::Null) => Ok(format!(
+                "#[derive(Clone, Debug, PartialEq, libipld::DagCbor)]\n\
+                 #[ipld(repr = \"null\")]\n\
+                 pub struct {};\n",
+                name
+            )),
+            Type::Unit(_) => self.unsupported("units must use the null representation"),
+        }
+    }
+
+    fn struct_def(&self, s: &StructType) -> Result<String, CodegenError> {
+        let (repr, fields): (_, Vec<_>) = match &s.repr {
+            StructRepr::Map => ("map", s.fields.iter().collect()),
+            StructRepr::Tuple { field_order } => {
+                if let Some(field) = s.fields.iter().find(|field| field.optional) {
+                    return self.unsupported(format!("tuple field `{}` is optional", field.name));
+                }
+                let fields = match field_order {
+                    Some(order) => order
+                        .iter()
+                        .filter_map(|name| s.fields.iter().find(|field| &field.name == name))
+                        .collect(),
+                    None => s.fields.iter().collect(),
+                };
+                ("tuple", fields)
+            }
+            _ => return self.unsupported("structs must use the map or tuple representation"),
+        };
+        let mut out = String::new();
+        writeln!(out, "#[derive(Clone, Debug, PartialEq, libipld::DagCbor)]").unwrap();
+        writeln!(out, "#[ipld(repr = {:?})]", repr).unwrap();
+        writeln!(out, "pub struct {} {{", self.name).unwrap();
+        for field in fields {
+            let ident = field_ident(&field.name);
+            let ty = self.value(&field.ty, field.optional || field.nullable)?;
+            let mut attrs = rename(&ident, field.key());
+            if repr == "map" {
+                let default = match (&field.implicit, field.optional) {
+                    (_, true) => Some("None".to_string()),
+                    (Some(implicit), false) => Some(self.implicit(field, implicit)?),
+                    (None, false) => None,
+                };
+                if let Some(default) = default {
+                    write!(attrs, "#[ipld(default = {})]\n    ", default).unwrap();
+                }
+            }
+            writeln!(out, "    {}pub {}: {},", attrs, ident, ty).unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+        Ok(out)
+    }
+
+    fn union_def(&self, u: &UnionType) -> Result<String, CodegenError> {
+        let repr = match u.repr {
+            UnionRepr::Keyed => "keyed",
+            UnionRepr::Kinded => "kinded",
+            _ => return self.unsupported("unions must use the keyed or kinded representation"),
+        };
+        let mut variants: Vec<String> = Vec::with_capacity(u.members.len());
+        let mut out = String::new();
+        writeln!(out, "#[derive(Clone, Debug, PartialEq, libipld::DagCbor)]").unwrap();
+        writeln!(out, "#[ipld(repr = {:?})]", repr).unwrap();
+        writeln!(out, "pub enum {} {{", self.name).unwrap();
+        for member in &u.members {
+            let variant = match &member.ty {
+                TypeExpr::Named(name) => name.clone(),
+                TypeExpr::Link(Some(name)) => format!("{}Link", name),
+                _ => "Link".to_string(),
+            };
+            if variants.contains(&variant) {
+                return self.unsupported(format!("variant `{}` is defined twice", variant));
+            }
+            let attrs = if u.repr == UnionRepr::Keyed {
+                rename(&variant, &member.discriminant)
+            } else {
+                String::new()
+            };
+            let ty = self.expr(&member.ty)?;
+            writeln!(
+                out,
+                "    {}#[ipld(repr = \"value\")]\n    {}({}),",
+                attrs, variant, ty
+            )
+            .unwrap();
+            variants.push(variant);
+        }
+        writeln!(out, "}}").unwrap();
+        Ok(out)
+    }
+
+    fn enum_def(&self, e: &EnumType) -> Result<String, CodegenError> {
+        let repr = match e.repr {
+            EnumRepr::String => "string",
+            EnumRepr::Int => "int",
+        };
+        let mut out = String::new();
+        writeln!(
+            out,
+            "#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, libipld::DagCbor)]"
+        )
+        .unwrap();
+        writeln!(out, "#[ipld(repr = {:?})]", repr).unwrap();
+        writeln!(out, "pub enum {} {{", self.name).unwrap();
+        for member in &e.members {
+            match e.repr {
+                EnumRepr::String => {
+                    let attrs = rename(&member.name, member.value());
+                    writeln!(out, "    {}{},", attrs, member.name).unwrap();
+                }
+                EnumRepr::Int => {
+                    let value: i128 = member.value().parse().unwrap();
+                    if value < 0 || value > i64::MAX as i128 {
+                        return self
+                            .unsupported(format!("value of `{}` is out of range", member.name));
+                    }
+                    writeln!(out, "    {} = {},", member.name, value).unwrap();
+                }
+            }
+        }
+        writeln!(out, "}}").unwrap();
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gen(schema: &str) -> Result<String, CodegenError> {
+        generate(&Schema::parse(schema).unwrap())
+    }
+
+    #[test]
+    fn field_idents() {
+        assert_eq!(field_ident("name"), "name");
+        assert_eq!(field_ident("fieldName"), "field_name");
+        assert_eq!(field_ident("HTTPServer"), "http_server");
+        assert_eq!(field_ident("v2Name"), "v2_name");
+        assert_eq!(field_ident("foo-bar"), "foo_bar");
+        assert_eq!(field_ident("type"), "r#type");
+        assert_eq!(field_ident("self"), "self_");
+    }
+
+    #[test]
+    fn generate_struct() {
+        let code = gen(r#"
+            type Kind enum {
+                | Leaf ("leaf")
+                | Branch
+            }
+            type Node struct {
+                name String
+                parentNode optional &Node
+                type Kind (implicit "leaf")
+                children [nullable Node]
+            }
+            "#)
+        .unwrap();
+        assert_eq!(
+            code,
+            r#"// Generated from an IPLD schema, do not edit.
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, libipld::DagCbor)]
+#[ipld(repr = "string")]
+pub enum Kind {
+    #[ipld(rename = "leaf")]
+    Leaf,
+    Branch,
+}
+
+#[derive(Clone, Debug, PartialEq, libipld::DagCbor)]
+#[ipld(repr = "map")]
+pub struct Node {
+    pub name: String,
+    #[ipld(rename = "parentNode")]
+    #[ipld(default = None)]
+    pub parent_node: Option<libipld::Link<Node>>,
+    #[ipld(rename = "type")]
+    #[ipld(default = Kind::Leaf)]
+    pub r#type: Kind,
+    pub children: Vec<Option<Node>>,
+}
+"#
+        );
+    }
+
+    #[test]
+    fn generate_unsupported() {
+        let err = gen(r#"type Foo struct { a String } representation stringjoin { join ":" }"#)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "type `Foo` is unsupported: structs must use the map or tuple representation"
+        );
+        let err = gen(r#"type Foo union { | String "s" } representation envelope {
+                discriminantKey "tag" contentKey "content"
+            }"#)
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "type `Foo` is unsupported: unions must use the keyed or kinded representation"
+        );
+        let err = gen("type Foo struct { a optional Int } representation tuple").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "type `Foo` is unsupported: tuple field `a` is optional"
+        );
+    }
+}
@@ -0,0 +1,659 @@
+//! Schema DSL parser.
+use super::ast::*;
+use super::{ParseError, Schema};
+use crate::ipld::Ipld;
+use std::collections::BTreeSet;
+
+const KINDS: &[&str] = &[
+    "null", "bool", "int", "float", "string", "bytes", "list", "map", "link",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Int(i128),
+    Punct(char),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Ident(ident) => write!(f, "`{}`", ident),
+            Self::Str(s) => write!(f, "{:?}", s),
+            Self::Int(i) => write!(f, "`{}`", i),
+            Self::Punct(c) => write!(f, "`{}`", c),
+        }
+    }
+}
+
+/// Position of a token.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Pos {
+    line: usize,
+    column: usize,
+}
+
+impl Pos {
+    fn error<T>(self, msg: impl Into<String>) -> Result<T, ParseError> {
+        Err(ParseError {
+            msg: msg.into(),
+            line: self.line,
+            column: self.column,
+        })
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, Pos)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut pos = Pos { line: 1, column: 1 };
+    let next = |chars: &mut std::iter::Peekable<std::str::Chars>, pos: &mut Pos| {
+        let c = chars.next();
+        if c == Some('\n') {
+            pos.line += 1;
+            pos.column = 1;
+        } else {
+            pos.column += 1;
+        }
+        c
+    };
+    while let Some(&c) = chars.peek() {
+        let start = pos;
+        if c.is_whitespace() || c == ',' {
+            next(&mut chars, &mut pos);
+        } else if c == '#' {
+            while !matches!(next(&mut chars, &mut pos), Some('\n') | None) {}
+        } else if c.is_alphabetic() || c == '_' {
+            let mut ident = String::new();
+            while let Some(&c) = chars.peek() {
+                if !c.is_alphanumeric() && c != '_' {
+                    break;
+                }
+                ident.push(c);
+                next(&mut chars, &mut pos);
+            }
+            tokens.push((Token::Ident(ident), start));
+        } else if c.is_ascii_digit() || c == '-' {
+            let mut int = String::new();
+            int.push(c);
+            next(&mut chars, &mut pos);
+            while let Some(&c) = chars.peek() {
+                if !c.is_ascii_digit() {
+                    break;
+                }
+                int.push(c);
+                next(&mut chars, &mut pos);
+            }
+            match int.parse() {
+                Ok(int) => tokens.push((Token::Int(int), start)),
+                Err(_) => return start.error(format!("invalid integer `{}`", int)),
+            }
+        } else if c == '"' {
+            next(&mut chars, &mut pos);
+            let mut s = String::new();
+            loop {
+                match next(&mut chars, &mut pos) {
+                    Some('"') => break,
+                    Some('\\') => match next(&mut chars, &mut pos) {
+                        Some('n') => s.push('\n'),
+                        Some('t') => s.push('\t'),
+                        Some(c) => s.push(c),
+                        None => return start.error("unterminated string"),
+                    },
+                    Some(c) => s.push(c),
+                    None => return start.error("unterminated string"),
+                }
+            }
+            tokens.push((Token::Str(s), start));
+        } else if "{}[]()|&=:".contains(c) {
+            next(&mut chars, &mut pos);
+            tokens.push((Token::Punct(c), start));
+        } else {
+            return start.error(format!("unexpected character `{}`", c));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Value of a representation parameter.
+enum Param {
+    Token(Token),
+    List(Vec<String>),
+}
+
+/// Parameters of a representation, like `{ join ":" }`.
+struct Params(Vec<(String, Param, Pos)>, Pos);
+
+impl Params {
+    fn take(&mut self, key: &str) -> Option<(Param, Pos)> {
+        let i = self.0.iter().position(|(k, _, _)| k == key)?;
+        let (_, param, pos) = self.0.remove(i);
+        Some((param, pos))
+    }
+
+    fn string(&mut self, key: &str) -> Result<String, ParseError> {
+        match self.take(key) {
+            Some((Param::Token(Token::Str(s)), _)) => Ok(s),
+            Some((Param::Token(token), pos)) => {
+                pos.error(format!("expected string but found {}", token))
+            }
+            Some((Param::List(_), pos)) => pos.error("expected string but found list"),
+            None => self.1.error(format!("missing parameter `{}`", key)),
+        }
+    }
+
+    fn list(&mut self, key: &str) -> Result<Option<(Vec<String>, Pos)>, ParseError> {
+        match self.take(key) {
+            Some((Param::List(list), pos)) => Ok(Some((list, pos))),
+            Some((Param::Token(token), pos)) => {
+                pos.error(format!("expected list but found {}", token))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn finish(self) -> Result<(), ParseError> {
+        if let Some((key, _, pos)) = self.0.first() {
+            return pos.error(format!("unexpected parameter `{}`", key));
+        }
+        Ok(())
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, Pos)>,
+    pos: usize,
+    end: Pos,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn peek_pos(&self) -> Pos {
+        self.tokens
+            .get(self.pos)
+            .map(|(_, pos)| *pos)
+            .unwrap_or(self.end)
+    }
+
+    fn next(&mut self) -> Result<(Token, Pos), ParseError> {
+        match self.tokens.get(self.pos) {
+            Some(token) => {
+                self.pos += 1;
+                Ok(token.clone())
+            }
+            None => self.end.error("unexpected end of schema"),
+        }
+    }
+
+    fn peek_ident(&self, ident: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(i)) if i == ident)
+    }
+
+    fn peek_punct(&self, c: char) -> bool {
+        self.peek() == Some(&Token::Punct(c))
+    }
+
+    fn eat_ident(&mut self, ident: &str) -> bool {
+        let found = self.peek_ident(ident);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn eat_punct(&mut self, c: char) -> bool {
+        let found = self.peek_punct(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn ident(&mut self) -> Result<(String, Pos), ParseError> {
+        match self.next()? {
+            (Token::Ident(ident), pos) => Ok((ident, pos)),
+            (token, pos) => pos.error(format!("expected identifier but found {}", token)),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        match self.next()? {
+            (Token::Str(s), _) => Ok(s),
+            (token, pos) => pos.error(format!("expected string but found {}", token)),
+        }
+    }
+
+    fn punct(&mut self, c: char) -> Result<(), ParseError> {
+        match self.next()? {
+            (Token::Punct(p), _) if p == c => Ok(()),
+            (token, pos) => pos.error(format!("expected `{}` but found {}", c, token)),
+        }
+    }
+
+    /// Parses `representation <strategy>` if present.
+    fn repr(&mut self) -> Result<Option<(String, Pos)>, ParseError> {
+        if self.eat_ident("representation") {
+            Ok(Some(self.ident()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Parses the parameters of a representation if present.
+    fn params(&mut self) -> Result<Params, ParseError> {
+        let start = self.peek_pos();
+        let mut params = Vec::new();
+        if self.eat_punct('{') {
+            while !self.eat_punct('}') {
+                let (key, pos) = self.ident()?;
+                let value = if self.eat_punct('[') {
+                    let mut list = Vec::new();
+                    while !self.eat_punct(']') {
+                        list.push(self.string()?);
+                    }
+                    Param::List(list)
+                } else {
+                    Param::Token(self.next()?.0)
+                };
+                params.push((key, value, pos));
+            }
+        }
+        Ok(Params(params, start))
+    }
+
+    fn schema(&mut self) -> Result<(Schema, Vec<Pos>), ParseError> {
+        let mut schema = Schema::default();
+        let mut positions = Vec::new();
+        while self.peek().is_some() {
+            let (keyword, pos) = self.ident()?;
+            match keyword.as_str() {
+                "type" => {}
+                "advanced" => return pos.error("advanced layouts are unsupported"),
+                _ => return pos.error(format!("expected `type` but found `{}`", keyword)),
+            }
+            let (name, pos) = self.ident()?;
+            let ty = self.type_def()?;
+            if !schema.insert(name.clone(), ty) {
+                return pos.error(format!("type `{}` is defined twice", name));
+            }
+            positions.push(pos);
+        }
+        Ok((schema, positions))
+    }
+
+    fn type_def(&mut self) -> Result<Type, ParseError> {
+        let (token, pos) = self.next()?;
+        let ty = match token {
+            Token::Punct('=') => Type::Copy(self.ident()?.0),
+            Token::Punct('&') => Type::Link(self.link()?),
+            Token::Punct('{') => Type::Map(self.map()?),
+            Token::Punct('[') => {
+                let list = self.list()?;
+                if let Some((repr, pos)) = self.repr()? {
+                    if repr != "list" {
+                        return pos.error(format!("unknown list representation `{}`", repr));
+                    }
+                }
+                Type::List(list)
+            }
+            Token::Ident(ident) => match ident.as_str() {
+                "bool" => Type::Bool,
+                "string" => Type::String,
+                "bytes" => Type::Bytes,
+                "int" => Type::Int,
+                "float" => Type::Float,
+                "any" => Type::Any,
+                "struct" => Type::Struct(self.struct_def()?),
+                "union" => Type::Union(self.union_def(pos)?),
+                "enum" => Type::Enum(self.enum_def()?),
+                "unit" => Type::Unit(self.unit_def(pos)?),
+                _ => return pos.error(format!("unknown type kind `{}`", ident)),
+            },
+            token => return pos.error(format!("expected type but found {}", token)),
+        };
+        Ok(ty)
+    }
+
+    fn type_expr(&mut self) -> Result<TypeExpr, ParseError> {
+        let (token, pos) = self.next()?;
+        Ok(match token {
+            Token::Ident(name) => TypeExpr::Named(name),
+            Token::Punct('&') => TypeExpr::Link(self.link()?),
+            Token::Punct('{') => {
+                let map = self.map_body()?;
+                TypeExpr::Map(Box::new(map))
+            }
+            Token::Punct('[') => TypeExpr::List(Box::new(self.list()?)),
+            token => return pos.error(format!("expected type but found {}", token)),
+        })
+    }
+
+    fn link(&mut self) -> Result<Option<String>, ParseError> {
+        let (name, _) = self.ident()?;
+        Ok(if name == "Any" { None } else { Some(name) })
+    }
+
+    fn map_body(&mut self) -> Result<MapType, ParseError> {
+        let (key, _) = self.ident()?;
+        self.punct(':')?;
+        let nullable = self.eat_ident("nullable");
+        let value = self.type_expr()?;
+        self.punct('}')?;
+        Ok(MapType {
+            key,
+            value,
+            nullable,
+            repr: MapRepr::Map,
+        })
+    }
+
+    fn map(&mut self) -> Result<MapType, ParseError> {
+        let mut map = self.map_body()?;
+        if let Some((repr, pos)) = self.repr()? {
+            map.repr = match repr.as_str() {
+                "map" => MapRepr::Map,
+                "stringpairs" => {
+                    let mut params = self.params()?;
+                    let repr = MapRepr::StringPairs {
+                        inner_delim: params.string("innerDelim")?,
+                        entry_delim: params.string("entryDelim")?,
+                    };
+                    params.finish()?;
+                    repr
+                }
+                "listpairs" => MapRepr::ListPairs,
+                _ => return pos.error(format!("unknown map representation `{}`", repr)),
+            };
+        }
+        Ok(map)
+    }
+
+    fn list(&mut self) -> Result<ListType, ParseError> {
+        let nullable = self.eat_ident("nullable");
+        let value = self.type_expr()?;
+        self.punct(']')?;
+        Ok(ListType { value, nullable })
+    }
+
+    fn struct_def(&mut self) -> Result<StructType, ParseError> {
+        self.punct('{')?;
+        let mut fields: Vec<StructField> = Vec::new();
+        while !self.eat_punct('}') {
+            let (name, pos) = self.ident()?;
+            if fields.iter().any(|field| field.name == name) {
+                return pos.error(format!("field `{}` is defined twice", name));
+            }
+            let optional = self.eat_ident("optional");
+            let nullable = self.eat_ident("nullable");
+            let ty = self.type_expr()?;
+            let mut field = StructField {
+                name,
+                ty,
+                optional,
+                nullable,
+                rename: None,
+                implicit: None,
+            };
+            if self.eat_punct('(') {
+                while !self.eat_punct(')') {
+                    let (key, pos) = self.ident()?;
+                    match key.as_str() {
+                        "rename" => field.rename = Some(self.string()?),
+                        "implicit" => field.implicit = Some(self.literal()?),
+                        _ => return pos.error(format!("unknown field parameter `{}`", key)),
+                    }
+                }
+            }
+            fields.push(field);
+        }
+        let repr = match self.repr()? {
+            None => StructRepr::Map,
+            Some((repr, pos)) => {
+                let mut params = self.params()?;
+                let repr = match repr.as_str() {
+                    "map" => StructRepr::Map,
+                    "tuple" => {
+                        let field_order = match params.list("fieldOrder")? {
+                            Some((order, pos)) => {
+                                let names: BTreeSet<_> = order.iter().collect();
+                                if names.len() != order.len()
+                                    || names.len() != fields.len()
+                                    || fields.iter().any(|field| !names.contains(&field.name))
+                                {
+                                    return pos.error("field order doesn't match the fields");
+                                }
+                                Some(order)
+                            }
+                            None => None,
+                        };
+                        StructRepr::Tuple { field_order }
+                    }
+                    "stringjoin" => StructRepr::StringJoin {
+                        join: params.string("join")?,
+                    },
+                    "stringpairs" => StructRepr::StringPairs {
+                        inner_delim: params.string("innerDelim")?,
+                        entry_delim: params.string("entryDelim")?,
+                    },
+                    "listpairs" => StructRepr::ListPairs,
+                    _ => return pos.error(format!("unknown struct representation `{}`", repr)),
+                };
+                params.finish()?;
+                repr
+            }
+        };
+        Ok(StructType { fields, repr })
+    }
+
+    fn literal(&mut self) -> Result<Ipld, ParseError> {
+        Ok(match self.next()? {
+            (Token::Str(s), _) => Ipld::String(s),
+            (Token::Int(i), _) => Ipld::Integer(i),
+            (Token::Ident(b), _) if b == "true" => Ipld::Bool(true),
+            (Token::Ident(b), _) if b == "false" => Ipld::Bool(false),
+            (token, pos) => return pos.error(format!("expected literal but found {}", token)),
+        })
+    }
+
+    fn union_def(&mut self, start: Pos) -> Result<UnionType, ParseError> {
+        self.punct('{')?;
+        let mut members = Vec::new();
+        while !self.eat_punct('}') {
+            self.punct('|')?;
+            let ty = if self.eat_punct('&') {
+                TypeExpr::Link(self.link()?)
+            } else {
+                TypeExpr::Named(self.ident()?.0)
+            };
+            let discriminant = self.next()?;
+            members.push((ty, discriminant));
+        }
+        let (repr, pos) = match self.repr()? {
+            Some(repr) => repr,
+            None => return start.error("unions require a representation"),
+        };
+        let mut params = self.params()?;
+        let repr = match repr.as_str() {
+            "keyed" => UnionRepr::Keyed,
+            "kinded" => UnionRepr::Kinded,
+            "envelope" => UnionRepr::Envelope {
+                discriminant_key: params.string("discriminantKey")?,
+                content_key: params.string("contentKey")?,
+            },
+            "inline" => UnionRepr::Inline {
+                discriminant_key: params.string("discriminantKey")?,
+            },
+            "stringprefix" => UnionRepr::StringPrefix,
+            "bytesprefix" => UnionRepr::BytesPrefix,
+            _ => return pos.error(format!("unknown union representation `{}`", repr)),
+        };
+        params.finish()?;
+        let mut seen = BTreeSet::new();
+        let members = members
+            .into_iter()
+            .map(|(ty, (token, pos))| {
+                let discriminant = match (&repr, token) {
+                    (UnionRepr::Kinded, Token::Ident(kind)) if KINDS.contains(&kind.as_str()) => {
+                        kind
+                    }
+                    (UnionRepr::Kinded, token) => {
+                        return pos.error(format!("expected kind but found {}", token))
+                    }
+                    (UnionRepr::BytesPrefix, Token::Str(hex)) => {
+                        if hex.is_empty()
+                            || hex.len() % 2 != 0
+                            || !hex.chars().all(|c| c.is_ascii_hexdigit())
+                        {
+                            return pos.error(format!("invalid hex prefix {:?}", hex));
+                        }
+                        hex.to_ascii_lowercase()
+                    }
+                    (_, Token::Str(s)) => s,
+                    (_, token) => return pos.error(format!("expected string but found {}", token)),
+                };
+                if !seen.insert(discriminant.clone()) {
+                    return pos.error(format!("discriminant {:?} is used twice", discriminant));
+                }
+                Ok(UnionMember { ty, discriminant })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(UnionType { members, repr })
+    }
+
+    fn enum_def(&mut self) -> Result<EnumType, ParseError> {
+        self.punct('{')?;
+        let mut members = Vec::new();
+        while !self.eat_punct('}') {
+            self.punct('|')?;
+            let (name, pos) = self.ident()?;
+            let value = if self.eat_punct('(') {
+                let value = self.string()?;
+                self.punct(')')?;
+                Some(value)
+            } else {
+                None
+            };
+            members.push((EnumMember { name, value }, pos));
+        }
+        let repr = match self.repr()? {
+            None => EnumRepr::String,
+            Some((repr, _)) if repr == "string" => EnumRepr::String,
+            Some((repr, _)) if repr == "int" => EnumRepr::Int,
+            Some((repr, pos)) => {
+                return pos.error(format!("unknown enum representation `{}`", repr))
+            }
+        };
+        let mut seen = BTreeSet::new();
+        let members = members
+            .into_iter()
+            .map(|(member, pos)| {
+                if repr == EnumRepr::Int && member.value().parse::<i128>().is_err() {
+                    return pos.error(format!("expected integer value for `{}`", member.name));
+                }
+                if !seen.insert(member.value().to_string()) {
+                    return pos.error(format!("value {:?} is used twice", member.value()));
+                }
+                Ok(member)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(EnumType { members, repr })
+    }
+
+    fn unit_def(&mut self, start: Pos) -> Result<UnitRepr, ParseError> {
+        match self.repr()? {
+            Some((repr, pos)) => Ok(match repr.as_str() {
+                "null" => UnitRepr::Null,
+                "true" => UnitRepr::True,
+                "false" => UnitRepr::False,
+                "emptymap" => UnitRepr::EmptyMap,
+                _ => return pos.error(format!("unknown unit representation `{}`", repr)),
+            }),
+            None => start.error("units require a representation"),
+        }
+    }
+}
+
+/// Checks that all referenced types are defined and that aliases aren't cyclic.
+fn check(schema: &Schema, positions: &[Pos]) -> Result<(), ParseError> {
+    fn check_name(schema: &Schema, name: &str, pos: Pos) -> Result<(), ParseError> {
+        if schema.get(name).is_none() {
+            return pos.error(format!("unknown type `{}`", name));
+        }
+        Ok(())
+    }
+
+    fn check_expr(schema: &Schema, expr: &TypeExpr, pos: Pos) -> Result<(), ParseError> {
+        match expr {
+            TypeExpr::Named(name) | TypeExpr::Link(Some(name)) => check_name(schema, name, pos),
+            TypeExpr::Link(None) => Ok(()),
+            TypeExpr::Map(map) => {
+                check_name(schema, &map.key, pos)?;
+                check_expr(schema, &map.value, pos)
+            }
+            TypeExpr::List(list) => check_expr(schema, &list.value, pos),
+        }
+    }
+
+    for ((name, ty), pos) in schema.types().zip(positions) {
+        let pos = *pos;
+        match ty {
+            Type::Link(Some(name)) => check_name(schema, name, pos)?,
+            Type::Map(map) => {
+                check_name(schema, &map.key, pos)?;
+                check_expr(schema, &map.value, pos)?;
+            }
+            Type::List(list) => check_expr(schema, &list.value, pos)?,
+            Type::Struct(s) => {
+                for field in &s.fields {
+                    check_expr(schema, &field.ty, pos)?;
+                }
+            }
+            Type::Union(u) => {
+                for member in &u.members {
+                    check_expr(schema, &member.ty, pos)?;
+                }
+            }
+            Type::Copy(target) => {
+                let mut seen = BTreeSet::new();
+                seen.insert(name);
+                let mut target = target.as_str();
+                loop {
+                    check_name(schema, target, pos)?;
+                    if !seen.insert(target) {
+                        return pos.error(format!("type `{}` is an alias of itself", name));
+                    }
+                    match schema.get(target) {
+                        Some(Type::Copy(next)) => target = next,
+                        _ => break,
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn parse(text: &str) -> Result<Schema, ParseError> {
+    let tokens = tokenize(text)?;
+    let end = Pos {
+        line: text.lines().count().max(1),
+        column: text
+            .lines()
+            .last()
+            .map(|l| l.chars().count() + 1)
+            .unwrap_or(1),
+    };
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        end,
+    };
+    let (schema, positions) = parser.schema()?;
+    check(&schema, &positions)?;
+    Ok(schema)
+}
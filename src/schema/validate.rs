@@ -0,0 +1,455 @@
+//! Validation of ipld against a schema.
+use super::ast::*;
+use super::{Schema, ValidationError};
+use crate::ipld::Ipld;
+use std::collections::BTreeMap;
+
+/// Returns the data model kind of `ipld`.
+fn kind(ipld: &Ipld) -> &'static str {
+    match ipld {
+        Ipld::Null => "null",
+        Ipld::Bool(_) => "bool",
+        Ipld::Integer(_) => "int",
+        Ipld::Float(_) => "float",
+        Ipld::String(_) => "string",
+        Ipld::Bytes(_) => "bytes",
+        Ipld::List(_) => "list",
+        Ipld::StringMap(_) => "map",
+        #[cfg(feature = "unleashed")]
+        Ipld::IntegerMap(_) => "map",
+        Ipld::Link(_) => "link",
+        #[cfg(feature = "unleashed")]
+        Ipld::Tag(_, _) => "tag",
+    }
+}
+
+/// Decodes a hex string checked by the parser.
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+type Result<T = ()> = std::result::Result<T, ValidationError>;
+
+pub struct Validator<'a> {
+    schema: &'a Schema,
+    path: Vec<String>,
+}
+
+impl<'a> Validator<'a> {
+    pub fn new(schema: &'a Schema) -> Self {
+        Self {
+            schema,
+            path: Vec::new(),
+        }
+    }
+
+    fn error<T>(&self, msg: impl Into<String>) -> Result<T> {
+        Err(ValidationError {
+            path: self.path.clone().into(),
+            msg: msg.into(),
+        })
+    }
+
+    fn mismatch<T>(&self, expected: &str, ipld: &Ipld) -> Result<T> {
+        self.error(format!("expected {} but found {}", expected, kind(ipld)))
+    }
+
+    /// Validates `ipld` with `segment` appended to the path.
+    fn nested(&mut self, segment: impl ToString, f: impl FnOnce(&mut Self) -> Result) -> Result {
+        self.path.push(segment.to_string());
+        f(self)?;
+        self.path.pop();
+        Ok(())
+    }
+
+    pub fn validate_named(&mut self, ipld: &Ipld, name: &str) -> Result {
+        match self.schema.get(name) {
+            Some(ty) => self.validate_type(ipld, ty),
+            None => self.error(format!("unknown type `{}`", name)),
+        }
+    }
+
+    fn validate_expr(&mut self, ipld: &Ipld, expr: &TypeExpr) -> Result {
+        match expr {
+            TypeExpr::Named(name) => self.validate_named(ipld, name),
+            TypeExpr::Link(_) => self.validate_link(ipld),
+            TypeExpr::Map(map) => self.validate_map(ipld, map),
+            TypeExpr::List(list) => self.validate_list(ipld, list),
+        }
+    }
+
+    fn validate_value(&mut self, ipld: &Ipld, expr: &TypeExpr, nullable: bool) -> Result {
+        if nullable && *ipld == Ipld::Null {
+            return Ok(());
+        }
+        self.validate_expr(ipld, expr)
+    }
+
+    fn validate_type(&mut self, ipld: &Ipld, ty: &Type) -> Result {
+        match (ty, ipld) {
+            (Type::Bool, Ipld::Bool(_)) => Ok(()),
+            (Type::Bool, _) => self.mismatch("bool", ipld),
+            (Type::String, Ipld::String(_)) => Ok(()),
+            (Type::String, _) => self.mismatch("string", ipld),
+            (Type::Bytes, Ipld::Bytes(_)) => Ok(()),
+            (Type::Bytes, _) => self.mismatch("bytes", ipld),
+            (Type::Int, Ipld::Integer(_)) => Ok(()),
+            (Type::Int, _) => self.mismatch("int", ipld),
+            (Type::Float, Ipld::Float(_)) => Ok(()),
+            (Type::Float, _) => self.mismatch("float", ipld),
+            (Type::Any, _) => Ok(()),
+            (Type::Link(_), _) => self.validate_link(ipld),
+            (Type::Map(map), _) => self.validate_map(ipld, map),
+            (Type::List(list), _) => self.validate_list(ipld, list),
+            (Type::Struct(s), _) => self.validate_struct(ipld, s),
+            (Type::Union(u), _) => self.validate_union(ipld, u),
+            (Type::Enum(e), _) => self.validate_enum(ipld, e),
+            (Type::Unit(repr), _) => self.validate_unit(ipld, *repr),
+            (Type::Copy(name), _) => self.validate_named(ipld, name),
+        }
+    }
+
+    fn validate_link(&mut self, ipld: &Ipld) -> Result {
+        // the linked block isn't loaded, so its type can't be checked
+        match ipld {
+            Ipld::Link(_) => Ok(()),
+            _ => self.mismatch("link", ipld),
+        }
+    }
+
+    fn validate_map(&mut self, ipld: &Ipld, map: &MapType) -> Result {
+        match (&map.repr, ipld) {
+            (MapRepr::Map, Ipld::StringMap(m)) => {
+                for (key, value) in m {
+                    self.nested(key, |v| {
+                        v.validate_named(&Ipld::String(key.clone()), &map.key)?;
+                        v.validate_value(value, &map.value, map.nullable)
+                    })?;
+                }
+                Ok(())
+            }
+            #[cfg(feature = "unleashed")]
+            (MapRepr::Map, Ipld::IntegerMap(m)) => {
+                for (key, value) in m {
+                    self.nested(key, |v| {
+                        v.validate_named(&Ipld::Integer(*key as i128), &map.key)?;
+                        v.validate_value(value, &map.value, map.nullable)
+                    })?;
+                }
+                Ok(())
+            }
+            (MapRepr::Map, _) => self.mismatch("map", ipld),
+            (
+                MapRepr::StringPairs {
+                    inner_delim,
+                    entry_delim,
+                },
+                Ipld::String(s),
+            ) => {
+                for (key, value) in self.string_pairs(s, inner_delim, entry_delim)? {
+                    self.nested(&key, |v| {
+                        v.validate_named(&Ipld::String(key.clone()), &map.key)?;
+                        v.validate_expr(&Ipld::String(value), &map.value)
+                    })?;
+                }
+                Ok(())
+            }
+            (MapRepr::StringPairs { .. }, _) => self.mismatch("string", ipld),
+            (MapRepr::ListPairs, Ipld::List(l)) => {
+                for (i, pair) in l.iter().enumerate() {
+                    self.nested(i, |v| {
+                        let (key, value) = v.list_pair(pair)?;
+                        v.validate_named(key, &map.key)?;
+                        v.validate_value(value, &map.value, map.nullable)
+                    })?;
+                }
+                Ok(())
+            }
+            (MapRepr::ListPairs, _) => self.mismatch("list", ipld),
+        }
+    }
+
+    fn validate_list(&mut self, ipld: &Ipld, list: &ListType) -> Result {
+        match ipld {
+            Ipld::List(l) => {
+                for (i, value) in l.iter().enumerate() {
+                    self.nested(i, |v| v.validate_value(value, &list.value, list.nullable))?;
+                }
+                Ok(())
+            }
+            _ => self.mismatch("list", ipld),
+        }
+    }
+
+    fn validate_struct(&mut self, ipld: &Ipld, s: &StructType) -> Result {
+        match (&s.repr, ipld) {
+            (StructRepr::Map, Ipld::StringMap(m)) => {
+                self.validate_fields(s, m.iter().map(|(k, v)| (k.as_str(), v)))
+            }
+            (StructRepr::Map, _) => self.mismatch("map", ipld),
+            (StructRepr::Tuple { field_order }, Ipld::List(l)) => {
+                let fields: Vec<&StructField> = match field_order {
+                    Some(order) => order
+                        .iter()
+                        .filter_map(|name| s.fields.iter().find(|field| &field.name == name))
+                        .collect(),
+                    None => s.fields.iter().collect(),
+                };
+                let required = fields.iter().rposition(|field| !field.optional);
+                let required = required.map(|i| i + 1).unwrap_or_default();
+                if l.len() < required || l.len() > fields.len() {
+                    return self.error(format!(
+                        "expected {} values but found {}",
+                        fields.len(),
+                        l.len()
+                    ));
+                }
+                for (i, (field, value)) in fields.iter().zip(l).enumerate() {
+                    self.nested(i, |v| v.validate_value(value, &field.ty, field.nullable))?;
+                }
+                Ok(())
+            }
+            (StructRepr::Tuple { .. }, _) => self.mismatch("list", ipld),
+            (StructRepr::StringJoin { join }, Ipld::String(string)) => {
+                let values: Vec<&str> = string.split(join.as_str()).collect();
+                if values.len() != s.fields.len() {
+                    return self.error(format!(
+                        "expected {} values but found {}",
+                        s.fields.len(),
+                        values.len()
+                    ));
+                }
+                for (field, value) in s.fields.iter().zip(values) {
+                    self.nested(&field.name, |v| {
+                        v.validate_expr(&Ipld::String(value.to_string()), &field.ty)
+                    })?;
+                }
+                Ok(())
+            }
+            (StructRepr::StringJoin { .. }, _) => self.mismatch("string", ipld),
+            (
+                StructRepr::StringPairs {
+                    inner_delim,
+                    entry_delim,
+                },
+                Ipld::String(string),
+            ) => {
+                let pairs: Vec<(String, Ipld)> = self
+                    .string_pairs(string, inner_delim, entry_delim)?
+                    .into_iter()
+                    .map(|(key, value)| (key, Ipld::String(value)))
+                    .collect();
+                self.validate_fields(s, pairs.iter().map(|(k, v)| (k.as_str(), v)))
+            }
+            (StructRepr::StringPairs { .. }, _) => self.mismatch("string", ipld),
+            (StructRepr::ListPairs, Ipld::List(l)) => {
+                let mut pairs = Vec::with_capacity(l.len());
+                for (i, pair) in l.iter().enumerate() {
+                    self.path.push(i.to_string());
+                    let pair = self.list_pair(pair).and_then(|(key, value)| match key {
+                        Ipld::String(key) => Ok((key.as_str(), value)),
+                        _ => self.mismatch("string", key),
+                    });
+                    self.path.pop();
+                    pairs.push(pair?);
+                }
+                self.validate_fields(s, pairs.into_iter())
+            }
+            (StructRepr::ListPairs, _) => self.mismatch("list", ipld),
+        }
+    }
+
+    /// Validates the fields of a struct represented as key value pairs.
+    fn validate_fields<'b>(
+        &mut self,
+        s: &StructType,
+        entries: impl Iterator<Item = (&'b str, &'b Ipld)>,
+    ) -> Result {
+        let mut present = BTreeMap::new();
+        for (key, value) in entries {
+            match s.fields.iter().position(|field| field.key() == key) {
+                Some(i) => {
+                    let field = &s.fields[i];
+                    self.nested(key, |v| v.validate_value(value, &field.ty, field.nullable))?;
+                    present.insert(i, ());
+                }
+                None => return self.error(format!("unexpected key `{}`", key)),
+            }
+        }
+        for (i, field) in s.fields.iter().enumerate() {
+            if !present.contains_key(&i) && !field.optional && field.implicit.is_none() {
+                return self.error(format!("missing key `{}`", field.key()));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_union(&mut self, ipld: &Ipld, u: &UnionType) -> Result {
+        let member = |discriminant: &str| {
+            u.members
+                .iter()
+                .find(|member| member.discriminant == discriminant)
+        };
+        match (&u.repr, ipld) {
+            (UnionRepr::Keyed, Ipld::StringMap(m)) => {
+                if m.len() != 1 {
+                    return self.error(format!("expected 1 key but found {}", m.len()));
+                }
+                let (key, value) = m.iter().next().unwrap();
+                match member(key) {
+                    Some(member) => self.nested(key, |v| v.validate_expr(value, &member.ty)),
+                    None => self.error(format!("unknown discriminant `{}`", key)),
+                }
+            }
+            (UnionRepr::Keyed, _) => self.mismatch("map", ipld),
+            (UnionRepr::Kinded, _) => match member(kind(ipld)) {
+                Some(member) => self.validate_expr(ipld, &member.ty),
+                None => {
+                    let kinds: Vec<_> = u.members.iter().map(|m| m.discriminant.as_str()).collect();
+                    self.mismatch(&kinds.join(" or "), ipld)
+                }
+            },
+            (
+                UnionRepr::Envelope {
+                    discriminant_key,
+                    content_key,
+                },
+                Ipld::StringMap(m),
+            ) => {
+                let member = self.discriminant(m, discriminant_key, member)?;
+                if let Some(key) = m
+                    .keys()
+                    .find(|key| *key != discriminant_key && *key != content_key)
+                {
+                    return self.error(format!("unexpected key `{}`", key));
+                }
+                match m.get(content_key) {
+                    Some(value) => self.nested(content_key, |v| v.validate_expr(value, &member.ty)),
+                    None => self.error(format!("missing key `{}`", content_key)),
+                }
+            }
+            (UnionRepr::Envelope { .. }, _) => self.mismatch("map", ipld),
+            (UnionRepr::Inline { discriminant_key }, Ipld::StringMap(m)) => {
+                let member = self.discriminant(m, discriminant_key, member)?;
+                let mut m = m.clone();
+                m.remove(discriminant_key);
+                self.validate_expr(&Ipld::StringMap(m), &member.ty)
+            }
+            (UnionRepr::Inline { .. }, _) => self.mismatch("map", ipld),
+            (UnionRepr::StringPrefix, Ipld::String(s)) => {
+                let member = u
+                    .members
+                    .iter()
+                    .find(|member| s.starts_with(member.discriminant.as_str()));
+                match member {
+                    Some(member) => {
+                        let rest = s[member.discriminant.len()..].to_string();
+                        self.validate_expr(&Ipld::String(rest), &member.ty)
+                    }
+                    None => self.error("unknown prefix"),
+                }
+            }
+            (UnionRepr::StringPrefix, _) => self.mismatch("string", ipld),
+            (UnionRepr::BytesPrefix, Ipld::Bytes(b)) => {
+                let member = u
+                    .members
+                    .iter()
+                    .map(|member| (member, hex(&member.discriminant)))
+                    .find(|(_, prefix)| b.starts_with(prefix));
+                match member {
+                    Some((member, prefix)) => {
+                        let rest = b[prefix.len()..].to_vec();
+                        self.validate_expr(&Ipld::Bytes(rest), &member.ty)
+                    }
+                    None => self.error("unknown prefix"),
+                }
+            }
+            (UnionRepr::BytesPrefix, _) => self.mismatch("bytes", ipld),
+        }
+    }
+
+    /// Returns the member of an envelope or inline union.
+    fn discriminant<'b>(
+        &self,
+        m: &BTreeMap<String, Ipld>,
+        key: &str,
+        member: impl Fn(&str) -> Option<&'b UnionMember>,
+    ) -> Result<&'b UnionMember> {
+        match m.get(key) {
+            Some(Ipld::String(discriminant)) => match member(discriminant) {
+                Some(member) => Ok(member),
+                None => self.error(format!("unknown discriminant `{}`", discriminant)),
+            },
+            Some(value) => {
+                let mut path = self.path.clone();
+                path.push(key.to_string());
+                Err(ValidationError {
+                    path: path.into(),
+                    msg: format!("expected string but found {}", kind(value)),
+                })
+            }
+            None => self.error(format!("missing key `{}`", key)),
+        }
+    }
+
+    fn validate_enum(&mut self, ipld: &Ipld, e: &EnumType) -> Result {
+        let found = match (e.repr, ipld) {
+            (EnumRepr::String, Ipld::String(s)) => {
+                e.members.iter().any(|member| member.value() == s)
+            }
+            (EnumRepr::String, _) => return self.mismatch("string", ipld),
+            (EnumRepr::Int, Ipld::Integer(i)) => e
+                .members
+                .iter()
+                .any(|member| member.value().parse::<i128>() == Ok(*i)),
+            (EnumRepr::Int, _) => return self.mismatch("int", ipld),
+        };
+        if !found {
+            return self.error(format!("unknown enum member {:?}", ipld));
+        }
+        Ok(())
+    }
+
+    fn validate_unit(&mut self, ipld: &Ipld, repr: UnitRepr) -> Result {
+        match (repr, ipld) {
+            (UnitRepr::Null, Ipld::Null) => Ok(()),
+            (UnitRepr::Null, _) => self.mismatch("null", ipld),
+            (UnitRepr::True, Ipld::Bool(true)) => Ok(()),
+            (UnitRepr::True, _) => self.error(format!("expected true but found {:?}", ipld)),
+            (UnitRepr::False, Ipld::Bool(false)) => Ok(()),
+            (UnitRepr::False, _) => self.error(format!("expected false but found {:?}", ipld)),
+            (UnitRepr::EmptyMap, Ipld::StringMap(m)) if m.is_empty() => Ok(()),
+            (UnitRepr::EmptyMap, _) => self.mismatch("empty map", ipld),
+        }
+    }
+
+    /// Splits a string of delimited key value pairs.
+    fn string_pairs(
+        &self,
+        s: &str,
+        inner_delim: &str,
+        entry_delim: &str,
+    ) -> Result<Vec<(String, String)>> {
+        if s.is_empty() {
+            return Ok(Vec::new());
+        }
+        s.split(entry_delim)
+            .map(|entry| match entry.split_once(inner_delim) {
+                Some((key, value)) => Ok((key.to_string(), value.to_string())),
+                None => self.error(format!("missing delimiter in {:?}", entry)),
+            })
+            .collect()
+    }
+
+    /// Returns the key and value of a list pair.
+    fn list_pair<'b>(&self, ipld: &'b Ipld) -> Result<(&'b Ipld, &'b Ipld)> {
+        match ipld {
+            Ipld::List(l) if l.len() == 2 => Ok((&l[0], &l[1])),
+            Ipld::List(l) => self.error(format!("expected 2 values but found {}", l.len())),
+            _ => self.mismatch("list", ipld),
+        }
+    }
+}
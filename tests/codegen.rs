@@ -0,0 +1,101 @@
+#![cfg(all(feature = "dag-cbor", feature = "derive"))]
+use libipld::cbor::{DagCborCodec, EncodingVersion};
+use libipld::codec::assert_roundtrip;
+use libipld::multihash::{Code as Hash, MultihashDigest};
+use libipld::schema::{codegen, Schema};
+use libipld::{ipld, Cid};
+
+fn cid() -> Cid {
+    Cid::new_v1(0x71, Hash::Sha2_256.digest(b"parent"))
+}
+
+mod schema {
+    include!("codegen/schema.rs");
+}
+use schema::*;
+
+#[test]
+fn generated_code_is_up_to_date() {
+    let schema = Schema::parse(include_str!("codegen/schema.ipldsch")).unwrap();
+    let code = codegen::generate(&schema).unwrap();
+    assert_eq!(code, include_str!("codegen/schema.rs"));
+}
+
+/// Encodes the signed integers of generated types like the `Ipld` they are compared with.
+const CONFORMANT: DagCborCodec = DagCborCodec.with_version(EncodingVersion::Conformant);
+
+#[test]
+fn roundtrip() {
+    let leaf = Node {
+        name: "leaf".into(),
+        parent_node: Some(cid().into()),
+        kind: Kind::Leaf,
+        tags: vec![("a".to_string(), Some(1)), ("b".to_string(), None)]
+            .into_iter()
+            .collect(),
+        children: vec![],
+    };
+    assert_roundtrip(
+        CONFORMANT,
+        &leaf,
+        &ipld!({
+            "name": "leaf",
+            "parentNode": cid(),
+            "tags": { "a": 1, "b": null },
+            "children": [],
+        }),
+    );
+    let branch = Node {
+        name: "branch".into(),
+        parent_node: None,
+        kind: Kind::Branch,
+        tags: Default::default(),
+        children: vec![leaf],
+    };
+    assert_roundtrip(
+        CONFORMANT,
+        &Message::Node(branch.clone()),
+        &ipld!({
+            "node": {
+                "name": "branch",
+                "kind": "Branch",
+                "tags": {},
+                "children": [{
+                    "name": "leaf",
+                    "parentNode": cid(),
+                    "tags": { "a": 1, "b": null },
+                    "children": [],
+                }],
+            },
+        }),
+    );
+    assert_roundtrip(CONFORMANT, &Message::Code(Code::Err), &ipld!({ "code": 1 }));
+    assert_roundtrip(CONFORMANT, &Message::Link(cid()), &ipld!({ "link": cid() }));
+    assert_roundtrip(CONFORMANT, &Point { x: 1, y: 2 }, &ipld!([1, 2]));
+    assert_roundtrip(CONFORMANT, &Value::Int(1), &ipld!(1));
+    assert_roundtrip(CONFORMANT, &Value::String("a".into()), &ipld!("a"));
+    assert_roundtrip(
+        CONFORMANT,
+        &Value::Point(Point { x: 1, y: 2 }),
+        &ipld!([1, 2]),
+    );
+    assert_roundtrip(CONFORMANT, &Nothing, &ipld!(null));
+}
+
+#[test]
+fn generated_types_match_schema() {
+    let schema = Schema::parse(include_str!("codegen/schema.ipldsch")).unwrap();
+    let node = Node {
+        name: "node".into(),
+        parent_node: None,
+        kind: Kind::Branch,
+        tags: Default::default(),
+        children: vec![],
+    };
+    let ipld = libipld::codec::Codec::decode(
+        &DagCborCodec,
+        &libipld::codec::Codec::encode(&DagCborCodec, &node).unwrap(),
+    )
+    .unwrap();
+    schema.validate(&ipld, "Node").unwrap();
+}
@@ -0,0 +1,71 @@
+// Generated from an IPLD schema, do not edit.
+
+pub type Name = String;
+
+pub type Tags = std::collections::BTreeMap<String, Option<i64>>;
+
+pub type Parent = libipld::Link<Node>;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, libipld::DagCbor)]
+#[ipld(repr = "string")]
+pub enum Kind {
+    #[ipld(rename = "leaf")]
+    Leaf,
+    Branch,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, libipld::DagCbor)]
+#[ipld(repr = "int")]
+pub enum Code {
+    Ok = 0,
+    Err = 1,
+}
+
+#[derive(Clone, Debug, PartialEq, libipld::DagCbor)]
+#[ipld(repr = "map")]
+pub struct Node {
+    pub name: Name,
+    #[ipld(rename = "parentNode")]
+    #[ipld(default = None)]
+    pub parent_node: Option<Parent>,
+    #[ipld(default = Kind::Leaf)]
+    pub kind: Kind,
+    pub tags: Tags,
+    pub children: Vec<Node>,
+}
+
+#[derive(Clone, Debug, PartialEq, libipld::DagCbor)]
+#[ipld(repr = "tuple")]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, libipld::DagCbor)]
+#[ipld(repr = "kinded")]
+pub enum Value {
+    #[ipld(repr = "value")]
+    Int(i64),
+    #[ipld(repr = "value")]
+    String(String),
+    #[ipld(repr = "value")]
+    Point(Point),
+}
+
+#[derive(Clone, Debug, PartialEq, libipld::DagCbor)]
+#[ipld(repr = "keyed")]
+pub enum Message {
+    #[ipld(rename = "node")]
+    #[ipld(repr = "value")]
+    Node(Node),
+    #[ipld(rename = "code")]
+    #[ipld(repr = "value")]
+    Code(Code),
+    #[ipld(rename = "link")]
+    #[ipld(repr = "value")]
+    Link(libipld::Cid),
+}
+
+#[derive(Clone, Debug, PartialEq, libipld::DagCbor)]
+#[ipld(repr = "null")]
+pub struct Nothing;